@@ -1,9 +1,106 @@
-mv log backup_log
+http_address=${LEETCODE_DAILY_HTTP_ADDRESS:-`awk '/^\[/ { section = $0 } section == "[http]" && $1 == "address" { gsub(/"/, "", $3); print $3 }' config.toml 2> /dev/null`}
+if [ -z "$http_address" ]
+then
+	echo The health check is served over HTTP, set http.address in config.toml or LEETCODE_DAILY_HTTP_ADDRESS
+	exit 1
+fi
+shutdown_timeout=${SHUTDOWN_TIMEOUT:-30}
+flush_timeout=${FLUSH_TIMEOUT:-60}
+status=0
+# Polls the health check for up to a minute
+healthy() {
+	for attempt in `seq 60`
+	do
+		if curl -sf http://$http_address/healthz > /dev/null
+		then
+			return
+		fi
+		sleep 1
+	done
+	curl -s http://$http_address/healthz
+	return 1
+}
+# Waits for a process to exit after SIGTERM. Past the timeout it is only killed early once its log
+# shows the database was flushed, and fails once it hasn't exited after the flush timeout either
+stop() {
+	lines=`cat $2 2> /dev/null | wc -l`
+	kill $1
+	for attempt in `seq $shutdown_timeout`
+	do
+		if ! kill -0 $1 2> /dev/null
+		then
+			return
+		fi
+		sleep 1
+	done
+	echo $1 did not exit after $shutdown_timeout seconds, waiting up to $flush_timeout seconds for it to flush the database
+	for attempt in `seq $flush_timeout`
+	do
+		if ! kill -0 $1 2> /dev/null
+		then
+			return
+		fi
+		# A log that shrank was rotated, so all of it is new
+		if [ `cat $2 | wc -l` -lt $lines ]
+		then
+			lines=0
+		fi
+		if tail -n +$((lines + 1)) $2 | grep -q "Database flushed"
+		then
+			echo Killing $1
+			kill -9 $1
+			sleep 1
+			return
+		fi
+		sleep 1
+	done
+	echo $1 did not flush the database after $flush_timeout more seconds, killing it anyway
+	kill -9 $1
+	sleep 1
+	return 1
+}
+# The new build only loads the config and database here, it never connects to the chat platform
+if ! target/release/leetcode_daily --check
+then
+	echo New build could not load the config or database, keeping the old process
+	exit 1
+fi
+# Deploys from before the pid file started the bot as target/release/leetcode_daily > log
+if [ -f leetcode_daily.pid ]
+then
+	pids=`cat leetcode_daily.pid`
+else
+	pids=`pgrep -f '^([^ ]*/)?(previous_)?leetcode_daily( |$)'`
+fi
+if [ -z "$pids" ]
+then
+	echo No running bot found, starting the first one
+fi
+for pid in $pids
+do
+	if kill -0 $pid 2> /dev/null
+	then
+		echo Stopping $pid
+		if ! stop $pid log
+		then
+			status=1
+		fi
+	fi
+done
 cp database.json backup_database.json
-pid=`pgrep leetcode_daily`
-target/release/leetcode_daily > log &
-if [[ $pid =~ ^[0-9]{5,}$ ]]
+target/release/leetcode_daily --log-output log >> crash_log 2>&1 &
+new_pid=$!
+echo $new_pid > leetcode_daily.pid
+if healthy
+then
+	echo New process is healthy
+	exit $status
+fi
+echo New process is not healthy, rolling back
+stop $new_pid log
+if [ -f previous_leetcode_daily ]
 then
-	echo Killing $pid
-	kill $pid
+	./previous_leetcode_daily --log-output log >> crash_log 2>&1 &
+	echo $! > leetcode_daily.pid
 fi
+exit 1
@@ -1,3 +1,4 @@
 git pull
+cp target/release/leetcode_daily previous_leetcode_daily 2> /dev/null
 cargo b --release
 sh scripts/restart.sh
@@ -0,0 +1,148 @@
+use crate::{config, get_user_from_id, Data, Guilds, Status};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    model::prelude::{GuildId, User, UserId},
+    utils::MessageBuilder,
+};
+
+pub const STREAK_DAYS: u32 = 30;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Badge {
+    FirstHard,
+    Streak,
+    ContestFullSolve,
+    TopVoted,
+    Comeback,
+    PerfectMonth,
+}
+
+impl Badge {
+    // Badges are referred to by their snake case name in commands, e.g. first_hard
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Badge::FirstHard => "First Hard",
+            Badge::Streak => "30 Day Streak",
+            Badge::ContestFullSolve => "Contest Full Solve",
+            Badge::TopVoted => "Top Voted",
+            Badge::Comeback => "Comeback",
+            Badge::PerfectMonth => "Perfect Month",
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Badge::FirstHard => String::from("completing a Hard daily question"),
+            Badge::Streak => format!("completing {STREAK_DAYS} daily questions in a row"),
+            Badge::ContestFullSolve => String::from("finishing every question of a weekly contest"),
+            Badge::TopVoted => String::from("getting the most votes for a daily submission"),
+            Badge::Comeback => format!(
+                "coming back after missing more than {} days",
+                config().scoring.comeback_days
+            ),
+            Badge::PerfectMonth => String::from("completing every daily question of a month"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EarnedBadge {
+    pub badge: Badge,
+    pub date: NaiveDate,
+}
+
+// Every badge can only be earned once, returns the ones that are new
+pub fn award(status: &mut Status, badges: &[Badge], date: NaiveDate) -> Vec<Badge> {
+    let mut awarded = Vec::new();
+    for &badge in badges {
+        if !status.badges.iter().any(|earned| earned.badge == badge) {
+            status.badges.push(EarnedBadge { badge, date });
+            awarded.push(badge);
+        }
+    }
+    awarded
+}
+
+pub fn construct_awarded_message<'a>(
+    message: &'a mut MessageBuilder,
+    awarded: &[Badge],
+) -> &'a mut MessageBuilder {
+    for badge in awarded {
+        message
+            .push("🏅 You earned the ")
+            .push_bold(badge.name())
+            .push_line(format!(" badge for {}!", badge.description()));
+    }
+    message
+}
+
+// Evaluated at the end of each day after the votes are counted
+pub fn award_top_voted(
+    guilds: &mut Guilds,
+    guild_id: &GuildId,
+    data: &mut Data,
+    winners: &[UserId],
+    date: NaiveDate,
+    message: &mut MessageBuilder,
+) {
+    for user_id in winners {
+        let Some(status) = data.users.get_mut(user_id) else {
+            continue;
+        };
+        for badge in award(status, &[Badge::TopVoted], date) {
+            let user = get_user_from_id!(guilds, guild_id, user_id);
+            message
+                .push("🏅 ")
+                .mention(user)
+                .push(" earned the ")
+                .push_bold(badge.name())
+                .push_line(format!(" badge for {}!", badge.description()));
+        }
+    }
+}
+
+pub fn construct_profile<'a>(
+    user: &User,
+    status: &Status,
+    message: &'a mut MessageBuilder,
+) -> &'a mut MessageBuilder {
+    message
+        .push_bold_line(user.name.clone())
+        .push_line(format!(
+            "Score: {}, all-time: {}",
+            status.score,
+            status.archived_score + status.score
+        ))
+        .push_line(format!(
+            "Completed this month: {}, current streak: {} {}",
+            status.monthly_record,
+            status.streak,
+            if status.streak == 1 { "day" } else { "days" }
+        ))
+        .push_line(format!(
+            "Weekly contest: {}/4 questions, votes received: {}",
+            status.weekly_submissions, status.votes
+        ));
+    if status.badges.is_empty() {
+        message.push("No badges yet");
+    } else {
+        message.push_line("Badges:");
+        for earned in &status.badges {
+            message
+                .push("🏅 ")
+                .push_bold(earned.badge.name())
+                .push_line(format!(
+                    " for {}, earned on {}",
+                    earned.badge.description(),
+                    earned.date.format("%d/%m/%Y")
+                ));
+        }
+    }
+    message
+}
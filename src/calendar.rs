@@ -0,0 +1,184 @@
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use std::error::Error;
+
+const NUM_MONTHS_IN_A_YEAR: u32 = 12;
+
+pub fn next_month(year: i32, month: u32) -> (i32, u32) {
+    if month >= NUM_MONTHS_IN_A_YEAR {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    }
+}
+
+pub fn first_of_month(year: i32, month: u32) -> Result<NaiveDate, Box<dyn Error>> {
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| format!("Invalid month {year}-{month}").into())
+}
+
+pub fn days_in_month(year: i32, month: u32) -> Result<u32, Box<dyn Error>> {
+    let first = first_of_month(year, month)?;
+    Ok(month_end(first, &Utc)?
+        .signed_duration_since(month_start(first, &Utc)?)
+        .num_days()
+        .try_into()?)
+}
+
+// Midnight can be skipped by a DST change, in which case the day starts an hour later
+fn start_of_day<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> Result<DateTime<Tz>, Box<dyn Error>> {
+    let midnight = NaiveDateTime::from(date);
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(midnight + TimeDelta::hours(1)))
+                .earliest()
+        })
+        .ok_or_else(|| format!("{date} has no start in this timezone").into())
+}
+
+pub fn month_start<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> Result<DateTime<Tz>, Box<dyn Error>> {
+    start_of_day(first_of_month(date.year(), date.month())?, tz)
+}
+
+// Exclusive, this is the first instant of the following month
+pub fn month_end<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> Result<DateTime<Tz>, Box<dyn Error>> {
+    let (year, month) = next_month(date.year(), date.month());
+    start_of_day(first_of_month(year, month)?, tz)
+}
+
+pub fn iso_week(date: NaiveDate) -> (i32, u32) {
+    let week = date.iso_week();
+    (week.year(), week.week())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, LocalResult, NaiveTime};
+
+    // Clocks go forward at midnight on 1 April, skipping it, and back an hour after midnight on
+    // 1 October, so midnight happens twice
+    #[derive(Clone, Copy, Debug)]
+    struct Zone;
+
+    impl Zone {
+        fn standard() -> FixedOffset {
+            FixedOffset::west_opt(4 * 3600).unwrap()
+        }
+
+        fn summer() -> FixedOffset {
+            FixedOffset::west_opt(3 * 3600).unwrap()
+        }
+    }
+
+    impl TimeZone for Zone {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Zone
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&NaiveDateTime::from(*local))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let offsets = [Zone::summer(), Zone::standard()]
+                .into_iter()
+                .filter(|&offset| self.offset_from_utc_datetime(&(*local - offset)) == offset)
+                .collect::<Vec<_>>();
+            match offsets[..] {
+                [] => LocalResult::None,
+                [offset] => LocalResult::Single(offset),
+                [earliest, latest] => LocalResult::Ambiguous(earliest, latest),
+                _ => unreachable!(),
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&NaiveDateTime::from(*utc))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let spring = datetime(2026, 4, 1, 4);
+            let autumn = datetime(2026, 10, 1, 4);
+            if (spring..autumn).contains(utc) {
+                Zone::summer()
+            } else {
+                Zone::standard()
+            }
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn datetime(year: i32, month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        date(year, month, day).and_time(NaiveTime::from_hms_opt(hour, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn days_in_month_knows_every_month() {
+        let days = (1..=12)
+            .map(|month| days_in_month(2026, month).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(days, [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]);
+        assert!(days_in_month(2026, 13).is_err());
+    }
+
+    #[test]
+    fn february_depends_on_leap_years() {
+        assert_eq!(days_in_month(2028, 2).unwrap(), 29);
+        assert_eq!(days_in_month(2026, 2).unwrap(), 28);
+        // Centuries are only leap years when divisible by 400
+        assert_eq!(days_in_month(2100, 2).unwrap(), 28);
+        assert_eq!(days_in_month(2000, 2).unwrap(), 29);
+    }
+
+    #[test]
+    fn december_is_followed_by_january() {
+        assert_eq!(next_month(2026, 12), (2027, 1));
+        assert_eq!(next_month(2026, 11), (2026, 12));
+        assert_eq!(days_in_month(2026, 12).unwrap(), 31);
+        assert_eq!(
+            month_end(date(2026, 12, 31), &Utc).unwrap(),
+            Utc.from_utc_datetime(&datetime(2027, 1, 1, 0))
+        );
+        assert_eq!(
+            month_start(date(2027, 1, 15), &Utc).unwrap(),
+            Utc.from_utc_datetime(&datetime(2027, 1, 1, 0))
+        );
+    }
+
+    #[test]
+    fn iso_weeks_can_belong_to_another_year() {
+        // 1 January 2027 is a Friday so it is still in the last week of 2026
+        assert_eq!(iso_week(date(2027, 1, 1)), (2026, 53));
+        assert_eq!(iso_week(date(2027, 1, 4)), (2027, 1));
+        // 29 December 2025 is a Monday so it starts the first week of 2026
+        assert_eq!(iso_week(date(2025, 12, 29)), (2026, 1));
+        assert_eq!(iso_week(date(2025, 12, 28)), (2025, 52));
+    }
+
+    #[test]
+    fn months_start_after_a_skipped_midnight() {
+        let start = month_start(date(2026, 4, 20), &Zone).unwrap();
+        assert_eq!(start.naive_local(), datetime(2026, 4, 1, 1));
+        assert_eq!(start.naive_utc(), datetime(2026, 4, 1, 4));
+        assert_eq!(month_end(date(2026, 3, 31), &Zone).unwrap(), start);
+    }
+
+    #[test]
+    fn months_start_at_the_first_of_a_repeated_midnight() {
+        let start = month_start(date(2026, 10, 20), &Zone).unwrap();
+        assert_eq!(start.naive_local(), datetime(2026, 10, 1, 0));
+        assert_eq!(start.naive_utc(), datetime(2026, 10, 1, 3));
+        assert_eq!(month_end(date(2026, 9, 1), &Zone).unwrap(), start);
+        // Both ends are in standard time for the rest of the year
+        assert_eq!(
+            month_end(date(2026, 11, 1), &Zone).unwrap().naive_utc(),
+            datetime(2026, 12, 1, 4)
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use clap::{Args, Parser, Subcommand};
+use leetcode_daily::{
+    config, daily_question_embed, import_database, merge_guilds, problem_embed,
+    random_question_embed, read_database, set_score, show_database, validate_database,
+    write_database, Backend, Config, Database, Embed, Format, Level, Platform, Site,
+};
+use serde_json::json;
+use serenity::model::prelude::{GuildId, UserId};
+use std::{error::Error, fs::write, net::SocketAddr, path::Path, path::PathBuf};
+
+const DEFAULT_CONFIG: &str = "config.toml";
+
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Path to the TOML config file, defaults to config.toml if it exists
+    #[arg(short, long, env = "LEETCODE_DAILY_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Chat platform to connect to
+    #[arg(long, env = "LEETCODE_DAILY_PLATFORM")]
+    pub platform: Option<Platform>,
+    /// Discord bot token
+    #[arg(long, env = "DISCORD_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+    /// Slack app-level token used to open the Socket Mode connection
+    #[arg(long, env = "SLACK_APP_TOKEN", hide_env_values = true)]
+    pub slack_app_token: Option<String>,
+    /// Slack bot token used to call the Web API
+    #[arg(long, env = "SLACK_BOT_TOKEN", hide_env_values = true)]
+    pub slack_bot_token: Option<String>,
+    /// URL of the Matrix homeserver, e.g. https://matrix.org
+    #[arg(long, env = "MATRIX_HOMESERVER")]
+    pub matrix_homeserver: Option<String>,
+    /// Access token of the Matrix bot account
+    #[arg(long, env = "MATRIX_ACCESS_TOKEN", hide_env_values = true)]
+    pub matrix_access_token: Option<String>,
+    /// Telegram bot token from BotFather
+    #[arg(long, env = "TELEGRAM_BOT_TOKEN", hide_env_values = true)]
+    pub telegram_token: Option<String>,
+    /// Path to the database file
+    #[arg(long, env = "LEETCODE_DAILY_DATABASE")]
+    pub database: Option<PathBuf>,
+    /// Storage format of the database
+    #[arg(long, env = "LEETCODE_DAILY_BACKEND")]
+    pub backend: Option<Backend>,
+    /// Minimum level of messages to log
+    #[arg(long, env = "LEETCODE_DAILY_LOG_LEVEL")]
+    pub log_level: Option<Level>,
+    /// Format of the log lines
+    #[arg(long, env = "LEETCODE_DAILY_LOG_FORMAT")]
+    pub log_format: Option<Format>,
+    /// File to append logs to instead of stdout, rotated once it grows too big
+    #[arg(long, env = "LEETCODE_DAILY_LOG_OUTPUT")]
+    pub log_output: Option<PathBuf>,
+    /// Base URL of LeetCode, e.g. a mirror or a test server
+    #[arg(long, env = "LEETCODE_DAILY_LEETCODE_URL")]
+    pub leetcode_url: Option<String>,
+    /// Directory of saved LeetCode responses to read instead of calling the API
+    #[arg(long, env = "LEETCODE_DAILY_LEETCODE_FIXTURES")]
+    pub leetcode_fixtures: Option<PathBuf>,
+    /// Address to serve metrics, health checks and the admin API on
+    #[arg(long, env = "LEETCODE_DAILY_HTTP_ADDRESS")]
+    pub http_address: Option<SocketAddr>,
+    /// Bearer token required by the admin API, which is disabled if not set
+    #[arg(long, env = "LEETCODE_DAILY_ADMIN_TOKEN", hide_env_values = true)]
+    pub admin_token: Option<String>,
+    /// Comma separated IDs of users allowed to run admin commands, as the platform shows them
+    #[arg(long, env = "LEETCODE_DAILY_ADMINS", value_delimiter = ',')]
+    pub admins: Vec<String>,
+    /// Load the config and database then exit without connecting, e.g. to try out a new build
+    #[arg(long)]
+    pub check: bool,
+}
+
+impl Cli {
+    pub fn load_config(&self) -> Result<Config, Box<dyn Error>> {
+        let mut config = match &self.config {
+            Some(path) => Config::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG).exists() => {
+                Config::from_file(Path::new(DEFAULT_CONFIG))?
+            }
+            None => Config::default(),
+        };
+        if let Some(platform) = self.platform {
+            config.platform = platform;
+        }
+        if let Some(token) = &self.slack_app_token {
+            config.slack.app_token = Some(token.clone());
+        }
+        if let Some(token) = &self.slack_bot_token {
+            config.slack.bot_token = Some(token.clone());
+        }
+        if let Some(homeserver) = &self.matrix_homeserver {
+            config.matrix.homeserver = Some(homeserver.clone());
+        }
+        if let Some(token) = &self.matrix_access_token {
+            config.matrix.access_token = Some(token.clone());
+        }
+        if let Some(token) = &self.telegram_token {
+            config.telegram.token = Some(token.clone());
+        }
+        if let Some(database) = &self.database {
+            config.database.path = database.clone();
+        }
+        if let Some(backend) = self.backend {
+            config.database.backend = backend;
+        }
+        if let Some(level) = self.log_level {
+            config.log.level = level;
+        }
+        if let Some(format) = self.log_format {
+            config.log.format = format;
+        }
+        if let Some(output) = &self.log_output {
+            config.log.output = Some(output.clone());
+        }
+        if let Some(url) = &self.leetcode_url {
+            config.leetcode.url = url.trim_end_matches('/').to_string();
+        }
+        if let Some(fixtures) = &self.leetcode_fixtures {
+            config.leetcode.fixtures = Some(fixtures.clone());
+        }
+        if let Some(address) = self.http_address {
+            config.http.address = Some(address);
+        }
+        if let Some(token) = &self.admin_token {
+            config.http.admin_token = Some(token.clone());
+        }
+        if !self.admins.is_empty() {
+            config.admins = self.admins.clone();
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Print today's daily question
+    Daily(Output),
+    /// Print a random question
+    Random {
+        /// Comma separated filters, e.g. medium,!paid
+        #[arg(long, value_delimiter = ',')]
+        filter: Vec<String>,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Print a question by its number or slug
+    Problem {
+        id: String,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Inspect and repair the database, only while the bot is stopped
+    #[command(subcommand)]
+    Db(DbCommand),
+}
+
+#[derive(Subcommand)]
+pub enum DbCommand {
+    /// Print the whole database, a guild or a user as JSON
+    Show {
+        guild_id: Option<GuildId>,
+        user_id: Option<UserId>,
+    },
+    /// Set the score of a user
+    SetScore {
+        guild_id: GuildId,
+        user_id: UserId,
+        score: usize,
+    },
+    /// Move every user of a guild into another, adding up the scores of users in both
+    MergeGuilds { from: GuildId, into: GuildId },
+    /// Check that the data is consistent, e.g. votes are for users who submitted
+    Validate,
+    /// Write the database or a single guild to a file, or stdout if none is given
+    Export {
+        #[arg(long)]
+        guild_id: Option<GuildId>,
+        output: Option<PathBuf>,
+    },
+    /// Add the guilds from an exported file, replacing the ones with the same ID
+    Import { input: PathBuf },
+}
+
+#[derive(Args)]
+pub struct Output {
+    /// LeetCode site to query
+    #[arg(long, default_value = "com")]
+    site: Site,
+    /// Print the question as text or JSON
+    #[arg(long, default_value = "text")]
+    format: Format,
+}
+
+impl Output {
+    fn print(&self, embed: &Embed) -> Result<(), Box<dyn Error>> {
+        match self.format {
+            Format::Text => {
+                println!("{}\n{}", embed.title, embed.url);
+                for (name, value) in &embed.fields {
+                    println!("{name}: {value}");
+                }
+            }
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&json!({
+                    "title": embed.title,
+                    "url": embed.url,
+                    "colour": format!("#{}", embed.colour.hex()),
+                    "fields": embed
+                        .fields
+                        .iter()
+                        .map(|(name, value)| json!({"name": name, "value": value}))
+                        .collect::<Vec<_>>(),
+                }))?
+            ),
+        }
+        Ok(())
+    }
+}
+
+impl Command {
+    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+        match self {
+            Command::Daily(output) => {
+                let (_, embed) = daily_question_embed(output.site).await?;
+                output.print(&embed)
+            }
+            Command::Random { filter, output } => {
+                let filters = filter.iter().map(String::as_str).collect::<Vec<_>>();
+                let (_, embed) = random_question_embed(output.site, &filters)
+                    .await?
+                    .ok_or("No question found that fits your requirement")?;
+                output.print(&embed)
+            }
+            Command::Problem { id, output } => {
+                let embed = problem_embed(output.site, id)
+                    .await?
+                    .ok_or(format!("No question found with number or slug {id}"))?;
+                output.print(&embed)
+            }
+            Command::Db(command) => command.run(&config().database.path),
+        }
+    }
+}
+
+fn check(database: &Database) -> Result<(), Box<dyn Error>> {
+    let problems = validate_database(database)?;
+    for problem in &problems {
+        eprintln!("{problem}");
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Found {} problems in the database", problems.len()).into())
+    }
+}
+
+impl DbCommand {
+    fn run(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut database = read_database(path)?;
+        match self {
+            DbCommand::Show { guild_id, user_id } => println!(
+                "{}",
+                serde_json::to_string_pretty(&show_database(&database, *guild_id, *user_id)?)?
+            ),
+            DbCommand::SetScore {
+                guild_id,
+                user_id,
+                score,
+            } => {
+                let previous = set_score(&mut database, *guild_id, *user_id, *score)?;
+                let backup = write_database(path, &database)?;
+                println!("Set the score of {user_id} in {guild_id} from {previous} to {score}, the previous database is saved at {backup:?}");
+            }
+            DbCommand::MergeGuilds { from, into } => {
+                let users = merge_guilds(&mut database, *from, *into)?;
+                let backup = write_database(path, &database)?;
+                println!("Merged {users} users from {from} into {into}, the previous database is saved at {backup:?}");
+            }
+            DbCommand::Validate => {
+                check(&database)?;
+                println!("No problems found");
+            }
+            DbCommand::Export { guild_id, output } => {
+                if let Some(guild_id) = guild_id {
+                    database.retain(|id, _| id == guild_id);
+                    if database.is_empty() {
+                        return Err("Guild not found".into());
+                    }
+                }
+                let contents = serde_json::to_string_pretty(&database)?;
+                match output {
+                    Some(output) => write(output, contents)?,
+                    None => println!("{contents}"),
+                }
+            }
+            DbCommand::Import { input } => {
+                let imported = read_database(input)?;
+                check(&imported)?;
+                let guilds = import_database(&mut database, imported);
+                let backup = write_database(path, &database)?;
+                println!("Imported {guilds} guilds, the previous database is saved at {backup:?}");
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use serenity::async_trait;
+use std::{
+    error::Error,
+    future::pending,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+use tokio::sync::watch;
+
+static CLOCK: OnceLock<Arc<dyn Clock>> = OnceLock::new();
+
+#[async_trait]
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    async fn sleep(&self, duration: Duration);
+}
+
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+// Time only moves when advanced, sleepers wake once it passes their deadline
+pub struct SimulatedClock {
+    now: watch::Sender<DateTime<Utc>>,
+}
+
+impl SimulatedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: watch::channel(now).0,
+        }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        self.now.send_replace(now);
+    }
+
+    pub fn advance(&self, duration: TimeDelta) {
+        self.now.send_modify(|now| *now += duration);
+    }
+
+    // Deadlines are fixed when going to sleep, so tests wait for this before moving time
+    pub fn sleepers(&self) -> usize {
+        self.now.receiver_count()
+    }
+}
+
+#[async_trait]
+impl Clock for SimulatedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.borrow()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        let Some(deadline) = TimeDelta::from_std(duration)
+            .ok()
+            .and_then(|duration| self.now().checked_add_signed(duration))
+        else {
+            return pending().await;
+        };
+        let _ = self.now.subscribe().wait_for(|&now| now >= deadline).await;
+    }
+}
+
+pub fn set_clock(clock: Arc<dyn Clock>) -> Result<(), Box<dyn Error>> {
+    CLOCK
+        .set(clock)
+        .map_err(|_| "Clock has already been set".into())
+}
+
+pub fn clock() -> &'static dyn Clock {
+    CLOCK.get_or_init(|| Arc::new(SystemClock)).as_ref()
+}
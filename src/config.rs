@@ -0,0 +1,337 @@
+use crate::Site;
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use serde::Deserialize;
+use std::{
+    cmp::Reverse, error::Error, fs::read_to_string, net::SocketAddr, path::Path, path::PathBuf,
+    sync::OnceLock,
+};
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, PartialOrd, Debug, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, Debug, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, Debug, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Json,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Debug, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    #[default]
+    Discord,
+    Slack,
+    Matrix,
+    Telegram,
+    Webhook,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookKind {
+    #[default]
+    Discord,
+    Slack,
+    Json,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub path: PathBuf,
+    pub backend: Backend,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("database.json"),
+            backend: Backend::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub level: Level,
+    pub format: Format,
+    pub local_time: bool,
+    pub output: Option<PathBuf>,
+    pub max_size: u64,
+    pub max_files: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: Level::default(),
+            format: Format::default(),
+            local_time: true,
+            output: None,
+            max_size: 10 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SubmissionReward {
+    pub hours_left: i64,
+    pub points: usize,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scoring {
+    pub submission: Vec<SubmissionReward>,
+    pub penalty: usize,
+    pub comeback_days: u32,
+    pub comeback_bonus: usize,
+    pub monthly_winner: usize,
+    pub perfect_month: usize,
+    pub contest_question: usize,
+    pub contest_podium: Vec<usize>,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self {
+            submission: [(23, 5), (21, 4), (16, 3), (8, 2), (0, 1)]
+                .into_iter()
+                .map(|(hours_left, points)| SubmissionReward { hours_left, points })
+                .collect(),
+            penalty: 1,
+            comeback_days: 7,
+            comeback_bonus: 5,
+            monthly_winner: 5,
+            perfect_month: 10,
+            contest_question: 1,
+            contest_podium: vec![4, 3, 2],
+        }
+    }
+}
+
+impl Scoring {
+    pub fn submission_points(&self, hours_left: i64) -> usize {
+        self.submission
+            .iter()
+            .find(|reward| hours_left >= reward.hours_left)
+            .map_or(0, |reward| reward.points)
+    }
+
+    pub fn contest_points(&self, place: usize) -> usize {
+        self.contest_podium
+            .get(place)
+            .copied()
+            .unwrap_or(self.contest_question)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Schedule {
+    pub daily: NaiveTime,
+    pub reminder_minutes: u32,
+    pub contest_day: Weekday,
+    pub contest_start: NaiveTime,
+    pub contest_minutes: i64,
+    pub season_months: u32,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            daily: NaiveTime::from_hms_opt(0, 1, 0).expect("Invalid time"),
+            reminder_minutes: 60,
+            contest_day: Weekday::Sun,
+            contest_start: NaiveTime::from_hms_opt(2, 30, 0).expect("Invalid time"),
+            contest_minutes: 90,
+            season_months: 0,
+        }
+    }
+}
+
+impl Schedule {
+    // Seasons line up with the start of the year, e.g. quarters when they are 3 months long
+    pub fn starts_season(&self, date: NaiveDate) -> bool {
+        self.season_months > 0
+            && date.day0() == 0
+            && date.month0().is_multiple_of(self.season_months)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct LeetCodeConfig {
+    pub url: String,
+    pub cn_url: String,
+    pub fixtures: Option<PathBuf>,
+}
+
+impl Default for LeetCodeConfig {
+    fn default() -> Self {
+        Self {
+            url: String::from("https://leetcode.com"),
+            cn_url: String::from("https://leetcode.cn"),
+            fixtures: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct LeaderboardConfig {
+    pub image: bool,
+    pub font: Option<PathBuf>,
+}
+
+impl Default for LeaderboardConfig {
+    fn default() -> Self {
+        Self {
+            image: true,
+            font: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    pub address: Option<SocketAddr>,
+    pub admin_token: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct SlackConfig {
+    pub app_token: Option<String>,
+    pub bot_token: Option<String>,
+    pub api_url: String,
+    pub ids: PathBuf,
+}
+
+impl Default for SlackConfig {
+    fn default() -> Self {
+        Self {
+            app_token: None,
+            bot_token: None,
+            api_url: String::from("https://slack.com/api"),
+            ids: PathBuf::from("slack_ids.json"),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct MatrixConfig {
+    pub homeserver: Option<String>,
+    pub access_token: Option<String>,
+    pub ids: PathBuf,
+}
+
+impl Default for MatrixConfig {
+    fn default() -> Self {
+        Self {
+            homeserver: None,
+            access_token: None,
+            ids: PathBuf::from("matrix_ids.json"),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelegramConfig {
+    pub token: Option<String>,
+    pub api_url: String,
+    pub ids: PathBuf,
+}
+
+impl Default for TelegramConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            api_url: String::from("https://api.telegram.org"),
+            ids: PathBuf::from("telegram_ids.json"),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    #[serde(default)]
+    pub kind: WebhookKind,
+    pub url: String,
+    #[serde(default)]
+    pub site: Site,
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub platform: Platform,
+    pub database: DatabaseConfig,
+    pub log: LogConfig,
+    pub scoring: Scoring,
+    pub schedule: Schedule,
+    pub leetcode: LeetCodeConfig,
+    pub leaderboard: LeaderboardConfig,
+    pub http: HttpConfig,
+    pub slack: SlackConfig,
+    pub matrix: MatrixConfig,
+    pub telegram: TelegramConfig,
+    pub webhooks: Vec<WebhookConfig>,
+    // IDs as the platform shows them, e.g. U024BE7LH on Slack or @alice:matrix.org on Matrix
+    pub admins: Vec<String>,
+}
+
+impl Config {
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut config = toml::from_str::<Self>(&read_to_string(path)?)?;
+        if ![0, 1, 2, 3, 4, 6, 12].contains(&config.schedule.season_months) {
+            return Err("schedule.season_months has to divide a year evenly or be 0".into());
+        }
+        // Submissions get the points of the first tier they reach, so the earliest tier goes first
+        config
+            .scoring
+            .submission
+            .sort_by_key(|reward| Reverse(reward.hours_left));
+        Ok(config)
+    }
+
+    pub fn is_admin(&self, native_id: &str) -> bool {
+        self.admins.is_empty() || self.admins.iter().any(|admin| admin == native_id)
+    }
+}
+
+pub fn set_config(config: Config) -> Result<(), Box<dyn Error>> {
+    CONFIG
+        .set(config)
+        .map_err(|_| "Config has already been set".into())
+}
+
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
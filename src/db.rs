@@ -0,0 +1,178 @@
+use crate::{clock, days_in_month, Database, Status};
+use chrono::Datelike;
+use serde_json::Value;
+use serenity::model::prelude::{GuildId, UserId};
+use std::{
+    cmp::{max, min},
+    error::Error,
+    ffi::OsString,
+    fs::{copy, read_to_string, write},
+    path::{Path, PathBuf},
+};
+
+pub fn read_database(path: &Path) -> Result<Database, Box<dyn Error>> {
+    let contents = read_to_string(path)?;
+    if contents.trim().is_empty() {
+        return Ok(Database::new());
+    }
+    serde_json::from_str(&contents)
+        .map_err(|why| format!("Invalid database {path:?}: {why}").into())
+}
+
+// The previous contents are kept next to the database in case an edit has to be undone
+pub fn write_database(path: &Path, database: &Database) -> Result<PathBuf, Box<dyn Error>> {
+    let mut backup = OsString::from(path.as_os_str());
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+    if path.exists() {
+        copy(path, &backup)?;
+    }
+    write(path, serde_json::to_string_pretty(database)?)?;
+    Ok(backup)
+}
+
+pub fn show_database(
+    database: &Database,
+    guild_id: Option<GuildId>,
+    user_id: Option<UserId>,
+) -> Result<Value, Box<dyn Error>> {
+    Ok(match (guild_id, user_id) {
+        (None, _) => serde_json::to_value(database)?,
+        (Some(guild_id), None) => {
+            serde_json::to_value(database.get(&guild_id).ok_or("Guild not found")?)?
+        }
+        (Some(guild_id), Some(user_id)) => serde_json::to_value(
+            database
+                .get(&guild_id)
+                .and_then(|data| data.users.get(&user_id))
+                .ok_or("User not found in guild")?,
+        )?,
+    })
+}
+
+// Returns the previous score
+pub fn set_score(
+    database: &mut Database,
+    guild_id: GuildId,
+    user_id: UserId,
+    score: usize,
+) -> Result<usize, Box<dyn Error>> {
+    let status = database
+        .get_mut(&guild_id)
+        .and_then(|data| data.users.get_mut(&user_id))
+        .ok_or("User not found in guild")?;
+    Ok(std::mem::replace(&mut status.score, score))
+}
+
+fn merge_status(into: &mut Status, from: Status) {
+    into.score += from.score;
+    into.archived_score += from.archived_score;
+    into.monthly_record = max(into.monthly_record, from.monthly_record);
+    into.weekly_submissions = max(into.weekly_submissions, from.weekly_submissions);
+    into.days_missed = min(into.days_missed, from.days_missed);
+    into.streak = max(into.streak, from.streak);
+    into.votes += from.votes;
+    for earned in from.badges {
+        if !into.badges.iter().any(|badge| badge.badge == earned.badge) {
+            into.badges.push(earned);
+        }
+    }
+    if into.submitted.is_none() {
+        into.submitted = from.submitted;
+    }
+    if into.voted_for.is_none() {
+        into.voted_for = from.voted_for;
+    }
+}
+
+// Moves every user of a guild into another one, the settings of the guild merged into are kept
+pub fn merge_guilds(
+    database: &mut Database,
+    from: GuildId,
+    into: GuildId,
+) -> Result<usize, Box<dyn Error>> {
+    if from == into {
+        return Err("Cannot merge a guild into itself".into());
+    }
+    if !database.contains_key(&into) {
+        return Err(format!("Guild {into} not found").into());
+    }
+    let from = database
+        .remove(&from)
+        .ok_or(format!("Guild {from} not found"))?;
+    let users = from.users.len();
+    let data = database.get_mut(&into).ok_or("Guild not found")?;
+    for (user_id, status) in from.users {
+        match data.users.get_mut(&user_id) {
+            Some(existing) => merge_status(existing, status),
+            None => {
+                data.users.insert(user_id, status);
+            }
+        }
+    }
+    Ok(users)
+}
+
+pub fn validate_database(database: &Database) -> Result<Vec<String>, Box<dyn Error>> {
+    let today = clock().now().date_naive();
+    let mut problems = Vec::new();
+    let mut guild_ids = database.keys().collect::<Vec<_>>();
+    guild_ids.sort();
+    for guild_id in guild_ids {
+        let data = &database[guild_id];
+        if data.last_rollover.is_some_and(|date| date > today) {
+            problems.push(format!("Guild {guild_id} last rolled over in the future"));
+        }
+        // Monthly records are reset on the first rollover of each month
+        let month = data.last_rollover.unwrap_or(today);
+        let days = days_in_month(month.year(), month.month())?;
+        let mut user_ids = data.users.keys().collect::<Vec<_>>();
+        user_ids.sort();
+        for user_id in user_ids {
+            let status = &data.users[user_id];
+            if let Some(voted_for) = status.voted_for {
+                match data.users.get(&voted_for) {
+                    None => problems.push(format!(
+                        "User {user_id} in guild {guild_id} voted for {voted_for} who is not in the guild"
+                    )),
+                    Some(_) if voted_for == *user_id => problems.push(format!(
+                        "User {user_id} in guild {guild_id} voted for themselves"
+                    )),
+                    Some(candidate) if candidate.submitted.is_none() => problems.push(format!(
+                        "User {user_id} in guild {guild_id} voted for {voted_for} who has not submitted"
+                    )),
+                    Some(_) => {}
+                }
+            }
+            if status.weekly_submissions > 4 {
+                problems.push(format!(
+                    "User {user_id} in guild {guild_id} has {} weekly submissions out of 4",
+                    status.weekly_submissions
+                ));
+            }
+            if status.monthly_record > days {
+                problems.push(format!(
+                    "User {user_id} in guild {guild_id} has a monthly record of {} in a month of {days} days",
+                    status.monthly_record
+                ));
+            }
+            if status
+                .submitted
+                .as_ref()
+                .is_some_and(|link| link.is_empty())
+            {
+                problems.push(format!(
+                    "User {user_id} in guild {guild_id} has an empty submission link"
+                ));
+            }
+        }
+    }
+    Ok(problems)
+}
+
+// Guilds in the imported database replace the ones with the same ID, returns how many were imported
+pub fn import_database(database: &mut Database, imported: Database) -> usize {
+    let guilds = imported.len();
+    database.extend(imported);
+    guilds
+}
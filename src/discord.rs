@@ -0,0 +1,389 @@
+use crate::{
+    acknowledge_interaction, add_guild, get_shared_state, handle_message, handle_page, handle_vote,
+    initialise_guilds, page_buttons,
+    platform::{
+        split_message, truncate_message, ChatPlatform, Embed, Incoming, Page, PlatformResult,
+    },
+    PageResponse, State, CUSTOM_ID,
+};
+use serenity::{
+    all::{
+        AutoArchiveDuration, ButtonStyle, CreateActionRow, CreateAttachment, CreateButton,
+        CreateEmbed, CreateEmbedFooter, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateMessage, CreateSelectMenu, CreateSelectMenuKind,
+        CreateThread, EditMessage,
+    },
+    async_trait,
+    model::prelude::*,
+    prelude::Context,
+};
+use std::error::Error;
+use tracing::info;
+
+const MESSAGE_LIMIT: usize = 2000;
+
+pub struct Discord {
+    ctx: Context,
+}
+
+impl Discord {
+    pub fn new(ctx: &Context) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    // Sends everything but the last part of a long message, which is returned so it can carry the
+    // embed, poll or file
+    async fn send_leading(&self, channel_id: ChannelId, content: &str) -> PlatformResult<String> {
+        let mut messages = split_message(content, MESSAGE_LIMIT);
+        let last = messages.pop().unwrap_or_default();
+        for message in messages {
+            channel_id.say(&self.ctx.http, message).await?;
+        }
+        Ok(last)
+    }
+}
+
+impl From<&Message> for Incoming {
+    fn from(msg: &Message) -> Self {
+        Self {
+            guild_id: msg.guild_id,
+            channel_id: msg.channel_id,
+            author: msg.author.id,
+            content: msg.content.clone(),
+            link: msg.link(),
+        }
+    }
+}
+
+fn create_embed(embed: Embed) -> CreateEmbed {
+    embed.fields.into_iter().fold(
+        CreateEmbed::default()
+            .title(embed.title)
+            .url(embed.url)
+            .colour(embed.colour),
+        |create, (name, value)| create.field(name, value, true),
+    )
+}
+
+fn create_page(page: &Page) -> (CreateEmbed, Vec<CreateActionRow>) {
+    let embed = CreateEmbed::new()
+        .title(&page.title)
+        .description(&page.content)
+        .colour(Colour::ORANGE)
+        .footer(CreateEmbedFooter::new(page.footer()));
+    let [previous, next, me] = page_buttons(page);
+    let buttons = vec![
+        CreateButton::new(previous)
+            .label("Previous")
+            .style(ButtonStyle::Secondary)
+            .disabled(page.page == 0),
+        CreateButton::new(next)
+            .label("Next")
+            .style(ButtonStyle::Secondary)
+            .disabled(page.page + 1 >= page.pages),
+        CreateButton::new(me)
+            .label("Me")
+            .style(ButtonStyle::Primary),
+    ];
+    (embed, vec![CreateActionRow::Buttons(buttons)])
+}
+
+#[async_trait]
+impl ChatPlatform for Discord {
+    fn current_user(&self) -> UserId {
+        self.ctx.cache.current_user().id
+    }
+
+    async fn say(&self, channel_id: ChannelId, content: String) -> PlatformResult<MessageId> {
+        let content = self.send_leading(channel_id, &content).await?;
+        Ok(channel_id.say(&self.ctx.http, content).await?.id)
+    }
+
+    async fn send_embed(
+        &self,
+        channel_id: ChannelId,
+        content: String,
+        embed: Embed,
+    ) -> PlatformResult<MessageId> {
+        let content = self.send_leading(channel_id, &content).await?;
+        Ok(channel_id
+            .send_message(
+                &self.ctx.http,
+                CreateMessage::new()
+                    .content(content)
+                    .embed(create_embed(embed)),
+            )
+            .await?
+            .id)
+    }
+
+    async fn send_file(
+        &self,
+        channel_id: ChannelId,
+        content: String,
+        file_name: String,
+        data: Vec<u8>,
+    ) -> PlatformResult<()> {
+        let content = self.send_leading(channel_id, &content).await?;
+        channel_id
+            .send_message(
+                &self.ctx.http,
+                CreateMessage::new()
+                    .content(content)
+                    .add_file(CreateAttachment::bytes(data, file_name)),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn send_page(&self, channel_id: ChannelId, page: Page) -> PlatformResult<MessageId> {
+        let (embed, components) = create_page(&page);
+        Ok(channel_id
+            .send_message(
+                &self.ctx.http,
+                CreateMessage::new().embed(embed).components(components),
+            )
+            .await?
+            .id)
+    }
+
+    async fn send_poll(&self, channel_id: ChannelId, content: String) -> PlatformResult<MessageId> {
+        let content = self.send_leading(channel_id, &content).await?;
+        let message = channel_id
+            .send_message(
+                &self.ctx.http,
+                CreateMessage::new().content(content).select_menu(
+                    CreateSelectMenu::new(
+                        CUSTOM_ID,
+                        CreateSelectMenuKind::User {
+                            default_users: None,
+                        },
+                    )
+                    .placeholder("No submission selected"),
+                ),
+            )
+            .await?;
+        message.pin(&self.ctx.http).await?;
+        Ok(message.id)
+    }
+
+    async fn edit(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: String,
+    ) -> PlatformResult<()> {
+        channel_id
+            .edit_message(
+                &self.ctx.http,
+                message_id,
+                EditMessage::new().content(truncate_message(&content, MESSAGE_LIMIT)),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn reply(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: String,
+    ) -> PlatformResult<()> {
+        let message = channel_id.message(&self.ctx.http, message_id).await?;
+        for content in split_message(&content, MESSAGE_LIMIT) {
+            message.reply(&self.ctx.http, content).await?;
+        }
+        Ok(())
+    }
+
+    async fn message_exists(&self, channel_id: ChannelId, message_id: MessageId) -> bool {
+        channel_id.message(&self.ctx.http, message_id).await.is_ok()
+    }
+
+    async fn create_thread(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        name: String,
+    ) -> PlatformResult<ChannelId> {
+        Ok(channel_id
+            .create_thread_from_message(
+                &self.ctx.http,
+                message_id,
+                CreateThread::new(name)
+                    .kind(ChannelType::PublicThread)
+                    .auto_archive_duration(AutoArchiveDuration::OneDay),
+            )
+            .await?
+            .id)
+    }
+
+    async fn is_text_channel(&self, channel_id: ChannelId) -> bool {
+        matches!(
+            channel_id.to_channel(&self.ctx.http).await,
+            Ok(Channel::Guild(channel)) if channel.kind == ChannelType::Text
+        )
+    }
+
+    async fn members(&self, guild_id: GuildId) -> PlatformResult<Vec<User>> {
+        Ok(guild_id
+            .members(&self.ctx.http, None, None)
+            .await?
+            .into_iter()
+            .map(|member| member.user)
+            .filter(|user| !user.bot)
+            .collect())
+    }
+
+    async fn role_members(
+        &self,
+        guild_id: GuildId,
+        role_id: RoleId,
+    ) -> PlatformResult<Vec<UserId>> {
+        Ok(guild_id
+            .members(&self.ctx.http, None, None)
+            .await?
+            .into_iter()
+            .filter(|member| member.roles.contains(&role_id))
+            .map(|member| member.user.id)
+            .collect())
+    }
+
+    async fn set_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+        assigned: bool,
+    ) -> PlatformResult<()> {
+        let reason = Some("LeetCode daily role reward");
+        if assigned {
+            self.ctx
+                .http
+                .add_member_role(guild_id, user_id, role_id, reason)
+                .await?;
+        } else {
+            self.ctx
+                .http
+                .remove_member_role(guild_id, user_id, role_id, reason)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+pub async fn setup(ctx: &Context, ready: Ready) -> Result<(), Box<dyn Error>> {
+    let mut data = ctx.data.write().await;
+    let state = get_shared_state!(data);
+    if state.ready {
+        Err("Already done setup".into())
+    } else {
+        state.ready = true;
+        let guilds = ready.guilds;
+        info!("Setting up guilds {guilds:?}");
+        let platform = Discord::new(ctx);
+        for guild in guilds {
+            if state.database.contains_key(&guild.id) {
+                initialise_guilds(&platform, &guild.id, state).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub async fn respond(ctx: &Context, msg: Message) -> Result<(), Box<dyn Error>> {
+    let mut data = ctx.data.write().await;
+    let state = get_shared_state!(data);
+    handle_message(&Discord::new(ctx), state, Incoming::from(&msg)).await
+}
+
+pub async fn vote(ctx: &Context, interaction: &Interaction) -> Result<(), Box<dyn Error>> {
+    if let Interaction::Component(component) = interaction {
+        let guild_id = component
+            .guild_id
+            .ok_or("This interaction was not received over the gateway")?;
+        if component.data.custom_id != CUSTOM_ID {
+            return Ok(());
+        }
+        if let ComponentInteractionDataKind::UserSelect { values } = &component.data.kind {
+            let voted_for = values.first().ok_or("Did not select a single value")?;
+            let response = {
+                let mut data = ctx.data.write().await;
+                let state = get_shared_state!(data);
+                handle_vote(
+                    state,
+                    guild_id,
+                    component.user.id,
+                    component.message.id,
+                    *voted_for,
+                )?
+            };
+            if let Some(response) = response {
+                return acknowledge_interaction!(ctx, component, response);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn turn_page(ctx: &Context, interaction: &Interaction) -> Result<(), Box<dyn Error>> {
+    let Interaction::Component(component) = interaction else {
+        return Ok(());
+    };
+    let guild_id = component
+        .guild_id
+        .ok_or("This interaction was not received over the gateway")?;
+    let response = {
+        let mut data = ctx.data.write().await;
+        let state = get_shared_state!(data);
+        handle_page(
+            &Discord::new(ctx),
+            state,
+            guild_id,
+            component.user.id,
+            &component.data.custom_id,
+        )
+        .await?
+    };
+    let response = match response {
+        Some(PageResponse::Update(page)) => {
+            let (embed, components) = create_page(&page);
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(embed)
+                    .components(components),
+            )
+        }
+        Some(PageResponse::Ephemeral(page)) => {
+            let (embed, components) = create_page(&page);
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .embed(embed)
+                    .components(components)
+                    .ephemeral(true),
+            )
+        }
+        Some(PageResponse::Message(content)) => {
+            return acknowledge_interaction!(ctx, component, content);
+        }
+        None => return Ok(()),
+    };
+    component.create_response(&ctx.http, response).await?;
+    Ok(())
+}
+
+pub async fn initialise_guild(ctx: &Context, guild: Guild) -> Result<(), Box<dyn Error>> {
+    let mut data = ctx.data.write().await;
+    let state = get_shared_state!(data);
+    add_guild(
+        &Discord::new(ctx),
+        state,
+        guild.id,
+        guild
+            .channels
+            .values()
+            .find(|channel| channel.kind == ChannelType::Text)
+            .map(|channel| channel.id),
+    )
+    .await
+}
@@ -0,0 +1,172 @@
+use crate::{clock, Data, Users};
+use serde_json::{json, Map, Value};
+use serenity::model::prelude::UserId;
+use std::{cmp::Reverse, error::Error};
+
+pub struct Table {
+    name: &'static str,
+    columns: &'static [&'static str],
+    rows: Vec<Vec<Value>>,
+}
+
+fn csv_field(value: &Value) -> String {
+    let field = match value {
+        Value::Null => String::new(),
+        // Spreadsheets run text starting with these as a formula, names are chosen by users
+        Value::String(string) if string.starts_with(['=', '+', '-', '@', '\t', '\r']) => {
+            format!("'{string}")
+        }
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+impl Table {
+    pub fn scores(data: &Data, users: Option<&Users>) -> Self {
+        let mut statuses = data.users.iter().collect::<Vec<_>>();
+        statuses.sort_by_key(|(id, status)| {
+            (Reverse(status.score), Reverse(status.monthly_record), **id)
+        });
+        Self {
+            name: "scores",
+            columns: &[
+                "rank",
+                "user_id",
+                "name",
+                "score",
+                "all_time_score",
+                "monthly_record",
+                "days_missed",
+                "weekly_submissions",
+            ],
+            rows: statuses
+                .into_iter()
+                .enumerate()
+                .map(|(place, (id, status))| {
+                    vec![
+                        json!(place + 1),
+                        json!(id.to_string()),
+                        json!(name(users, id)),
+                        json!(status.score),
+                        json!(status.archived_score + status.score),
+                        json!(status.monthly_record),
+                        json!(status.days_missed),
+                        json!(status.weekly_submissions),
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    pub fn monthly(data: &Data, users: Option<&Users>) -> Self {
+        let month = clock().now().format("%Y-%m").to_string();
+        let mut statuses = data.users.iter().collect::<Vec<_>>();
+        statuses.sort_by_key(|(id, status)| (Reverse(status.monthly_record), **id));
+        Self {
+            name: "monthly",
+            columns: &[
+                "month",
+                "rank",
+                "user_id",
+                "name",
+                "monthly_record",
+                "submitted_today",
+            ],
+            rows: statuses
+                .into_iter()
+                .enumerate()
+                .map(|(place, (id, status))| {
+                    vec![
+                        json!(month),
+                        json!(place + 1),
+                        json!(id.to_string()),
+                        json!(name(users, id)),
+                        json!(status.monthly_record),
+                        json!(status.submitted.is_some()),
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    pub fn history(data: &Data, users: Option<&Users>) -> Self {
+        Self {
+            name: "history",
+            columns: &[
+                "month",
+                "rank",
+                "user_id",
+                "name",
+                "monthly_record",
+                "score",
+                "badge",
+            ],
+            rows: data
+                .history
+                .iter()
+                .flat_map(|snapshot| {
+                    snapshot
+                        .standings
+                        .iter()
+                        .enumerate()
+                        .map(|(place, standing)| {
+                            vec![
+                                json!(snapshot.month),
+                                json!(place + 1),
+                                json!(standing.user_id.to_string()),
+                                json!(name(users, &standing.user_id)),
+                                json!(standing.monthly_record),
+                                json!(standing.score),
+                                json!(snapshot.badges.contains(&standing.user_id)),
+                            ]
+                        })
+                })
+                .collect(),
+        }
+    }
+
+    pub fn csv(&self) -> String {
+        let mut csv = self.columns.join(",");
+        csv.push('\n');
+        for row in &self.rows {
+            csv.push_str(&row.iter().map(csv_field).collect::<Vec<_>>().join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn json(&self) -> Result<String, Box<dyn Error>> {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .map(|column| column.to_string())
+                    .zip(row.iter().cloned())
+                    .collect::<Map<_, _>>()
+            })
+            .collect::<Vec<_>>();
+        Ok(serde_json::to_string_pretty(&rows)?)
+    }
+
+    pub fn file_name(&self, extension: &str) -> String {
+        format!(
+            "{}-{}.{extension}",
+            self.name,
+            clock().now().format("%Y-%m-%d")
+        )
+    }
+}
+
+fn name(users: Option<&Users>, id: &UserId) -> String {
+    users
+        .and_then(|users| users.get(id))
+        .map(|user| user.name.clone())
+        .unwrap_or_default()
+}
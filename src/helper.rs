@@ -2,30 +2,26 @@
 macro_rules! save_to_database {
     ($ctx:ident) => {
         if let Err(why) = save_to_database($ctx).await {
-            log!("Error saving to database: {why}");
+            tracing::error!("Error saving to database: {why}");
         }
     };
 }
 
 #[macro_export]
 macro_rules! schedule_thread {
-    ($ctx:ident, $schedule:ident) => {{
-        let ctx = $ctx.clone();
+    ($platform:ident, $data:expr, $schedule:ident) => {{
+        let platform = $platform.clone();
+        let data = $data.clone();
         spawn(async move {
-            if let Err(why) = $schedule(&ctx).await {
-                log!("Error scheduling: {why}");
+            $crate::metrics().set_scheduler_alive(stringify!($schedule), true);
+            if let Err(why) = $schedule(platform.as_ref(), &data).await {
+                tracing::error!(scheduler = stringify!($schedule), "Error scheduling: {why}");
             }
+            $crate::metrics().set_scheduler_alive(stringify!($schedule), false);
         });
     }};
 }
 
-#[macro_export]
-macro_rules! log {
-    ($message:expr) => {
-        println!("[{}] {}", Utc::now(), format!($message));
-    };
-}
-
 #[macro_export]
 macro_rules! get_channel_from_guild {
     ($guild:expr) => {
@@ -43,20 +39,25 @@ macro_rules! get_thread_from_guild {
 #[macro_export]
 macro_rules! write_to_database {
     ($state:expr) => {
+        let start = std::time::Instant::now();
+        let contents = serde_json::to_string_pretty(&$state.database)?;
         $state.file.seek(SeekFrom::Start(0))?;
         $state.file.set_len(0)?;
-        $state
-            .file
-            .write_all(serde_json::to_string_pretty(&$state.database)?.as_bytes())?
+        $state.file.write_all(contents.as_bytes())?;
+        $crate::metrics().record_database_write(start.elapsed(), contents.len())
     };
 }
 
 #[macro_export]
 macro_rules! get_shared_state {
     ($data:ident) => {{
-        $data
+        let state = $data
             .get_mut::<State>()
-            .ok_or("Failed to get share data from context")?
+            .ok_or("Failed to get share data from context")?;
+        if state.shutting_down {
+            return Err("Shutting down".into());
+        }
+        state
     }};
 }
 
@@ -0,0 +1,323 @@
+use crate::{config, get_user_from_id, Data, Guilds};
+use chrono::{Datelike, Month, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    model::prelude::{GuildId, User, UserId},
+    utils::MessageBuilder,
+};
+use std::{cmp::Reverse, error::Error};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Standing {
+    pub user_id: UserId,
+    pub monthly_record: u32,
+    pub score: usize,
+}
+
+// The final standings of a month, kept when the first rollover of the next month resets them
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MonthlySnapshot {
+    pub month: String,
+    pub standings: Vec<Standing>,
+    pub badges: Vec<UserId>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SeasonStanding {
+    pub user_id: UserId,
+    pub score: usize,
+}
+
+// The first and last month of a season that has ended along with its final scores
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SeasonRecord {
+    pub start: String,
+    pub end: String,
+    pub standings: Vec<SeasonStanding>,
+    pub champions: Vec<UserId>,
+}
+
+impl SeasonRecord {
+    fn name(&self) -> Result<String, Box<dyn Error>> {
+        Ok(if self.start == self.end {
+            month_name(&self.start)?
+        } else {
+            format!("{} to {}", month_name(&self.start)?, month_name(&self.end)?)
+        })
+    }
+}
+
+pub fn month_name(month: &str) -> Result<String, Box<dyn Error>> {
+    let date = parse_month(month).ok_or("Invalid month")?;
+    Ok(format!(
+        "{} {}",
+        Month::try_from(TryInto::<u8>::try_into(date.month())?)?.name(),
+        date.year()
+    ))
+}
+
+pub fn parse_month(month: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d").ok()
+}
+
+pub fn snapshot(data: &Data, last_month: NaiveDate) -> MonthlySnapshot {
+    let mut standings = data
+        .users
+        .iter()
+        .map(|(&user_id, status)| Standing {
+            user_id,
+            monthly_record: status.monthly_record,
+            score: status.score,
+        })
+        .collect::<Vec<_>>();
+    standings.sort_by_key(|standing| {
+        (
+            Reverse(standing.monthly_record),
+            Reverse(standing.score),
+            standing.user_id,
+        )
+    });
+    MonthlySnapshot {
+        month: last_month.format("%Y-%m").to_string(),
+        badges: standings
+            .iter()
+            .filter(|standing| standing.monthly_record == last_month.day())
+            .map(|standing| standing.user_id)
+            .collect(),
+        standings,
+    }
+}
+
+pub fn construct_standings<'a>(
+    snapshot: &MonthlySnapshot,
+    guilds: &mut Guilds,
+    guild_id: &GuildId,
+    message: &'a mut MessageBuilder,
+) -> Result<&'a mut MessageBuilder, Box<dyn Error>> {
+    message.push_line(format!(
+        "The standings of {}:",
+        month_name(&snapshot.month)?
+    ));
+    let mut has_record = false;
+    for (place, standing) in snapshot.standings.iter().enumerate() {
+        if standing.monthly_record > 0 {
+            has_record = true;
+            let user_id = &standing.user_id;
+            let user = get_user_from_id!(guilds, guild_id, user_id);
+            message
+                .push_line(format!("{}. {}", place + 1, user.name))
+                .push_bold(format!("\t{} ", standing.monthly_record))
+                .push(if standing.monthly_record > 1 {
+                    "questions"
+                } else {
+                    "question"
+                })
+                .push_line(" completed")
+                .push_bold(format!("\t{} ", standing.score))
+                .push_line(if standing.score > 1 {
+                    "points"
+                } else {
+                    "point"
+                });
+        }
+    }
+    if !has_record {
+        message.push_line("No one completed any questions that month");
+    }
+    if !snapshot.badges.is_empty() {
+        message.push("\nEarned the Daily Challenge badge: ");
+        for (index, user_id) in snapshot.badges.iter().enumerate() {
+            if index > 0 {
+                message.push(", ");
+            }
+            let user = get_user_from_id!(guilds, guild_id, user_id);
+            message.push_bold(user.name.clone());
+        }
+    }
+    Ok(message)
+}
+
+// Archives the scores of the season ending the day before date and starts the next one from 0
+pub fn end_season(
+    guilds: &mut Guilds,
+    guild_id: &GuildId,
+    data: &mut Data,
+    date: NaiveDate,
+    message: &mut MessageBuilder,
+) -> Result<(), Box<dyn Error>> {
+    let start = date
+        .checked_sub_months(Months::new(config().schedule.season_months))
+        .ok_or("Invalid date")?;
+    let end = date.pred_opt().ok_or("Invalid date")?;
+    let mut standings = data
+        .users
+        .iter()
+        .map(|(&user_id, status)| SeasonStanding {
+            user_id,
+            score: status.score,
+        })
+        .collect::<Vec<_>>();
+    standings.sort_by_key(|standing| (Reverse(standing.score), standing.user_id));
+    let highest_score = standings.first().map_or(0, |standing| standing.score);
+    let record = SeasonRecord {
+        start: start.format("%Y-%m").to_string(),
+        end: end.format("%Y-%m").to_string(),
+        champions: standings
+            .iter()
+            .filter(|standing| highest_score > 0 && standing.score == highest_score)
+            .map(|standing| standing.user_id)
+            .collect(),
+        standings,
+    };
+    message.push(format!("\nThe season of {} is over! ", record.name()?));
+    if record.champions.is_empty() {
+        message.push("No one scored any points this season");
+    } else {
+        message.push("Congratulations to ");
+        for (index, user_id) in record.champions.iter().enumerate() {
+            if index > 0 {
+                message.push(", ");
+            }
+            let user = get_user_from_id!(guilds, guild_id, user_id);
+            message.mention(user);
+        }
+        message
+            .push(" for winning it with ")
+            .push_bold(highest_score.to_string())
+            .push(if highest_score > 1 {
+                " points"
+            } else {
+                " point"
+            })
+            .push(" 🏆");
+    }
+    message.push_line(
+        "\nEveryone starts the new season from 0 points, all-time totals are kept in /halloffame",
+    );
+    for status in data.users.values_mut() {
+        status.archived_score += status.score;
+        status.score = 0;
+    }
+    data.seasons.retain(|season| season.start != record.start);
+    data.seasons.push(record);
+    Ok(())
+}
+
+pub fn construct_hall_of_fame<'a>(
+    data: &Data,
+    guilds: &mut Guilds,
+    guild_id: &GuildId,
+    message: &'a mut MessageBuilder,
+) -> Result<&'a mut MessageBuilder, Box<dyn Error>> {
+    message.push_line("Season champions:");
+    if data.seasons.is_empty() {
+        message.push_line(if config().schedule.season_months > 0 {
+            "No season has ended yet"
+        } else {
+            "Seasons are turned off, scores are never reset"
+        });
+    }
+    for season in data.seasons.iter().rev() {
+        message.push(format!("{}: ", season.name()?));
+        if season.champions.is_empty() {
+            message.push_line("no one scored any points");
+            continue;
+        }
+        for (index, user_id) in season.champions.iter().enumerate() {
+            if index > 0 {
+                message.push(", ");
+            }
+            let user = get_user_from_id!(guilds, guild_id, user_id);
+            message.push_bold(user.name.clone());
+        }
+        let score = season
+            .standings
+            .first()
+            .map_or(0, |standing| standing.score);
+        message.push_line(format!(
+            " with {score} {}",
+            if score > 1 { "points" } else { "point" }
+        ));
+    }
+    message.push_line("\nAll-time totals:");
+    let mut totals = data
+        .users
+        .iter()
+        .map(|(user_id, status)| (*user_id, status.archived_score + status.score))
+        .filter(|(_, total)| *total > 0)
+        .collect::<Vec<_>>();
+    totals.sort_by_key(|&(user_id, total)| (Reverse(total), user_id));
+    if totals.is_empty() {
+        message.push("No one has any points yet");
+    }
+    for (place, (user_id, total)) in totals.into_iter().take(10).enumerate() {
+        let user_id = &user_id;
+        let user = get_user_from_id!(guilds, guild_id, user_id);
+        message
+            .push(format!("{}. {} ", place + 1, user.name))
+            .push_bold(total.to_string())
+            .push_line(if total > 1 { " points" } else { " point" });
+    }
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Status;
+
+    fn data(records: &[(u32, usize)]) -> Data {
+        let mut data = Data::default();
+        for (index, &(monthly_record, score)) in records.iter().enumerate() {
+            data.users.insert(
+                UserId::new(index as u64 + 1),
+                Status {
+                    monthly_record,
+                    score,
+                    ..Default::default()
+                },
+            );
+        }
+        data
+    }
+
+    fn user_ids(ids: &[u64]) -> Vec<UserId> {
+        ids.iter().map(|&id| UserId::new(id)).collect()
+    }
+
+    #[test]
+    fn standings_rank_by_record_then_score() {
+        let february = NaiveDate::from_ymd_opt(2026, 2, 28).unwrap();
+        let snapshot = snapshot(
+            &data(&[(20, 5), (28, 3), (20, 9), (0, 0), (20, 9)]),
+            february,
+        );
+        assert_eq!(snapshot.month, "2026-02");
+        assert_eq!(
+            snapshot
+                .standings
+                .iter()
+                .map(|standing| standing.user_id)
+                .collect::<Vec<_>>(),
+            user_ids(&[2, 3, 5, 1, 4])
+        );
+        assert_eq!(snapshot.standings[0].monthly_record, 28);
+        assert_eq!(snapshot.standings[0].score, 3);
+    }
+
+    #[test]
+    fn badges_go_to_everyone_who_completed_the_whole_month() {
+        let data = data(&[(30, 1), (31, 2), (31, 0)]);
+        let march = NaiveDate::from_ymd_opt(2026, 3, 31).unwrap();
+        assert_eq!(snapshot(&data, march).badges, user_ids(&[2, 3]));
+        // Records are only compared with the length of the month that ended
+        let april = NaiveDate::from_ymd_opt(2026, 4, 30).unwrap();
+        assert_eq!(snapshot(&data, april).badges, user_ids(&[1]));
+    }
+
+    #[test]
+    fn month_names_are_spelled_out() {
+        assert_eq!(month_name("2026-12").unwrap(), "December 2026");
+        assert!(month_name("2026-13").is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{
+    config, daily_date, metrics, rollover_guilds, write_to_database, ChatPlatform, Data,
+    SharedState, State,
+};
+use axum::{
+    extract::{Path, Query, Request, State as Extract},
+    http::{header::AUTHORIZATION, StatusCode},
+    middleware::{from_fn, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{ConnectionStage, ShardManager},
+    model::prelude::{GuildId, UserId},
+    prelude::{RwLock, TypeMap},
+};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    io::{Seek, SeekFrom, Write},
+    net::SocketAddr,
+    sync::{Arc, OnceLock},
+};
+use subtle::ConstantTimeEq;
+use tokio::net::TcpListener;
+use tracing::info;
+
+static PLATFORM: OnceLock<Arc<dyn ChatPlatform>> = OnceLock::new();
+
+type Rejection = (StatusCode, String);
+
+#[derive(Clone)]
+struct Server {
+    data: Arc<RwLock<TypeMap>>,
+    shard_manager: Option<Arc<ShardManager>>,
+}
+
+#[derive(Serialize)]
+struct Health {
+    healthy: bool,
+    gateway_connected: bool,
+    schedulers: BTreeMap<String, bool>,
+    scheduler_last_run: BTreeMap<String, i64>,
+    last_save: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct ScoreAdjustment {
+    delta: isize,
+}
+
+#[derive(Serialize)]
+struct Score {
+    score: usize,
+}
+
+#[derive(Deserialize)]
+struct Rollover {
+    #[serde(default)]
+    force: bool,
+}
+
+#[derive(Serialize)]
+struct RolloverResult {
+    guilds: usize,
+}
+
+pub fn set_platform(platform: Arc<dyn ChatPlatform>) {
+    let _ = PLATFORM.set(platform);
+}
+
+fn internal_error(why: Box<dyn Error>) -> Rejection {
+    (StatusCode::INTERNAL_SERVER_ERROR, why.to_string())
+}
+
+async fn render_metrics() -> String {
+    metrics().render()
+}
+
+async fn health(Extract(server): Extract<Server>) -> Response {
+    let gateway_connected = if let Some(shard_manager) = &server.shard_manager {
+        let runners = shard_manager.runners.lock().await;
+        !runners.is_empty()
+            && runners
+                .values()
+                .all(|runner| runner.stage == ConnectionStage::Connected)
+    } else {
+        metrics().gateway_connected()
+    };
+    let schedulers = metrics().schedulers();
+    let healthy =
+        gateway_connected && !schedulers.is_empty() && schedulers.values().all(|&alive| alive);
+    (
+        if healthy {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        },
+        Json(Health {
+            healthy,
+            gateway_connected,
+            schedulers,
+            scheduler_last_run: metrics().scheduler_last_runs(),
+            last_save: metrics().database_last_write(),
+        }),
+    )
+        .into_response()
+}
+
+async fn authorise(request: Request, next: Next) -> Result<Response, Rejection> {
+    let token = config()
+        .http
+        .admin_token
+        .as_ref()
+        .ok_or((StatusCode::NOT_FOUND, String::from("Admin API is disabled")))?;
+    let authorised = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .is_some_and(|bearer| bool::from(bearer.as_bytes().ct_eq(token.as_bytes())));
+    if authorised {
+        Ok(next.run(request).await)
+    } else {
+        Err((
+            StatusCode::UNAUTHORIZED,
+            String::from("Invalid admin token"),
+        ))
+    }
+}
+
+// Same checks as get_shared_state! so nothing is changed after the shutdown flush
+fn shared_state(data: &mut TypeMap) -> Result<&mut SharedState, Rejection> {
+    let state = data.get_mut::<State>().ok_or((
+        StatusCode::NOT_FOUND,
+        String::from("There is no database in webhook mode"),
+    ))?;
+    if state.shutting_down {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            String::from("Shutting down"),
+        ));
+    }
+    Ok(state)
+}
+
+fn save(state: &mut SharedState) -> Result<(), Box<dyn Error>> {
+    write_to_database!(state);
+    Ok(())
+}
+
+async fn read_guild(
+    Extract(server): Extract<Server>,
+    Path(guild_id): Path<u64>,
+) -> Result<Json<Data>, Rejection> {
+    let mut data = server.data.write().await;
+    shared_state(&mut data)?
+        .database
+        .get(&GuildId::new(guild_id))
+        .cloned()
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, String::from("Guild not found")))
+}
+
+async fn adjust_score(
+    Extract(server): Extract<Server>,
+    Path((guild_id, user_id)): Path<(u64, u64)>,
+    Json(adjustment): Json<ScoreAdjustment>,
+) -> Result<Json<Score>, Rejection> {
+    let mut data = server.data.write().await;
+    let state = shared_state(&mut data)?;
+    let status = state
+        .database
+        .get_mut(&GuildId::new(guild_id))
+        .and_then(|data| data.users.get_mut(&UserId::new(user_id)))
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            String::from("User not found in guild"),
+        ))?;
+    status.score = status.score.saturating_add_signed(adjustment.delta);
+    let score = status.score;
+    info!(
+        guild_id,
+        user_id,
+        delta = adjustment.delta,
+        score,
+        "Adjusted score through the admin API"
+    );
+    save(state).map_err(internal_error)?;
+    Ok(Json(Score { score }))
+}
+
+async fn rollover(
+    Extract(server): Extract<Server>,
+    Query(rollover): Query<Rollover>,
+) -> Result<Json<RolloverResult>, Rejection> {
+    let platform = PLATFORM.get().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        String::from("Not connected to the chat platform yet"),
+    ))?;
+    let mut data = server.data.write().await;
+    let state = shared_state(&mut data)?;
+    let today = daily_date();
+    if rollover.force {
+        let yesterday = today
+            .pred_opt()
+            .ok_or_else(|| internal_error("Invalid date".into()))?;
+        for data in state.database.values_mut() {
+            data.last_rollover = Some(yesterday);
+        }
+    }
+    let guilds = state
+        .database
+        .values()
+        .filter(|data| data.last_rollover.is_some_and(|date| date < today))
+        .count();
+    info!(
+        force = rollover.force,
+        guilds, "Rollover triggered through the admin API"
+    );
+    rollover_guilds(platform.as_ref(), state)
+        .await
+        .map_err(internal_error)?;
+    Ok(Json(RolloverResult { guilds }))
+}
+
+pub async fn serve(
+    address: SocketAddr,
+    data: Arc<RwLock<TypeMap>>,
+    shard_manager: Option<Arc<ShardManager>>,
+) -> Result<(), Box<dyn Error>> {
+    let admin = Router::new()
+        .route("/guilds/:guild_id", get(read_guild))
+        .route("/guilds/:guild_id/users/:user_id/score", post(adjust_score))
+        .route("/rollover", post(rollover))
+        .route_layer(from_fn(authorise));
+    let router = Router::new()
+        .route("/metrics", get(render_metrics))
+        .route("/healthz", get(health))
+        .nest("/admin", admin)
+        .with_state(Server {
+            data,
+            shard_manager,
+        });
+    let listener = TcpListener::bind(address).await?;
+    info!("Serving HTTP on {address}");
+    axum::serve(listener, router).await?;
+    Ok(())
+}
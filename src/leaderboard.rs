@@ -0,0 +1,808 @@
+use crate::{
+    clock, config, days_in_month, get_user_from_id,
+    platform::{ChatPlatform, Page, PlatformResult},
+    roles::parse_role,
+    Guilds, State, Status, UserInfo,
+};
+use ab_glyph::{point, Font, FontArc, GlyphId, PxScale, ScaleFont};
+use cached::{proc_macro::cached, Cached};
+use chrono::Datelike;
+use futures_util::future::join_all;
+use reqwest::Client;
+use serenity::{
+    model::prelude::{GuildId, RoleId, User, UserId},
+    prelude::{RwLock, TypeMap},
+    utils::MessageBuilder,
+};
+use std::{cmp::Reverse, error::Error, fs::read, time::Duration};
+use tiny_skia::{
+    Color, FillRule, FilterQuality, Paint, PathBuilder, Pattern, Pixmap, PremultipliedColorU8,
+    Rect, SpreadMode, Transform,
+};
+use tokio::{spawn, task::spawn_blocking};
+use tracing::warn;
+
+const WIDTH: u32 = 800;
+const HEADER: u32 = 72;
+const ROW: u32 = 64;
+const FOOTER: u32 = 40;
+const AVATAR: f32 = 44.0;
+const MAX_ROWS: usize = 50;
+const PAGE_SIZE: usize = 10;
+const BAR_X: f32 = 460.0;
+const BAR_WIDTH: f32 = 160.0;
+
+const REGULAR: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+const BOLD: &[u8] = include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf");
+
+const BACKGROUND: [u8; 3] = [0x1e, 0x1f, 0x22];
+const ROWS: [[u8; 3]; 2] = [[0x31, 0x33, 0x38], [0x2b, 0x2d, 0x31]];
+const TEXT: [u8; 3] = [0xf2, 0xf3, 0xf5];
+const MUTED: [u8; 3] = [0xb5, 0xba, 0xc1];
+const BAR: [u8; 3] = [0x4e, 0x50, 0x58];
+const PROGRESS: [u8; 3] = [0xff, 0xa1, 0x16];
+const PODIUM: [[u8; 3]; 3] = [[0xff, 0xd7, 0x00], [0xc0, 0xc0, 0xc0], [0xcd, 0x7f, 0x32]];
+const PLACEHOLDERS: [[u8; 3]; 5] = [
+    [0x58, 0x65, 0xf2],
+    [0x57, 0xf2, 0x87],
+    [0xeb, 0x45, 0x9e],
+    [0xed, 0x42, 0x45],
+    [0x2d, 0xb5, 0xa3],
+];
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub enum SortBy {
+    #[default]
+    Score,
+    Monthly,
+    Streak,
+    Votes,
+    Weekly,
+}
+
+impl SortBy {
+    fn name(&self) -> &'static str {
+        match self {
+            SortBy::Score => "score",
+            SortBy::Monthly => "monthly",
+            SortBy::Streak => "streak",
+            SortBy::Votes => "votes",
+            SortBy::Weekly => "weekly",
+        }
+    }
+
+    // The value people are ranked by and the one that breaks ties
+    fn key(&self, status: &Status) -> (usize, usize) {
+        let score = status.score;
+        let monthly_record = status.monthly_record as usize;
+        match self {
+            SortBy::Score => (score, monthly_record),
+            SortBy::Monthly => (monthly_record, score),
+            SortBy::Streak => (status.streak as usize, score),
+            SortBy::Votes => (status.votes, score),
+            SortBy::Weekly => (status.weekly_submissions, score),
+        }
+    }
+
+    fn describe(&self, status: &Status) -> String {
+        let plural = |count: usize, one: &str, many: &str| {
+            format!("{count} {}", if count == 1 { one } else { many })
+        };
+        let points = plural(status.score, "point", "points");
+        match self {
+            SortBy::Score => format!(
+                "{points}, {} this month",
+                plural(status.monthly_record as usize, "question", "questions")
+            ),
+            SortBy::Monthly => format!(
+                "{} this month, {points}",
+                plural(status.monthly_record as usize, "question", "questions")
+            ),
+            SortBy::Streak => format!("{} day streak, {points}", status.streak),
+            SortBy::Votes => format!(
+                "{} received, {points}",
+                plural(status.votes, "vote", "votes")
+            ),
+            SortBy::Weekly => format!(
+                "{} of this week's contest, {points}",
+                plural(status.weekly_submissions, "question", "questions")
+            ),
+        }
+    }
+}
+
+// What /scores ranks people by and who it includes, kept in the page buttons as /scores arguments
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Ranking {
+    pub by: SortBy,
+    pub role: Option<RoleId>,
+    pub top: Option<usize>,
+}
+
+impl Ranking {
+    // Returns false if the argument isn't one of by:, role: or top:
+    pub fn parse_arg(&mut self, arg: &str) -> bool {
+        match arg.split_once(':') {
+            Some(("by", by)) => {
+                self.by = match by {
+                    "score" => SortBy::Score,
+                    "monthly" => SortBy::Monthly,
+                    "streak" => SortBy::Streak,
+                    "votes" => SortBy::Votes,
+                    "weekly" => SortBy::Weekly,
+                    _ => return false,
+                };
+                true
+            }
+            Some(("role", role)) => {
+                self.role = parse_role(role);
+                self.role.is_some()
+            }
+            Some(("top", top)) => {
+                self.top = top.parse().ok().filter(|&top| top > 0);
+                self.top.is_some()
+            }
+            _ => false,
+        }
+    }
+
+    pub fn parse(args: &str) -> Option<Self> {
+        let mut ranking = Self::default();
+        args.split_whitespace()
+            .all(|arg| ranking.parse_arg(arg))
+            .then_some(ranking)
+    }
+
+    pub fn args(&self) -> String {
+        let mut args = vec![format!("by:{}", self.by.name())];
+        if let Some(role) = self.role {
+            args.push(format!("role:{role}"));
+        }
+        if let Some(top) = self.top {
+            args.push(format!("top:{top}"));
+        }
+        args.join(" ")
+    }
+
+    fn title(&self) -> String {
+        format!(
+            "{}{}",
+            self.top
+                .map_or(String::from("The current leaderboard"), |top| {
+                    format!("The top {top}")
+                }),
+            match self.by {
+                SortBy::Score => "",
+                SortBy::Monthly => " by questions completed this month",
+                SortBy::Streak => " by streak",
+                SortBy::Votes => " by votes received",
+                SortBy::Weekly => " by weekly contest questions",
+            }
+        )
+    }
+
+    // Everyone with more than 0 of what is ranked, highest first
+    fn ranked<'a>(&self, users: &'a UserInfo) -> Vec<(UserId, &'a Status)> {
+        let mut ranked = users
+            .iter()
+            .filter(|(_, status)| self.by.key(status).0 > 0)
+            .map(|(id, status)| (*id, status))
+            .collect::<Vec<_>>();
+        ranked.sort_by_key(|(id, status)| {
+            let (value, tiebreak) = self.by.key(status);
+            (Reverse(value), Reverse(tiebreak), *id)
+        });
+        if let Some(top) = self.top {
+            ranked.truncate(top);
+        }
+        ranked
+    }
+}
+
+// Shows the page with user_id on it if there is one, returns None if they aren't ranked
+pub fn construct_page(
+    users: &UserInfo,
+    guilds: &mut Guilds,
+    guild_id: &GuildId,
+    ranking: &Ranking,
+    page: usize,
+    user_id: Option<UserId>,
+) -> Option<Page> {
+    let ranked = ranking.ranked(users);
+    let pages = ranked.len().div_ceil(PAGE_SIZE).max(1);
+    let page = match user_id {
+        Some(user_id) => ranked.iter().position(|(id, _)| *id == user_id)? / PAGE_SIZE,
+        None => page.min(pages - 1),
+    };
+    let mut message = MessageBuilder::new();
+    if let Some(role) = ranking.role {
+        message.push("Only members of ").role(role).push_line("");
+    }
+    if ranked.is_empty() {
+        message.push("No one is on this leaderboard yet");
+    }
+    for (place, (id, status)) in ranked
+        .iter()
+        .enumerate()
+        .skip(page * PAGE_SIZE)
+        .take(PAGE_SIZE)
+    {
+        let user = get_user_from_id!(guilds, guild_id, id);
+        message
+            .push_bold(format!("{}.", place + 1))
+            .push(format!(" {}: ", user.name))
+            .push(ranking.by.describe(status))
+            .push_line(if Some(*id) == user_id { " ⬅️" } else { "" });
+    }
+    Some(Page {
+        title: ranking.title(),
+        content: message.build(),
+        page,
+        pages,
+        args: ranking.args(),
+    })
+}
+
+// Leaves out everyone who doesn't have the role when the ranking is restricted to one
+pub async fn filter_role(
+    platform: &dyn ChatPlatform,
+    guild_id: &GuildId,
+    users: &UserInfo,
+    ranking: &Ranking,
+) -> PlatformResult<UserInfo> {
+    let Some(role) = ranking.role else {
+        return Ok(users.clone());
+    };
+    let members = platform.role_members(*guild_id, role).await?;
+    Ok(users
+        .iter()
+        .filter(|(id, _)| members.contains(id))
+        .map(|(id, status)| (*id, status.clone()))
+        .collect())
+}
+
+struct Entry {
+    user: User,
+    score: usize,
+    monthly_record: u32,
+    avatar: Option<Pixmap>,
+}
+
+struct Fonts {
+    regular: FontArc,
+    bold: FontArc,
+}
+
+impl Fonts {
+    fn load() -> Result<Self, Box<dyn Error>> {
+        Ok(match &config().leaderboard.font {
+            Some(path) => {
+                let font = FontArc::try_from_vec(read(path)?)?;
+                Self {
+                    regular: font.clone(),
+                    bold: font,
+                }
+            }
+            None => Self {
+                regular: FontArc::try_from_slice(REGULAR)?,
+                bold: FontArc::try_from_slice(BOLD)?,
+            },
+        })
+    }
+}
+
+fn paint(colour: [u8; 3]) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(colour[0], colour[1], colour[2], 255);
+    paint.anti_alias = true;
+    paint
+}
+
+fn fill_rect(pixmap: &mut Pixmap, x: f32, y: f32, width: f32, height: f32, colour: [u8; 3]) {
+    if let Some(rect) = Rect::from_xywh(x, y, width, height) {
+        pixmap.fill_rect(rect, &paint(colour), Transform::identity(), None);
+    }
+}
+
+fn text_width(font: &FontArc, size: f32, text: &str) -> f32 {
+    let font = font.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            width += font.kern(previous, id);
+        }
+        width += font.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+// Cuts off the end of the text with an ellipsis until it fits
+fn truncate(font: &FontArc, size: f32, text: &str, max_width: f32) -> String {
+    if text_width(font, size, text) <= max_width {
+        return text.to_string();
+    }
+    let mut chars = text.chars().collect::<Vec<_>>();
+    while !chars.is_empty() {
+        chars.pop();
+        let truncated = format!("{}…", chars.iter().collect::<String>().trim_end());
+        if text_width(font, size, &truncated) <= max_width {
+            return truncated;
+        }
+    }
+    String::new()
+}
+
+// The background is always opaque so the text can be blended straight into it
+fn draw_text(
+    pixmap: &mut Pixmap,
+    font: &FontArc,
+    size: f32,
+    text: &str,
+    x: f32,
+    baseline: f32,
+    colour: [u8; 3],
+) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let (width, height) = (pixmap.width() as i32, pixmap.height() as i32);
+    let pixels = pixmap.pixels_mut();
+    let mut caret = x;
+    let mut previous: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        previous = Some(id);
+        let glyph = id.with_scale_and_position(size, point(caret, baseline));
+        caret += scaled.h_advance(id);
+        // Characters missing from the font are skipped instead of drawn as boxes
+        if id.0 == 0 {
+            continue;
+        }
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= width || py >= height {
+                return;
+            }
+            let pixel = &mut pixels[(py * width + px) as usize];
+            let coverage = coverage.clamp(0.0, 1.0);
+            let blend = |src: u8, dst: u8| {
+                (src as f32 * coverage + dst as f32 * (1.0 - coverage)).round() as u8
+            };
+            if let Some(blended) = PremultipliedColorU8::from_rgba(
+                blend(colour[0], pixel.red()),
+                blend(colour[1], pixel.green()),
+                blend(colour[2], pixel.blue()),
+                255,
+            ) {
+                *pixel = blended;
+            }
+        });
+    }
+}
+
+fn draw_avatar(pixmap: &mut Pixmap, fonts: &Fonts, entry: &Entry, x: f32, y: f32) {
+    let radius = AVATAR / 2.0;
+    let Some(circle) = PathBuilder::from_circle(x + radius, y + radius, radius) else {
+        return;
+    };
+    match &entry.avatar {
+        Some(avatar) => {
+            let scale = AVATAR / avatar.width().max(1) as f32;
+            let paint = Paint {
+                shader: Pattern::new(
+                    avatar.as_ref(),
+                    SpreadMode::Pad,
+                    FilterQuality::Bicubic,
+                    1.0,
+                    Transform::from_scale(scale, scale).post_translate(x, y),
+                ),
+                anti_alias: true,
+                ..Default::default()
+            };
+            pixmap.fill_path(
+                &circle,
+                &paint,
+                FillRule::Winding,
+                Transform::identity(),
+                None,
+            );
+        }
+        None => {
+            let colour = PLACEHOLDERS[(entry.user.id.get() % PLACEHOLDERS.len() as u64) as usize];
+            pixmap.fill_path(
+                &circle,
+                &paint(colour),
+                FillRule::Winding,
+                Transform::identity(),
+                None,
+            );
+            let initial = entry
+                .user
+                .name
+                .chars()
+                .next()
+                .map(|c| c.to_uppercase().to_string())
+                .unwrap_or_default();
+            let width = text_width(&fonts.bold, 20.0, &initial);
+            draw_text(
+                pixmap,
+                &fonts.bold,
+                20.0,
+                &initial,
+                x + radius - width / 2.0,
+                y + radius + 7.0,
+                TEXT,
+            );
+        }
+    }
+}
+
+fn draw(entries: &[Entry], hidden: usize, days: u32) -> Result<Vec<u8>, Box<dyn Error>> {
+    let fonts = Fonts::load()?;
+    let height = HEADER + ROW * entries.len() as u32 + if hidden > 0 { FOOTER } else { 0 };
+    let mut pixmap = Pixmap::new(WIDTH, height).ok_or("Invalid leaderboard size")?;
+    pixmap.fill(Color::from_rgba8(
+        BACKGROUND[0],
+        BACKGROUND[1],
+        BACKGROUND[2],
+        255,
+    ));
+    let width = WIDTH as f32;
+    draw_text(
+        &mut pixmap,
+        &fonts.bold,
+        28.0,
+        "Leaderboard",
+        24.0,
+        46.0,
+        TEXT,
+    );
+    let month = clock().now().format("%B %Y").to_string();
+    draw_text(
+        &mut pixmap,
+        &fonts.regular,
+        18.0,
+        &month,
+        width - 24.0 - text_width(&fonts.regular, 18.0, &month),
+        44.0,
+        MUTED,
+    );
+    for (place, entry) in entries.iter().enumerate() {
+        let y = (HEADER + ROW * place as u32) as f32;
+        fill_rect(&mut pixmap, 0.0, y, width, ROW as f32, ROWS[place % 2]);
+        draw_text(
+            &mut pixmap,
+            &fonts.bold,
+            20.0,
+            &format!("#{}", place + 1),
+            24.0,
+            y + 39.0,
+            PODIUM.get(place).copied().unwrap_or(MUTED),
+        );
+        draw_avatar(&mut pixmap, &fonts, entry, 80.0, y + 10.0);
+        let name = truncate(&fonts.bold, 20.0, &entry.user.name, BAR_X - 164.0);
+        draw_text(&mut pixmap, &fonts.bold, 20.0, &name, 140.0, y + 39.0, TEXT);
+        let progress = (entry.monthly_record as f32 / days.max(1) as f32).min(1.0);
+        fill_rect(&mut pixmap, BAR_X, y + 20.0, BAR_WIDTH, 10.0, BAR);
+        fill_rect(
+            &mut pixmap,
+            BAR_X,
+            y + 20.0,
+            BAR_WIDTH * progress,
+            10.0,
+            PROGRESS,
+        );
+        draw_text(
+            &mut pixmap,
+            &fonts.regular,
+            13.0,
+            &format!("{}/{days} days this month", entry.monthly_record),
+            BAR_X,
+            y + 48.0,
+            MUTED,
+        );
+        let points = format!(
+            "{} {}",
+            entry.score,
+            if entry.score > 1 { "points" } else { "point" }
+        );
+        draw_text(
+            &mut pixmap,
+            &fonts.bold,
+            20.0,
+            &points,
+            width - 24.0 - text_width(&fonts.bold, 20.0, &points),
+            y + 39.0,
+            TEXT,
+        );
+    }
+    if hidden > 0 {
+        let y = (HEADER + ROW * entries.len() as u32) as f32;
+        draw_text(
+            &mut pixmap,
+            &fonts.regular,
+            16.0,
+            &format!("and {hidden} more, see /export for everyone"),
+            24.0,
+            y + 26.0,
+            MUTED,
+        );
+    }
+    Ok(pixmap.encode_png()?)
+}
+
+fn avatar_url(user: &User) -> Option<String> {
+    user.avatar.map(|hash| {
+        format!(
+            "https://cdn.discordapp.com/avatars/{}/{hash}.png?size=64",
+            user.id
+        )
+    })
+}
+
+#[cached(time = 86400, option = true)] // a day
+async fn fetch_avatar(url: String) -> Option<Pixmap> {
+    let response = Client::new()
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    let bytes = match response {
+        Ok(response) => response.bytes().await.ok()?,
+        Err(why) => {
+            warn!("Could not fetch avatar: {}", why.without_url());
+            return None;
+        }
+    };
+    Pixmap::decode_png(&bytes).ok()
+}
+
+async fn fetch_avatars(urls: Vec<String>) {
+    join_all(urls.into_iter().map(fetch_avatar)).await;
+}
+
+// Fetches the avatars of everyone shown on the leaderboard images with only a read lock held for
+// copying their URLs, so the images drawn later under the write lock find them in the cache
+pub async fn prefetch_avatars(data: &RwLock<TypeMap>) {
+    if !config().leaderboard.image {
+        return;
+    }
+    let urls = {
+        let data = data.read().await;
+        let Some(state) = data.get::<State>() else {
+            return;
+        };
+        state
+            .database
+            .iter()
+            .flat_map(|(guild_id, data)| {
+                Ranking::default()
+                    .ranked(&data.users)
+                    .into_iter()
+                    .take(MAX_ROWS)
+                    .filter_map(|(id, _)| avatar_url(state.guilds.get(guild_id)?.get(&id)?))
+            })
+            .collect::<Vec<_>>()
+    };
+    fetch_avatars(urls).await;
+}
+
+// Returns None when there is nothing to draw or drawing failed, the text leaderboard is sent instead.
+// It runs with the state locked, so avatars only come from the cache and missing ones are fetched in
+// the background for the next image
+pub async fn render_leaderboard(
+    users: &UserInfo,
+    guilds: &mut Guilds,
+    guild_id: &GuildId,
+) -> Option<Vec<u8>> {
+    if !config().leaderboard.image {
+        return None;
+    }
+    let mut entries = Ranking::default()
+        .ranked(users)
+        .into_iter()
+        .map(|(id, status)| {
+            let id = &id;
+            Entry {
+                user: get_user_from_id!(guilds, guild_id, id).clone(),
+                score: status.score,
+                monthly_record: status.monthly_record,
+                avatar: None,
+            }
+        })
+        .collect::<Vec<_>>();
+    if entries.is_empty() {
+        return None;
+    }
+    let hidden = entries.len().saturating_sub(MAX_ROWS);
+    entries.truncate(MAX_ROWS);
+    let mut missing = Vec::new();
+    {
+        let mut cache = FETCH_AVATAR.lock().await;
+        for entry in &mut entries {
+            if let Some(url) = avatar_url(&entry.user) {
+                entry.avatar = cache.cache_get(&url).cloned();
+                if entry.avatar.is_none() {
+                    missing.push(url);
+                }
+            }
+        }
+    }
+    if !missing.is_empty() {
+        spawn(fetch_avatars(missing));
+    }
+    let now = clock().now();
+    let days = days_in_month(now.year(), now.month()).map_err(|why| why.to_string());
+    let result = match days {
+        Ok(days) => {
+            spawn_blocking(move || draw(&entries, hidden, days).map_err(|why| why.to_string()))
+                .await
+                .unwrap_or_else(|why| Err(why.to_string()))
+        }
+        Err(why) => Err(why),
+    };
+    match result {
+        Ok(png) => Some(png),
+        Err(why) => {
+            warn!("Could not draw the leaderboard: {why}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const GUILD: GuildId = GuildId::new(1);
+
+    // Users 1 to n with the given scores
+    fn users(scores: &[usize]) -> (UserInfo, Guilds) {
+        let mut users = UserInfo::new();
+        let mut names = HashMap::new();
+        for (index, &score) in scores.iter().enumerate() {
+            let id = UserId::new(index as u64 + 1);
+            users.insert(
+                id,
+                Status {
+                    score,
+                    ..Default::default()
+                },
+            );
+            let mut user = User::default();
+            user.id = id;
+            user.name = format!("user{}", index + 1);
+            names.insert(id, user);
+        }
+        (users, Guilds::from([(GUILD, names)]))
+    }
+
+    #[test]
+    fn pages_are_clamped_to_the_leaderboard() {
+        let (users, mut guilds) = users(&(1..=25).rev().collect::<Vec<_>>());
+        let ranking = Ranking::default();
+        let first = construct_page(&users, &mut guilds, &GUILD, &ranking, 0, None).unwrap();
+        assert_eq!((first.page, first.pages), (0, 3));
+        assert_eq!(first.content.lines().count(), PAGE_SIZE);
+        assert!(first.content.starts_with("**1.** user1: 25 points"));
+        let last = construct_page(&users, &mut guilds, &GUILD, &ranking, 2, None).unwrap();
+        assert_eq!(last.content.lines().count(), 5);
+        assert!(last.content.contains("**25.** user25: 1 point,"));
+        // A page past the end shows the last one
+        let past = construct_page(&users, &mut guilds, &GUILD, &ranking, 7, None).unwrap();
+        assert_eq!(past.page, 2);
+        assert_eq!(past.content, last.content);
+        assert_eq!(past.footer(), "Page 3 of 3");
+    }
+
+    #[test]
+    fn me_shows_the_page_the_user_is_on() {
+        let (users, mut guilds) = users(&[0, 5, 3, 8, 1, 9, 2, 7, 4, 6, 11, 10]);
+        let ranking = Ranking::default();
+        // User 5 has the fewest points of the 11 with any, so is alone on the second page
+        let page = construct_page(
+            &users,
+            &mut guilds,
+            &GUILD,
+            &ranking,
+            0,
+            Some(UserId::new(5)),
+        )
+        .unwrap();
+        assert_eq!(page.page, 1);
+        assert_eq!(
+            page.content,
+            "**11.** user5: 1 point, 0 questions this month ⬅️\n"
+        );
+        // Users without points aren't on it
+        assert!(construct_page(
+            &users,
+            &mut guilds,
+            &GUILD,
+            &ranking,
+            0,
+            Some(UserId::new(1))
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn empty_leaderboards_have_one_page() {
+        let (users, mut guilds) = users(&[0, 0]);
+        let page =
+            construct_page(&users, &mut guilds, &GUILD, &Ranking::default(), 3, None).unwrap();
+        assert_eq!((page.page, page.pages), (0, 1));
+        assert_eq!(page.content, "No one is on this leaderboard yet");
+    }
+
+    #[test]
+    fn rankings_parse_every_argument() {
+        assert_eq!(Ranking::parse(""), Some(Ranking::default()));
+        assert_eq!(
+            Ranking::parse("by:votes role:<@&42> top:5"),
+            Some(Ranking {
+                by: SortBy::Votes,
+                role: Some(RoleId::new(42)),
+                top: Some(5),
+            })
+        );
+        // Buttons carry the role as a bare ID
+        assert_eq!(
+            Ranking::parse("role:42").unwrap().role,
+            Some(RoleId::new(42))
+        );
+        for invalid in [
+            "by:age",
+            "top:0",
+            "top:many",
+            "role:everyone",
+            "page:2",
+            "streak",
+        ] {
+            assert_eq!(Ranking::parse(invalid), None, "{invalid}");
+        }
+        let ranking = Ranking {
+            by: SortBy::Weekly,
+            role: Some(RoleId::new(7)),
+            top: Some(3),
+        };
+        assert_eq!(ranking.args(), "by:weekly role:7 top:3");
+        assert_eq!(Ranking::parse(&ranking.args()), Some(ranking));
+    }
+
+    #[test]
+    fn rankings_sort_by_the_metric_then_score() {
+        let (mut users, mut guilds) = users(&[5, 3, 8]);
+        users.get_mut(&UserId::new(1)).unwrap().streak = 2;
+        users.get_mut(&UserId::new(2)).unwrap().streak = 4;
+        users.get_mut(&UserId::new(3)).unwrap().streak = 2;
+        let by_streak = Ranking::parse("by:streak").unwrap();
+        let ranked = by_streak
+            .ranked(&users)
+            .into_iter()
+            .map(|(id, _)| id.get())
+            .collect::<Vec<_>>();
+        assert_eq!(ranked, [2, 3, 1]);
+        let top = Ranking::parse("by:streak top:2").unwrap();
+        let page = construct_page(&users, &mut guilds, &GUILD, &top, 0, None).unwrap();
+        assert_eq!(page.title, "The top 2 by streak");
+        assert_eq!(
+            page.content,
+            "**1.** user2: 4 day streak, 3 points\n**2.** user3: 2 day streak, 8 points\n"
+        );
+        // Nobody has votes, so nobody is ranked by them
+        let by_votes = Ranking::parse("by:votes").unwrap();
+        assert!(by_votes.ranked(&users).is_empty());
+    }
+}
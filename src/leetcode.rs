@@ -1,15 +1,34 @@
 use cached::proc_macro::cached;
 use rand::{prelude::SliceRandom, thread_rng};
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
-use serenity::all::{
-    AutoArchiveDuration, ChannelId, ChannelType, Colour, Context, CreateEmbed, CreateMessage,
-    CreateThread, EmbedMessageBuilding, Message, MessageBuilder,
+use serenity::all::{ChannelId, Colour, EmbedMessageBuilding, MessageBuilder, MessageId};
+use std::{error::Error, fs::read_to_string, sync::Arc, time::Instant};
+
+use crate::{
+    config, create_thread_from_message, metrics,
+    platform::{ChatPlatform, Embed},
 };
-use std::{error::Error, sync::Arc};
 
-use crate::create_thread_from_message;
+#[derive(
+    Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Hash, Debug, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Site {
+    #[default]
+    Com,
+    Cn,
+}
+
+impl Site {
+    pub fn url(&self) -> &'static str {
+        match self {
+            Site::Com => &config().leetcode.url,
+            Site::Cn => &config().leetcode.cn_url,
+        }
+    }
+}
 
 #[derive(Serialize)]
 struct GraphQLQuery {
@@ -40,7 +59,6 @@ struct Question {
     #[allow(unused)]
     status: Option<String>,
     title: String,
-    #[allow(unused)]
     title_slug: String,
     #[allow(unused)]
     has_video_solution: bool,
@@ -90,10 +108,122 @@ struct ProblemsetQuestionListResponse {
     data: ProblemsetQuestionListData,
 }
 
-const URL: &str = "https://leetcode.com";
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CnQuestion {
+    ac_rate: Option<f64>,
+    difficulty: String,
+    #[allow(unused)]
+    freq_bar: Option<f64>,
+    frontend_question_id: String,
+    #[allow(unused)]
+    is_favor: bool,
+    paid_only: bool,
+    #[allow(unused)]
+    status: Option<String>,
+    title: String,
+    title_cn: Option<String>,
+    title_slug: String,
+    #[allow(unused)]
+    has_video_solution: Option<bool>,
+    #[allow(unused)]
+    topic_tags: Vec<TopicTag>,
+}
+
+impl From<CnQuestion> for Question {
+    fn from(question: CnQuestion) -> Self {
+        Question {
+            ac_rate: question.ac_rate.map(|ac_rate| ac_rate * 100.0),
+            difficulty: question.difficulty,
+            freq_bar: question.freq_bar,
+            frontend_question_id: question.frontend_question_id,
+            is_favor: question.is_favor,
+            paid_only: question.paid_only,
+            status: question.status,
+            title: question
+                .title_cn
+                .filter(|title_cn| !title_cn.trim().is_empty())
+                .unwrap_or(question.title),
+            title_slug: question.title_slug,
+            has_video_solution: question.has_video_solution.unwrap_or_default(),
+            has_solution: false,
+            topic_tags: question.topic_tags,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TodayRecord {
+    #[allow(unused)]
+    date: String,
+    #[allow(unused)]
+    user_status: Option<String>,
+    question: CnQuestion,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TodayRecordData {
+    today_record: Vec<TodayRecord>,
+}
+
+#[derive(Deserialize)]
+struct TodayRecordResponse {
+    data: TodayRecordData,
+}
+
+#[derive(Deserialize)]
+struct CnProblemsetQuestionList {
+    #[allow(unused)]
+    total: u16,
+    questions: Vec<CnQuestion>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CnProblemsetQuestionListData {
+    problemset_question_list: CnProblemsetQuestionList,
+}
+
+#[derive(Deserialize)]
+struct CnProblemsetQuestionListResponse {
+    data: CnProblemsetQuestionListData,
+}
+
+async fn post_graphql<T: DeserializeOwned>(
+    site: Site,
+    operation: &str,
+    query: &str,
+    variables: serde_json::Value,
+) -> Result<T, Box<dyn Error + Send + Sync>> {
+    // Responses are read from {operation}.json instead when testing offline
+    if let Some(fixtures) = &config().leetcode.fixtures {
+        let contents = read_to_string(fixtures.join(format!("{operation}.json")))?;
+        return Ok(serde_json::from_str(&contents)?);
+    }
+    let gql_query = GraphQLQuery {
+        query: query.to_string(),
+        variables,
+    };
+    let start = Instant::now();
+    let result = async {
+        Client::new()
+            .post(format!("{}/graphql", site.url()))
+            .json(&gql_query)
+            .header("Content-Type", "application/json")
+            .send()
+            .await?
+            .json::<T>()
+            .await
+    }
+    .await;
+    metrics().record_leetcode_request(operation, start.elapsed(), result.is_ok());
+    Ok(result?)
+}
 
-async fn fetch_daily_question() -> Result<ActiveDailyCodingChallengeQuestionResponse, reqwest::Error>
-{
+async fn fetch_daily_question(
+) -> Result<ActiveDailyCodingChallengeQuestionResponse, Box<dyn Error + Send + Sync>> {
     let query = "
         query questionOfToday {
             activeDailyCodingChallengeQuestion {
@@ -121,32 +251,16 @@ async fn fetch_daily_question() -> Result<ActiveDailyCodingChallengeQuestionResp
             }
         }
     ";
-    let gql_query = GraphQLQuery {
-        query: query.to_string(),
-        variables: serde_json::Value::default(),
-    };
-    Client::new()
-        .post(format!("{URL}/graphql"))
-        .json(&gql_query)
-        .header("Content-Type", "application/json")
-        .send()
-        .await?
-        .json::<ActiveDailyCodingChallengeQuestionResponse>()
-        .await
+    post_graphql(Site::Com, "daily", query, serde_json::Value::default()).await
 }
 
-#[cached(time = 2500000)] // roughly a month
-async fn fetch_all_questions() -> Arc<Result<ProblemsetQuestionListResponse, reqwest::Error>> {
+async fn fetch_cn_daily_question() -> Result<TodayRecordResponse, Box<dyn Error + Send + Sync>> {
     let query = "
-        query problemsetQuestionList($categorySlug: String, $limit: Int, $skip: Int, $filters: QuestionListFilterInput) {
-            problemsetQuestionList: questionList(
-                categorySlug: $categorySlug
-                limit: $limit
-                skip: $skip
-                filters: $filters
-            ) {
-                total: totalNum
-                questions: data {
+        query questionOfToday {
+            todayRecord {
+                date
+                userStatus
+                question {
                     acRate
                     difficulty
                     freqBar
@@ -155,149 +269,293 @@ async fn fetch_all_questions() -> Arc<Result<ProblemsetQuestionListResponse, req
                     paidOnly: isPaidOnly
                     status
                     title
+                    titleCn: translatedTitle
                     titleSlug
+                    hasVideoSolution
                     topicTags {
                         name
                         id
                         slug
                     }
-                    hasSolution
-                    hasVideoSolution
                 }
             }
         }
     ";
-    let gql_query = GraphQLQuery {
-        query: query.to_string(),
-        variables: json!({"categorySlug": "", "skip": 0, "limit": 5000, "filters": {}}),
-    };
-    let res = Client::new()
-        .post(format!("{URL}/graphql"))
-        .json(&gql_query)
-        .header("Content-Type", "application/json")
-        .send()
-        .await;
-    Arc::new(match res {
-        Ok(response) => match response.json::<ProblemsetQuestionListResponse>().await {
-            Ok(parsed_response) => Ok(parsed_response),
-            Err(err) => Err(err),
-        },
-        Err(err) => Err(err),
+    post_graphql(Site::Cn, "cn_daily", query, serde_json::Value::default()).await
+}
+
+async fn fetch_site_daily_question(
+    site: Site,
+) -> Result<(Question, String), Box<dyn Error + Send + Sync>> {
+    Ok(match site {
+        Site::Com => {
+            let challenge = fetch_daily_question()
+                .await?
+                .data
+                .active_daily_coding_challenge_question;
+            (challenge.question, challenge.link)
+        }
+        Site::Cn => {
+            let record = fetch_cn_daily_question()
+                .await?
+                .data
+                .today_record
+                .into_iter()
+                .next()
+                .ok_or("No daily question today")?;
+            let link = format!("/problems/{}/", record.question.title_slug);
+            (record.question.into(), link)
+        }
+    })
+}
+
+#[cached(time = 2500000)] // roughly a month
+async fn fetch_all_questions(
+    site: Site,
+) -> Arc<Result<Vec<Question>, Box<dyn Error + Send + Sync>>> {
+    let variables = json!({"categorySlug": "", "skip": 0, "limit": 5000, "filters": {}});
+    Arc::new(match site {
+        Site::Com => {
+            let query = "
+                query problemsetQuestionList($categorySlug: String, $limit: Int, $skip: Int, $filters: QuestionListFilterInput) {
+                    problemsetQuestionList: questionList(
+                        categorySlug: $categorySlug
+                        limit: $limit
+                        skip: $skip
+                        filters: $filters
+                    ) {
+                        total: totalNum
+                        questions: data {
+                            acRate
+                            difficulty
+                            freqBar
+                            frontendQuestionId: questionFrontendId
+                            isFavor
+                            paidOnly: isPaidOnly
+                            status
+                            title
+                            titleSlug
+                            topicTags {
+                                name
+                                id
+                                slug
+                            }
+                            hasSolution
+                            hasVideoSolution
+                        }
+                    }
+                }
+            ";
+            post_graphql::<ProblemsetQuestionListResponse>(site, "problemset", query, variables)
+                .await
+                .map(|response| response.data.problemset_question_list.questions)
+        }
+        Site::Cn => {
+            let query = "
+                query problemsetQuestionList($categorySlug: String, $limit: Int, $skip: Int, $filters: QuestionListFilterInput) {
+                    problemsetQuestionList(
+                        categorySlug: $categorySlug
+                        limit: $limit
+                        skip: $skip
+                        filters: $filters
+                    ) {
+                        total
+                        questions {
+                            acRate
+                            difficulty
+                            freqBar
+                            frontendQuestionId
+                            isFavor
+                            paidOnly
+                            status
+                            title
+                            titleCn
+                            titleSlug
+                            topicTags {
+                                name
+                                id
+                                slug
+                            }
+                        }
+                    }
+                }
+            ";
+            post_graphql::<CnProblemsetQuestionListResponse>(
+                site,
+                "cn_problemset",
+                query,
+                variables,
+            )
+            .await
+            .map(|response| {
+                response
+                    .data
+                    .problemset_question_list
+                    .questions
+                    .into_iter()
+                    .map(Question::from)
+                    .collect()
+            })
+        }
     })
 }
 
-fn create_embed(question: &Question, link: String) -> CreateEmbed {
+fn create_embed(question: &Question, link: String, site: Site) -> Embed {
     let title = format!(
         "{}. {}",
         question.frontend_question_id,
         question.title.trim()
     );
-    let url = format!("{}{}", URL, link);
+    let url = format!("{}{}", site.url(), link);
     let colour = match question.difficulty.as_str() {
         "Easy" => Colour::DARK_GREEN,
         "Medium" => Colour::ORANGE,
         "Hard" => Colour::DARK_RED,
         _ => Colour::default(),
     };
-    CreateEmbed::default()
-        .title(title)
-        .url(url)
-        .colour(colour)
-        .field("Difficulty", question.difficulty.clone(), true)
-        .field(
-            "Acceptance Rate",
-            format!("{:.2}%", question.ac_rate.unwrap_or_default()),
-            true,
-        )
+    Embed {
+        title,
+        url,
+        colour,
+        fields: vec![
+            (String::from("Difficulty"), question.difficulty.clone()),
+            (
+                String::from("Acceptance Rate"),
+                format!("{:.2}%", question.ac_rate.unwrap_or_default()),
+            ),
+        ],
+    }
+}
+
+fn question_link(question: &Question, site: Site) -> String {
+    match site {
+        Site::Com => format!(
+            "/problems/{}",
+            question
+                .title
+                .trim()
+                .replace(' ', "-")
+                .chars()
+                .filter(|&ch| ch.is_alphanumeric() || ch == '-')
+                .collect::<String>()
+        ),
+        Site::Cn => format!("/problems/{}/", question.title_slug),
+    }
 }
 
 macro_rules! embed_message {
-    ($before:expr, $after:expr) => {
+    ($site:expr, $before:expr, $after:expr) => {
         MessageBuilder::new()
             .push(format!("{} ", $before))
-            .push_named_link("LeetCode", "https://leetcode.com/problemset")
+            .push_named_link("LeetCode", format!("{}/problemset", $site.url()))
             .push(format!(" {}", $after))
             .build()
     };
 }
 
+// Returns the message and embed along with the difficulty of the question
+async fn daily_question(site: Site) -> Result<(String, Embed, String), Box<dyn Error>> {
+    let (question, link) = fetch_site_daily_question(site)
+        .await
+        .map_err(|why| why as Box<dyn Error>)?;
+    Ok((
+        embed_message!(site, "Today's", "Daily question is out @everyone"),
+        create_embed(&question, link, site),
+        question.difficulty,
+    ))
+}
+
+pub async fn daily_question_embed(site: Site) -> Result<(String, Embed), Box<dyn Error>> {
+    let (content, embed, _) = daily_question(site).await?;
+    Ok((content, embed))
+}
+
 pub async fn send_leetcode_daily_question_message(
-    ctx: &Context,
+    platform: &dyn ChatPlatform,
     channel_id: ChannelId,
-) -> Result<Message, Box<dyn Error>> {
-    let challenge = fetch_daily_question()
-        .await?
-        .data
-        .active_daily_coding_challenge_question;
-    Ok(channel_id
-        .send_message(
-            ctx,
-            CreateMessage::new()
-                .content(embed_message!("Today's", "Daily question is out @everyone"))
-                .embed(create_embed(&challenge.question, challenge.link)),
+    site: Site,
+) -> Result<(MessageId, String), Box<dyn Error>> {
+    let (content, embed, difficulty) = daily_question(site).await?;
+    Ok((
+        platform.send_embed(channel_id, content, embed).await?,
+        difficulty,
+    ))
+}
+
+fn matches_filter(question: &Question, filter: &str) -> bool {
+    if filter == "free" || filter == "!paid" {
+        !question.paid_only
+    } else if filter == "paid" || filter == "!free" {
+        question.paid_only
+    } else if ["easy", "medium", "hard"].contains(&filter) {
+        question.difficulty.to_lowercase() == filter
+    } else if ["!easy", "!medium", "!hard"].contains(&filter) {
+        question.difficulty.to_lowercase() != filter.chars().skip(1).collect::<String>()
+    } else {
+        false
+    }
+}
+
+// Returns the title of the question along with its embed
+pub async fn random_question_embed(
+    site: Site,
+    filters: &[&str],
+) -> Result<Option<(String, Embed)>, Box<dyn Error>> {
+    let questions = fetch_all_questions(site).await;
+    let Ok(questions) = questions.as_ref() else {
+        return Err("Failed to fetch all questions".into());
+    };
+    let questions = questions
+        .iter()
+        .filter(|question| {
+            filters
+                .iter()
+                .all(|filter| matches_filter(question, filter))
+        })
+        .collect::<Vec<_>>();
+    Ok(questions.choose(&mut thread_rng()).map(|question| {
+        (
+            question.title.trim().to_string(),
+            create_embed(question, question_link(question, site), site),
         )
-        .await?)
+    }))
+}
+
+// Looks a question up by its number or slug
+pub async fn problem_embed(site: Site, id: &str) -> Result<Option<Embed>, Box<dyn Error>> {
+    let questions = fetch_all_questions(site).await;
+    let Ok(questions) = questions.as_ref() else {
+        return Err("Failed to fetch all questions".into());
+    };
+    Ok(questions
+        .iter()
+        .find(|question| question.frontend_question_id == id || question.title_slug == id)
+        .map(|question| create_embed(question, question_link(question, site), site)))
 }
 
 pub async fn send_random_leetcode_question_message(
-    ctx: &Context,
+    platform: &dyn ChatPlatform,
     channel_id: ChannelId,
     filters: Vec<&str>,
+    site: Site,
 ) -> Result<(), Box<dyn Error>> {
-    if let Ok(response) = fetch_all_questions().await.as_ref() {
-        let questions = response
-            .data
-            .problemset_question_list
-            .questions
-            .iter()
-            .filter(|question| {
-                filters.iter().all(|&filter| {
-                    if filter == "free" || filter == "!paid" {
-                        !question.paid_only
-                    } else if filter == "paid" || filter == "!free" {
-                        question.paid_only
-                    } else if ["easy", "medium", "hard"].contains(&filter) {
-                        question.difficulty.to_lowercase() == filter
-                    } else if ["!easy", "!medium", "!hard"].contains(&filter) {
-                        question.difficulty.to_lowercase()
-                            != filter.chars().skip(1).collect::<String>()
-                    } else {
-                        false
-                    }
-                })
-            })
-            .collect::<Vec<_>>();
-        let question = questions.choose(&mut thread_rng());
-        if let Some(question) = question {
-            let message_id = channel_id
-                .send_message(
-                    ctx,
-                    CreateMessage::new()
-                        .content(embed_message!("Here's a random", "question"))
-                        .embed(create_embed(
-                            question,
-                            format!(
-                                "/problems/{}",
-                                question
-                                    .title
-                                    .trim()
-                                    .replace(' ', "-")
-                                    .chars()
-                                    .filter(|&ch| ch.is_alphanumeric() || ch == '-')
-                                    .collect::<String>()
-                            ),
-                        )),
-                )
-                .await?
-                .id;
-            create_thread_from_message!(ctx, channel_id, message_id, question.title.trim());
-        } else {
-            channel_id
-                .say(ctx, "No question found that fits your requirement")
-                .await?;
-        }
-        Ok(())
+    let question = random_question_embed(site, &filters).await?;
+    if let Some((title, embed)) = question {
+        let message_id = platform
+            .send_embed(
+                channel_id,
+                embed_message!(site, "Here's a random", "question"),
+                embed,
+            )
+            .await?;
+        create_thread_from_message!(platform, channel_id, message_id, title);
     } else {
-        Err("Failed to fetch all questions".into())
+        platform
+            .say(
+                channel_id,
+                String::from("No question found that fits your requirement"),
+            )
+            .await?;
     }
+    Ok(())
 }
@@ -1,29 +1,79 @@
+mod badges;
+mod calendar;
+mod clock;
+mod config;
+mod db;
+mod discord;
+mod export;
 mod helper;
+mod history;
+mod http;
+mod leaderboard;
 mod leetcode;
+mod logger;
+mod matrix;
+mod memory;
 mod messages;
-use chrono::{Datelike, Month, TimeDelta, TimeZone, Utc, Weekday};
+mod metrics;
+mod platform;
+mod registry;
+mod roles;
+mod slack;
+mod telegram;
+mod webhook;
+use badges::{
+    award, award_top_voted, construct_awarded_message, construct_profile, Badge, EarnedBadge,
+    STREAK_DAYS,
+};
+use calendar::{days_in_month, iso_week};
+use chrono::{Datelike, Month, NaiveDate, TimeDelta, TimeZone, Utc};
+pub use clock::{clock, set_clock, Clock, SimulatedClock, SystemClock};
+pub use config::{
+    config, set_config, Backend, Config, Format, Level, Platform, WebhookConfig, WebhookKind,
+};
+pub use db::{
+    import_database, merge_guilds, read_database, set_score, show_database, validate_database,
+    write_database,
+};
+pub use discord::{initialise_guild, respond, setup, turn_page, vote, Discord};
+use export::Table;
+use history::{
+    construct_hall_of_fame, construct_standings, end_season, month_name, parse_month, snapshot,
+    MonthlySnapshot, SeasonRecord,
+};
+pub use http::{serve, set_platform};
+use leaderboard::{construct_page, filter_role, prefetch_avatars, render_leaderboard, Ranking};
+pub use leetcode::{daily_question_embed, problem_embed, random_question_embed, Site};
 use leetcode::{send_leetcode_daily_question_message, send_random_leetcode_question_message};
+pub use logger::init_logger;
+pub use matrix::{run_matrix, Matrix};
+pub use memory::{MemoryPlatform, Sent, Thread};
+pub use metrics::{metrics, Metrics};
+pub use platform::{ChatPlatform, Embed, Incoming, Page, PlatformResult};
 use regex::Regex;
+use roles::{
+    construct_role_rewards, parse_role, remove_role_reward, update_role_rewards, Reward, RoleReward,
+};
 use serde::{Deserialize, Serialize};
 use serenity::{
-    all::{
-        CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
-        CreateSelectMenuKind, CreateThread, EditMessage,
-    },
-    builder::CreateSelectMenu,
+    all::ShardManager,
     model::prelude::*,
     prelude::*,
     utils::{EmbedMessageBuilding, MessageBuilder},
 };
+pub use slack::{run_slack, Slack};
 use std::{
     cmp::Ordering,
     collections::HashMap,
     error::Error,
     fs::File,
     io::{Seek, SeekFrom, Write},
+    sync::Arc,
     time::Duration,
 };
-use tokio::time::sleep;
+pub use telegram::{run_telegram, Telegram};
+use tracing::{debug, error, info, instrument, warn};
+pub use webhook::{post_daily_question, run_webhooks};
 
 type Guilds = HashMap<GuildId, Users>;
 type Users = HashMap<UserId, User>;
@@ -39,6 +89,18 @@ pub struct Data {
     poll_id: Option<MessageId>,
     active_weekly: bool,
     active_daily: bool,
+    #[serde(default)]
+    last_rollover: Option<NaiveDate>,
+    #[serde(default)]
+    site: Site,
+    #[serde(default)]
+    history: Vec<MonthlySnapshot>,
+    #[serde(default)]
+    seasons: Vec<SeasonRecord>,
+    #[serde(default)]
+    difficulty: Option<String>,
+    #[serde(default)]
+    role_rewards: Vec<RoleReward>,
 }
 
 #[derive(Default, Serialize, Deserialize, Clone)]
@@ -49,10 +111,21 @@ struct Status {
     monthly_record: u32,
     days_missed: u32,
     score: usize,
+    // Scores from seasons that have ended, score only covers the current season
+    #[serde(default)]
+    archived_score: usize,
+    #[serde(default)]
+    streak: u32,
+    #[serde(default)]
+    badges: Vec<EarnedBadge>,
+    // Votes received over all time, never reset
+    #[serde(default)]
+    votes: usize,
 }
 
 pub struct SharedState {
     pub ready: bool,
+    pub shutting_down: bool,
     pub guilds: Guilds,
     pub file: File,
     pub database: Database,
@@ -64,9 +137,28 @@ impl TypeMapKey for State {
 }
 
 const CUSTOM_ID: &str = "favourite_submission";
+const PAGE_ID: &str = "scores_page";
+const ME_ID: &str = "scores_me";
+const COMMANDS: [&str; 15] = [
+    "/active",
+    "/channel",
+    "/daily",
+    "/export",
+    "/halloffame",
+    "/help",
+    "/poll",
+    "/profile",
+    "/random",
+    "/reset",
+    "/roles",
+    "/scores",
+    "/season",
+    "/site",
+    "/top",
+];
 const POLL_ERROR_MESSAGE: &str = "Poll message is not in this channel";
-const NUM_SECS_IN_AN_HOUR: u64 = chrono::Duration::minutes(60).num_seconds() as _;
-const NUM_MONTHS_IN_A_YEAR: u32 = 12;
+const ROLLOVER_RETRY: Duration = Duration::from_secs(60);
+const ROLLOVER_MAX_RETRY: Duration = Duration::from_secs(30 * 60);
 
 pub async fn save_to_database(ctx: Context) -> Result<(), Box<dyn Error>> {
     let mut data = ctx.data.write().await;
@@ -75,6 +167,23 @@ pub async fn save_to_database(ctx: Context) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+pub async fn shutdown(
+    data: Arc<RwLock<TypeMap>>,
+    shard_manager: Option<Arc<ShardManager>>,
+) -> Result<(), Box<dyn Error>> {
+    // Webhook mode runs without a database so there is nothing to flush
+    if let Some(state) = data.write().await.get_mut::<State>() {
+        state.shutting_down = true;
+        write_to_database!(state);
+        state.file.sync_all()?;
+        info!("Database flushed");
+    }
+    if let Some(shard_manager) = shard_manager {
+        shard_manager.shutdown_all().await;
+    }
+    Ok(())
+}
+
 fn construct_leaderboard<'a>(
     users: &UserInfo,
     guilds: &mut Guilds,
@@ -124,221 +233,487 @@ fn construct_leaderboard<'a>(
 }
 
 fn time_till_utc_midnight() -> Result<TimeDelta, Box<dyn Error>> {
+    let now = clock().now();
+    let daily = config().schedule.daily;
+    let date = if now.time() < daily {
+        now.date_naive()
+    } else {
+        now.date_naive().succ_opt().ok_or("Invalid date")?
+    };
     Ok(Utc
-        .from_utc_datetime(
-            &Utc::now()
-                .naive_utc()
-                .date()
-                .succ_opt()
-                .ok_or("Invalid date")?
-                .and_hms_opt(0, 1, 0)
-                .ok_or("Invalid time")?,
-        )
-        .signed_duration_since(Utc::now()))
+        .from_utc_datetime(&date.and_time(daily))
+        .signed_duration_since(now))
+}
+
+// The day of the latest daily question, which is still yesterday until the daily time comes around
+fn daily_date() -> NaiveDate {
+    let now = clock().now();
+    if now.time() < config().schedule.daily {
+        now.date_naive() - TimeDelta::days(1)
+    } else {
+        now.date_naive()
+    }
+}
+
+fn ordinal(place: usize) -> String {
+    let suffix = match (place % 10, place % 100) {
+        (1, 11) | (2, 12) | (3, 13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{place}{suffix}")
 }
 
 fn num_days_curr_month() -> Result<u32, Box<dyn Error>> {
-    let now = Utc::now();
-    let curr_month = now.month();
-    let this_month = Utc
-        .with_ymd_and_hms(now.year(), curr_month, 1, 0, 0, 0)
-        .unwrap();
-    let next_month = Utc
-        .with_ymd_and_hms(
-            now.year() + (curr_month / NUM_MONTHS_IN_A_YEAR) as i32,
-            (curr_month + 1) % NUM_MONTHS_IN_A_YEAR,
-            1,
-            0,
-            0,
-            0,
+    let now = clock().now();
+    days_in_month(now.year(), now.month())
+}
+
+fn rollover(
+    guilds: &mut Guilds,
+    guild_id: &GuildId,
+    data: &mut Data,
+    date: NaiveDate,
+    penalise: bool,
+    message: &mut MessageBuilder,
+) -> Result<(), Box<dyn Error>> {
+    let scoring = &config().scoring;
+    let mut penalties = 0;
+    let mut votes = HashMap::new();
+    if date.day0() == 0 {
+        if let Some(status) = data
+            .users
+            .values()
+            .max_by_key(|status| status.monthly_record)
+        {
+            let highest_monthly_record = status.monthly_record;
+            if highest_monthly_record > 0 {
+                message.push("Welcome to a new month! Last month ");
+                let last_month = date.pred_opt().ok_or("Invalid date")?;
+                for (user_id, status) in data.users.iter_mut().filter(|(_, monthly_record)| {
+                    monthly_record.monthly_record == highest_monthly_record
+                }) {
+                    message.mention(user_id);
+                    status.score += scoring.monthly_winner;
+                    if highest_monthly_record == last_month.day() {
+                        status.score += scoring.perfect_month;
+                    }
+                }
+                construct_reward_message!(
+                    message
+                        .push(" completed ")
+                        .push_bold(highest_monthly_record.to_string())
+                        .push(" questions which is the highest in this server!"),
+                    scoring.monthly_winner
+                );
+                if highest_monthly_record == last_month.day() {
+                    construct_badge_message!(
+                        message.push(format!(", and another {} points", scoring.perfect_month)),
+                        last_month
+                    );
+                } else {
+                    message.push_line("");
+                }
+                message.push_line("");
+            }
+        }
+        let snapshot = snapshot(data, date.pred_opt().ok_or("Invalid date")?);
+        data.history
+            .retain(|archived| archived.month != snapshot.month);
+        data.history.push(snapshot);
+    }
+    for user in data.users.values_mut() {
+        if date.day0() == 0 {
+            user.monthly_record = 0;
+        }
+        if let Some(voted_for) = user.voted_for {
+            votes
+                .entry(voted_for)
+                .and_modify(|votes| *votes += 1)
+                .or_insert(1);
+        }
+        if user.submitted.is_some() {
+            user.submitted = None;
+        } else if penalise {
+            penalties += 1;
+            user.days_missed += 1;
+            user.streak = 0;
+            user.score = user.score.saturating_sub(scoring.penalty);
+        }
+        user.voted_for = None;
+    }
+    message
+        .push("Yesterday ")
+        .push_line(if !penalise {
+            "there was no question while I was offline so no one lost any points".to_string()
+        } else if penalties > 0 {
+            format!(
+                "{penalties} {} did not complete the challenge 😭 each lost {} {} as a penalty",
+                if penalties > 1 { "people" } else { "person" },
+                scoring.penalty,
+                if scoring.penalty > 1 {
+                    "points"
+                } else {
+                    "point"
+                }
+            )
+        } else {
+            "everyone completed the challenge! Awesome job to start a new day!".to_string()
+        })
+        .push_line("\nThe number of votes received:");
+    let mut votes = votes.iter().collect::<Vec<_>>();
+    if votes.is_empty() {
+        message.push_line("No one voted 😞");
+    } else {
+        votes.sort_by(|a, b| b.1.cmp(a.1));
+        let most_votes = *votes[0].1;
+        let top_voted = votes
+            .iter()
+            .filter(|(_, &votes)| votes == most_votes)
+            .map(|(&user_id, _)| user_id)
+            .collect::<Vec<_>>();
+        for (place, (user_id, &votes)) in votes.into_iter().enumerate() {
+            let user = get_user_from_id!(guilds, guild_id, user_id);
+            let status = get_user_from_id!(data.users, *user_id);
+            status.score += votes;
+            status.votes += votes;
+            message
+                .push((place + 1).to_string())
+                .push(". ")
+                .mention(user)
+                .push(": ")
+                .push_bold(votes.to_string())
+                .push_line("");
+        }
+        let yesterday = date.pred_opt().ok_or("Invalid date")?;
+        award_top_voted(guilds, guild_id, data, &top_voted, yesterday, message);
+    }
+    if config().schedule.starts_season(date) {
+        end_season(guilds, guild_id, data, date, message)?;
+    }
+    Ok(())
+}
+
+pub async fn rollover_guilds(
+    platform: &dyn ChatPlatform,
+    state: &mut SharedState,
+) -> Result<(), Box<dyn Error>> {
+    let today = daily_date();
+    for (guild_id, data) in state.database.iter_mut() {
+        let last_rollover = data.last_rollover.unwrap_or(today);
+        if last_rollover >= today {
+            data.last_rollover = Some(today);
+            continue;
+        }
+        // Rolled over on a copy so a guild that fails is left as it was and retried on the next run
+        let mut rolled = data.clone();
+        match rollover_guild(
+            platform,
+            &mut state.guilds,
+            guild_id,
+            &mut rolled,
+            last_rollover,
+            today,
         )
-        .unwrap();
-    Ok(TryInto::<u32>::try_into(
-        next_month.signed_duration_since(this_month).num_days(),
-    )?)
+        .await
+        {
+            Ok(()) => {
+                rolled.last_rollover = Some(today);
+                *data = rolled;
+                metrics().reset_submissions_today(*guild_id);
+            }
+            Err(why) => error!(guild_id = guild_id.get(), "Error rolling over guild: {why}"),
+        }
+    }
+    write_to_database!(state);
+    metrics().record_scheduler_run("daily_rollover");
+    Ok(())
+}
+
+async fn rollover_guild(
+    platform: &dyn ChatPlatform,
+    guilds: &mut Guilds,
+    guild_id: &GuildId,
+    data: &mut Data,
+    last_rollover: NaiveDate,
+    today: NaiveDate,
+) -> Result<(), Box<dyn Error>> {
+    data.poll_id = None;
+    data.thread_id = None;
+    if !data.active_daily {
+        return Ok(());
+    }
+    let missed = last_rollover
+        .iter_days()
+        .skip(1)
+        .take_while(|&date| date <= today)
+        .collect::<Vec<_>>();
+    if missed.len() > 1 {
+        info!(
+            guild_id = guild_id.get(),
+            missed = missed.len(),
+            "Catching up on missed rollovers"
+        );
+    }
+    let mut message = MessageBuilder::new();
+    for (day, &date) in missed.iter().enumerate() {
+        if missed.len() > 1 {
+            message.push_bold_line(date.format("%d/%m/%Y").to_string());
+        }
+        // Only the first day had a question, there was no thread to submit to while the bot was down
+        rollover(guilds, guild_id, data, date, day == 0, &mut message)?;
+    }
+    send_daily_message_with_leaderboard!(platform, guilds, guild_id, data, message.push('\n'));
+    Ok(())
+}
+
+pub async fn remind_guilds(
+    platform: &dyn ChatPlatform,
+    state: &mut SharedState,
+) -> Result<(), Box<dyn Error>> {
+    for (guild_id, data) in state.database.iter_mut() {
+        if !data.active_daily {
+            continue;
+        }
+        // A guild that fails is skipped so the others still get their reminder
+        if let Err(why) = remind_guild(platform, &mut state.guilds, guild_id, data).await {
+            error!(guild_id = guild_id.get(), "Error reminding guild: {why}");
+        }
+    }
+    metrics().record_scheduler_run("daily_reminder");
+    Ok(())
+}
+
+async fn remind_guild(
+    platform: &dyn ChatPlatform,
+    guilds: &mut Guilds,
+    guild_id: &GuildId,
+    data: &mut Data,
+) -> Result<(), Box<dyn Error>> {
+    let reminder_minutes = config().schedule.reminder_minutes;
+    data.poll_id = Some(poll(platform, data, guilds, guild_id).await?);
+    send_message_with_leaderboard!(
+        platform,
+        guilds,
+        guild_id,
+        get_thread_from_guild!(data),
+        &data.users,
+        MessageBuilder::new().push_line(format!(
+            "{} left to make your submission for today's question if you haven't already\n",
+            if reminder_minutes == 60 {
+                String::from("An hour")
+            } else {
+                format!("{reminder_minutes} minutes")
+            }
+        ))
+    );
+    Ok(())
+}
+
+pub async fn update_roles(
+    platform: &dyn ChatPlatform,
+    state: &mut SharedState,
+) -> Result<(), Box<dyn Error>> {
+    for (guild_id, data) in state.database.iter_mut() {
+        update_role_rewards(platform, guild_id, data).await;
+    }
+    write_to_database!(state);
+    Ok(())
 }
 
-async fn initialise_guilds(
-    ctx: &Context,
+pub async fn initialise_guilds(
+    platform: &dyn ChatPlatform,
     guild_id: &GuildId,
     state: &mut SharedState,
 ) -> Result<(), Box<dyn Error>> {
-    let members = guild_id.members(&ctx.http, None, None).await?;
+    let members = platform.members(*guild_id).await?;
     state.guilds.insert(
         *guild_id,
         members
             .into_iter()
-            .filter_map(|member| {
-                let user = member.user;
-                if user.bot {
-                    None
-                } else {
-                    Some((user.id, user))
-                }
-            })
+            .map(|user| (user.id, user))
             .collect::<Users>(),
     );
     Ok(())
 }
 
-pub async fn setup(ctx: &Context, ready: Ready) -> Result<(), Box<dyn Error>> {
-    let mut data = ctx.data.write().await;
-    let state = get_shared_state!(data);
-    if state.ready {
-        Err("Already done setup".into())
-    } else {
-        state.ready = true;
-        let guilds = ready.guilds;
-        log!("Setting up guilds {guilds:?}");
-        for guild in guilds {
-            if state.database.contains_key(&guild.id) {
-                initialise_guilds(ctx, &guild.id, state).await?;
+fn pending_rollovers(state: &SharedState) -> bool {
+    let today = daily_date();
+    state.database.values().any(|data| {
+        data.last_rollover
+            .is_some_and(|last_rollover| last_rollover < today)
+    })
+}
+
+// Guilds whose rollover failed are retried with backoff until they succeed or the reminder is due
+async fn retry_rollovers(
+    platform: &dyn ChatPlatform,
+    data: &RwLock<TypeMap>,
+) -> Result<(), Box<dyn Error>> {
+    let reminder = i64::from(config().schedule.reminder_minutes) * 60;
+    let mut backoff = ROLLOVER_RETRY;
+    loop {
+        {
+            let mut data = data.write().await;
+            let state = get_shared_state!(data);
+            if !pending_rollovers(state) {
+                return Ok(());
             }
         }
-        Ok(())
+        if time_till_utc_midnight()?.num_seconds() - reminder < backoff.as_secs().try_into()? {
+            warn!("Giving up on failed rollovers until the next daily");
+            return Ok(());
+        }
+        warn!("Retrying failed rollovers in {backoff:?}");
+        clock().sleep(backoff).await;
+        prefetch_avatars(data).await;
+        {
+            let mut data = data.write().await;
+            let state = get_shared_state!(data);
+            rollover_guilds(platform, state).await?;
+            update_roles(platform, state).await?;
+        }
+        backoff = (backoff * 2).min(ROLLOVER_MAX_RETRY);
     }
 }
 
-pub async fn schedule_daily_question(ctx: &Context) -> Result<(), Box<dyn Error>> {
+pub async fn schedule_daily_question(
+    platform: &dyn ChatPlatform,
+    data: &RwLock<TypeMap>,
+) -> Result<(), Box<dyn Error>> {
     loop {
+        prefetch_avatars(data).await;
+        {
+            let mut data = data.write().await;
+            let state = get_shared_state!(data);
+            rollover_guilds(platform, state).await?;
+            update_roles(platform, state).await?;
+        }
+        retry_rollovers(platform, data).await?;
+
         let mut duration: u64 = time_till_utc_midnight()?.num_seconds().try_into()?;
-        log!("{duration} seconds until next daily");
-        if duration > NUM_SECS_IN_AN_HOUR {
-            sleep(Duration::from_secs(duration - NUM_SECS_IN_AN_HOUR)).await;
-            duration = NUM_SECS_IN_AN_HOUR;
-            let mut data = ctx.data.write().await;
+        debug!("{duration} seconds until next daily");
+        let reminder = u64::from(config().schedule.reminder_minutes) * 60;
+        if duration > reminder {
+            clock()
+                .sleep(Duration::from_secs(duration - reminder))
+                .await;
+            duration = reminder;
+            prefetch_avatars(data).await;
+            let mut data = data.write().await;
             let state = get_shared_state!(data);
+            remind_guilds(platform, state).await?;
+        }
+        clock().sleep(Duration::from_secs(duration)).await;
+    }
+}
 
-            for (guild_id, data) in state.database.iter_mut() {
-                if !data.active_daily {
-                    continue;
-                }
-                data.poll_id = Some(poll(ctx, data, &mut state.guilds, guild_id).await?.id);
-                if data.poll_id.is_some() {
-                    send_message_with_leaderboard!(
-                        ctx,
-                        &mut state.guilds,
-                        guild_id,
-                        get_thread_from_guild!(data),
-                        &data.users,
-                        MessageBuilder::new().push_line("An hour left to make your submission for today's question if you haven't already\n")
-                    );
-                }
-            }
+pub async fn start_weekly_contest(
+    platform: &dyn ChatPlatform,
+    state: &mut SharedState,
+) -> Result<(), Box<dyn Error>> {
+    for (guild_id, data) in state.database.iter_mut() {
+        if data.active_weekly {
+            let channel_id = get_channel_from_guild!(data);
+            let message_id = platform.say(channel_id,
+                MessageBuilder::new()
+                    .push_named_link("Weekly Contest", format!("{}/contest/", data.site.url()))
+                    .push(format!(" starting now! The first {} people to finish all 4 questions will get bonus points @everyone", config().scoring.contest_podium.len()))
+                    .build())
+                .await?;
+            create_thread_from_message!(
+                platform,
+                &mut state.guilds,
+                guild_id,
+                data,
+                MessageBuilder::new(),
+                channel_id,
+                message_id,
+                data.weekly_id,
+                // Threads have always been numbered from week 0
+                format!("Week {}", iso_week(clock().now().date_naive()).1 - 1)
+            );
         }
+        for user in data.users.values_mut() {
+            user.weekly_submissions = 0;
+        }
+    }
+    write_to_database!(state);
+    metrics().record_scheduler_run("weekly_contest_start");
+    Ok(())
+}
 
-        sleep(Duration::from_secs(duration)).await;
-        let mut data = ctx.data.write().await;
-        let state = get_shared_state!(data);
-        for (guild_id, data) in state.database.iter_mut() {
-            data.poll_id = None;
-            data.thread_id = None;
-            if !data.active_daily {
-                continue;
-            }
-            let mut message = MessageBuilder::new();
-            let mut penalties = 0;
-            let mut votes = HashMap::new();
-            if Utc::now().day0() == 0 {
-                if let Some(status) = data
-                    .users
-                    .values()
-                    .max_by_key(|status| status.monthly_record)
-                {
-                    let highest_monthly_record = status.monthly_record;
-                    if highest_monthly_record > 0 {
-                        message.push("Welcome to a new month! Last month ");
-                        let last_month =
-                            Utc::now().date_naive().pred_opt().ok_or("Invalid date")?;
-                        for (user_id, status) in
-                            data.users.iter_mut().filter(|(_, monthly_record)| {
-                                monthly_record.monthly_record == highest_monthly_record
-                            })
-                        {
-                            message.mention(user_id);
-                            status.score += 5;
-                            if highest_monthly_record == last_month.day() {
-                                status.score += 10;
-                            }
-                        }
-                        construct_reward_message!(
-                            message
-                                .push(" completed ")
-                                .push_bold(highest_monthly_record.to_string())
-                                .push(" questions which is the highest in this server!"),
-                            5
-                        );
-                        if highest_monthly_record == last_month.day() {
-                            construct_badge_message!(
-                                message.push(", and another 10 points"),
-                                last_month
-                            );
-                        } else {
-                            message.push_line("");
-                        }
-                        message.push_line("");
-                    }
-                }
-            }
-            for user in data.users.values_mut() {
-                if Utc::now().day0() == 0 {
-                    user.monthly_record = 0;
-                }
-                if let Some(voted_for) = user.voted_for {
-                    votes
-                        .entry(voted_for)
-                        .and_modify(|votes| *votes += 1)
-                        .or_insert(1);
-                }
-                if user.submitted.is_none() {
-                    penalties += 1;
-                    user.days_missed += 1;
-                    if user.score > 0 {
-                        user.score -= 1;
-                    }
-                } else {
-                    user.submitted = None;
-                }
-                user.voted_for = None;
-            }
-            message.push("Yesterday ")
-                .push_line(if penalties > 0 {
-                    format!("{penalties} {} did not complete the challenge 😭 each lost 1 point as a penalty", if penalties > 1 { "people" } else { "person" })
+pub async fn end_weekly_contest(
+    platform: &dyn ChatPlatform,
+    state: &mut SharedState,
+) -> Result<(), Box<dyn Error>> {
+    for (guild_id, guild) in state.database.iter_mut() {
+        let mut message = MessageBuilder::new();
+        message.push_line("Weekly contest just ended, the results are:");
+        let mut submissions = guild
+            .users
+            .iter()
+            .filter_map(|(user_id, status)| {
+                if status.weekly_submissions == 0 {
+                    None
                 } else {
-                    "everyone completed the challenge! Awesome job to start a new day!".to_string()
-                })
-                .push_line("\nThe number of votes received:");
-            let mut votes = votes.iter().collect::<Vec<_>>();
-            if votes.is_empty() {
-                message.push_line("No one voted 😞");
-            } else {
-                votes.sort_by(|a, b| b.1.cmp(a.1));
-                for (place, (user_id, &votes)) in votes.into_iter().enumerate() {
-                    let user = get_user_from_id!(state.guilds, guild_id, user_id);
-                    get_user_from_id!(data.users, *user_id).score += votes;
-                    message
-                        .push((place + 1).to_string())
-                        .push(". ")
-                        .mention(user)
-                        .push(": ")
-                        .push_bold(votes.to_string())
-                        .push_line("");
+                    Some((
+                        get_user_from_id!(state.guilds, guild_id, user_id).clone(),
+                        status.weekly_submissions,
+                    ))
                 }
+            })
+            .collect::<Vec<_>>();
+        if submissions.is_empty() {
+            message.push_line("No one participated in the contest 😩");
+        } else {
+            submissions.sort_by_key(|b| std::cmp::Reverse(b.1));
+            for (place, (user, submission)) in submissions.into_iter().enumerate() {
+                message
+                    .push((place + 1).to_string())
+                    .push(". ")
+                    .mention(&user)
+                    .push(" completed ")
+                    .push_bold(submission.to_string())
+                    .push_line(if submission > 1 {
+                        " questions"
+                    } else {
+                        " question"
+                    });
             }
-            send_daily_message_with_leaderboard!(ctx, state, guild_id, data, message.push('\n'));
         }
-        write_to_database!(state);
+        for user in guild.users.values_mut() {
+            user.weekly_submissions = 0;
+        }
+        if let Some(weekly_id) = guild.weekly_id {
+            send_message_with_leaderboard!(
+                platform,
+                &mut state.guilds,
+                guild_id,
+                weekly_id,
+                &guild.users,
+                message.push_line("")
+            );
+        }
+        guild.weekly_id = None;
     }
+    write_to_database!(state);
+    metrics().record_scheduler_run("weekly_contest_end");
+    Ok(())
 }
 
-pub async fn schedule_weekly_contest(ctx: &Context) -> Result<(), Box<dyn Error>> {
+pub async fn schedule_weekly_contest(
+    platform: &dyn ChatPlatform,
+    data: &RwLock<TypeMap>,
+) -> Result<(), Box<dyn Error>> {
     loop {
-        let now = Utc::now();
-        let num_days_from_sunday: i64 = Weekday::Sun.days_since(now.weekday()).into();
-        let same_day_until_contest_start = Utc
-            .with_ymd_and_hms(now.year(), now.month(), now.day(), 2, 30, 0)
-            .unwrap()
-            .time()
+        let now = clock().now();
+        let schedule = &config().schedule;
+        let num_days_from_sunday: i64 = schedule.contest_day.days_since(now.weekday()).into();
+        let same_day_until_contest_start = schedule
+            .contest_start
             .signed_duration_since(now.time())
             .num_seconds();
         let duration = Duration::from_secs(
@@ -353,117 +728,71 @@ pub async fn schedule_weekly_contest(ctx: &Context) -> Result<(), Box<dyn Error>
             } + same_day_until_contest_start)
                 .try_into()?,
         );
-        log!("{num_days_from_sunday} days / {duration:?} until next contest");
-        sleep(duration).await;
+        debug!("{num_days_from_sunday} days / {duration:?} until next contest");
+        clock().sleep(duration).await;
+        prefetch_avatars(data).await;
         {
-            let mut data = ctx.data.write().await;
+            let mut data = data.write().await;
             let state = get_shared_state!(data);
-            for (guild_id, data) in state.database.iter_mut() {
-                if data.active_weekly {
-                    let channel_id = get_channel_from_guild!(data);
-                    let message_id = channel_id.say(ctx.clone(),
-                        MessageBuilder::new()
-                            .push_named_link("Weekly Contest", "https://leetcode.com/contest/")
-                            .push(" starting now! The first 3 people to finish all 4 questions will get bonus points @everyone")
-                            .build())
-                        .await?
-                        .id;
-                    create_thread_from_message!(
-                        ctx,
-                        state,
-                        guild_id,
-                        data,
-                        MessageBuilder::new(),
-                        channel_id,
-                        message_id,
-                        data.weekly_id,
-                        format!("Week {}", Utc::now().iso_week().week0())
-                    );
-                }
-                for user in data.users.values_mut() {
-                    user.weekly_submissions = 0;
-                }
-            }
-            write_to_database!(state);
+            start_weekly_contest(platform, state).await?;
         }
-        sleep(Duration::from_secs(
-            chrono::Duration::minutes(90).num_seconds().try_into()?,
-        ))
-        .await;
-        let mut data = ctx.data.write().await;
+        clock()
+            .sleep(Duration::from_secs(
+                chrono::Duration::minutes(schedule.contest_minutes)
+                    .num_seconds()
+                    .try_into()?,
+            ))
+            .await;
+        prefetch_avatars(data).await;
+        let mut data = data.write().await;
         let state = get_shared_state!(data);
-        for (guild_id, guild) in state.database.iter_mut() {
-            let mut message = MessageBuilder::new();
-            message.push_line("Weekly contest just ended, the results are:");
-            let mut submissions = guild
-                .users
-                .iter()
-                .filter_map(|(user_id, status)| {
-                    if status.weekly_submissions == 0 {
-                        None
-                    } else {
-                        Some((
-                            get_user_from_id!(state.guilds, guild_id, user_id).clone(),
-                            status.weekly_submissions,
-                        ))
-                    }
-                })
-                .collect::<Vec<_>>();
-            if submissions.is_empty() {
-                message.push_line("No one participated in the contest 😩");
-            } else {
-                submissions.sort_by(|a, b| b.1.cmp(&a.1));
-                for (place, (user, submission)) in submissions.into_iter().enumerate() {
-                    message
-                        .push((place + 1).to_string())
-                        .push(". ")
-                        .mention(&user)
-                        .push(" completed ")
-                        .push_bold(submission.to_string())
-                        .push_line(if submission > 1 {
-                            " questions"
-                        } else {
-                            " question"
-                        });
-                }
-            }
-            for user in guild.users.values_mut() {
-                user.weekly_submissions = 0;
-            }
-            if let Some(weekly_id) = guild.weekly_id {
-                send_message_with_leaderboard!(
-                    ctx,
-                    &mut state.guilds,
-                    guild_id,
-                    weekly_id,
-                    &guild.users,
-                    message.push_line("")
-                );
-            }
-            guild.weekly_id = None;
-        }
-        write_to_database!(state);
+        end_weekly_contest(platform, state).await?;
     }
 }
 
-pub async fn respond(
-    ctx: &Context,
-    msg: Message,
-    current_user_id: UserId,
+#[instrument(skip_all, fields(
+    guild_id = msg.guild_id.map(GuildId::get),
+    user_id = msg.author.get(),
+    command = msg.content.split(' ').next().filter(|command| command.starts_with('/')),
+))]
+pub async fn handle_message(
+    platform: &dyn ChatPlatform,
+    state: &mut SharedState,
+    msg: Incoming,
 ) -> Result<(), Box<dyn Error>> {
+    let current_user_id = platform.current_user();
     if let Some(guild_id) = &msg.guild_id {
-        let mut data = ctx.data.write().await;
-        let state = get_shared_state!(data);
-        let user_id = &msg.author.id;
+        let user_id = &msg.author;
         let data = get_guild_from_id!(state, guild_id);
         let channel = get_channel_from_guild!(data);
         let code_block = Regex::new(r"(?s)```.+```")?;
         let mut message = MessageBuilder::new();
-        if msg.content.starts_with("/active") {
-            let args = msg.content.split(' ').collect::<Vec<&str>>();
-            msg.channel_id
+        let args = msg.content.split(' ').collect::<Vec<&str>>();
+        let admin_only = matches!(
+            args.as_slice(),
+            ["/reset"]
+                | ["/daily"]
+                | ["/active", _, "toggle"]
+                | ["/channel", _]
+                | ["/site", _]
+                | ["/roles", "add" | "remove", ..]
+        );
+        if let Some(&command) = args.first().filter(|command| COMMANDS.contains(command)) {
+            metrics().record_command(command);
+        } else if code_block.is_match(&msg.content) {
+            metrics().record_command("submission");
+        }
+        if admin_only && !config().is_admin(&platform.native_user_id(*user_id)) {
+            platform
+                .say(
+                    msg.channel_id,
+                    String::from("Only admins can run this command"),
+                )
+                .await?;
+        } else if msg.content.starts_with("/active") {
+            platform
                 .say(
-                    &ctx.http,
+                    msg.channel_id,
                     (if let Some(message_builder) = match args.len() {
                         1 => {
                             if msg.content == "/active" {
@@ -504,8 +833,8 @@ pub async fn respond(
                                 *active = !*active;
                                 if args[1] == "daily" && *active && data.thread_id.is_none() {
                                     send_daily_message_with_leaderboard!(
-                                        ctx,
-                                        state,
+                                        platform,
+                                        &mut state.guilds,
                                         guild_id,
                                         data,
                                         MessageBuilder::new()
@@ -535,28 +864,113 @@ pub async fn respond(
                 .await?;
         } else if msg.content == "/help" {
             send_help_message!(
-                ctx,
+                platform,
                 message,
                 current_user_id,
                 msg.channel_id,
                 channel,
-                data.thread_id
+                data.thread_id,
+                data.site
             );
         } else if msg.content == "/reset" {
             let channel_id = data.channel_id;
+            let site = data.site;
+            let role_rewards = std::mem::take(&mut data.role_rewards);
             **data = default_data(data.users.keys().copied().collect::<Vec<_>>());
             data.channel_id = channel_id;
-            msg.channel_id.say(ctx, "Database has been reset").await?;
+            data.site = site;
+            data.role_rewards = role_rewards;
+            platform
+                .say(msg.channel_id, String::from("Database has been reset"))
+                .await?;
         } else if msg.content == "/daily" {
             for status in data.users.values_mut() {
                 status.submitted = None;
             }
-            send_daily_message_with_leaderboard!(ctx, state, guild_id, data, message);
+            send_daily_message_with_leaderboard!(
+                platform,
+                &mut state.guilds,
+                guild_id,
+                data,
+                message
+            );
+        } else if msg.content.starts_with("/site") {
+            match args.as_slice() {
+                ["/site"] => {
+                    message
+                        .push("This server is using ")
+                        .push_named_link(data.site.url(), data.site.url());
+                }
+                ["/site", site @ ("com" | "cn")] => {
+                    data.site = if *site == "cn" { Site::Cn } else { Site::Com };
+                    message
+                        .push("Successfully set site to be ")
+                        .push_named_link(data.site.url(), data.site.url())
+                        .push(", the new daily question will be posted from there");
+                }
+                _ => {
+                    message
+                        .push("Usage:")
+                        .push_codeblock("/site [com|cn]", None);
+                }
+            }
+            platform.say(msg.channel_id, message.build()).await?;
+        } else if msg.content.starts_with("/roles") {
+            match args.as_slice() {
+                ["/roles"] => {
+                    construct_role_rewards(data, &mut message);
+                }
+                ["/roles", "add", role, kind, value] => {
+                    match (parse_role(role), Reward::parse(kind, value)) {
+                        (Some(role_id), Some(reward)) => {
+                            data.role_rewards
+                                .retain(|role_reward| role_reward.role_id != role_id);
+                            data.role_rewards.push(RoleReward {
+                                role_id,
+                                reward,
+                                holders: Vec::new(),
+                            });
+                            update_role_rewards(platform, guild_id, data).await;
+                            message
+                                .push("Successfully set up ")
+                                .role(role_id)
+                                .push(", it will be updated after every daily rollover");
+                        }
+                        _ => {
+                            message.push("Usage:").push_codeblock(
+                                "/roles add @role [rank number|streak days|badge name]",
+                                None,
+                            );
+                        }
+                    }
+                }
+                ["/roles", "remove", role] => match parse_role(role) {
+                    Some(role_id)
+                        if remove_role_reward(platform, guild_id, data, role_id).await =>
+                    {
+                        message
+                            .push("Successfully removed ")
+                            .role(role_id)
+                            .push(" from the role rewards");
+                    }
+                    _ => {
+                        message.push("This role is not a role reward");
+                    }
+                },
+                _ => {
+                    message.push("Usage:").push_codeblock(
+                        "/roles [add @role [rank number|streak days|badge name]|remove @role]",
+                        None,
+                    );
+                }
+            }
+            platform.say(msg.channel_id, message.build()).await?;
         } else if msg.content.starts_with("/random") {
             send_random_leetcode_question_message(
-                ctx,
+                platform,
                 msg.channel_id,
                 msg.content.split(' ').skip(1).collect::<Vec<_>>(),
+                data.site,
             )
             .await?;
         } else if msg.content.starts_with("/top") {
@@ -607,37 +1021,195 @@ pub async fn respond(
             if !has_score {
                 message.push("No one has done any questions yet");
             }
-            msg.channel_id.say(ctx, message.build()).await?;
-        } else if msg.content == "/scores" {
-            send_message_with_leaderboard!(
-                ctx,
-                &mut state.guilds,
-                guild_id,
-                msg.channel_id,
-                &data.users,
-                message
-            );
+            platform.say(msg.channel_id, message.build()).await?;
+        } else if msg.content.starts_with("/scores") {
+            let mut ranking = Ranking::default();
+            let mut page = Some(0);
+            let mut me = None;
+            let mut valid = true;
+            for &arg in args.iter().skip(1) {
+                if arg == "me" {
+                    me = Some(*user_id);
+                } else if let Ok(number) = arg.parse::<usize>() {
+                    page = number.checked_sub(1);
+                } else {
+                    valid &= ranking.parse_arg(arg);
+                }
+            }
+            let page = match page.filter(|_| valid) {
+                Some(page) => {
+                    let users = filter_role(platform, guild_id, &data.users, &ranking).await?;
+                    Some(construct_page(
+                        &users,
+                        &mut state.guilds,
+                        guild_id,
+                        &ranking,
+                        page,
+                        me,
+                    ))
+                }
+                None => None,
+            };
+            match page {
+                Some(Some(page)) => {
+                    platform.send_page(msg.channel_id, page).await?;
+                }
+                Some(None) => {
+                    platform
+                        .say(
+                            msg.channel_id,
+                            String::from("You aren't on this leaderboard yet"),
+                        )
+                        .await?;
+                }
+                None => {
+                    platform
+                        .say(
+                            msg.channel_id,
+                            message
+                                .push("Usage:")
+                                .push_codeblock(
+                                    "/scores [page|me] [by:score|monthly|streak|votes|weekly] [role:@role] [top:N]",
+                                    None,
+                                )
+                                .build(),
+                        )
+                        .await?;
+                }
+            }
+        } else if msg.content.starts_with("/export") {
+            let users = state.guilds.get(guild_id);
+            let table = match args.as_slice() {
+                ["/export"] | ["/export", "scores", ..] => Some(Table::scores(data, users)),
+                ["/export", "monthly", ..] => Some(Table::monthly(data, users)),
+                ["/export", "history", ..] if !data.history.is_empty() => {
+                    Some(Table::history(data, users))
+                }
+                _ => None,
+            };
+            match (table, args.get(2).copied().unwrap_or("csv")) {
+                (Some(table), format @ ("csv" | "json")) if args.len() <= 3 => {
+                    let contents = if format == "csv" {
+                        table.csv()
+                    } else {
+                        table.json()?
+                    };
+                    platform
+                        .send_file(
+                            msg.channel_id,
+                            String::from("Here is the export you asked for"),
+                            table.file_name(format),
+                            contents.into_bytes(),
+                        )
+                        .await?;
+                }
+                _ if args.get(1) == Some(&"history") && data.history.is_empty() => {
+                    platform
+                        .say(
+                            msg.channel_id,
+                            String::from(
+                                "There is no history to export yet, the standings are saved at the end of each month",
+                            ),
+                        )
+                        .await?;
+                }
+                _ => {
+                    message
+                        .push("Usage:")
+                        .push_codeblock("/export [scores|history|monthly] [csv|json]", None);
+                    platform.say(msg.channel_id, message.build()).await?;
+                }
+            }
+        } else if msg.content.starts_with("/profile") {
+            let profile_id = match args.as_slice() {
+                ["/profile"] => Some(*user_id),
+                ["/profile", mention] => mention
+                    .trim_start_matches("<@")
+                    .trim_start_matches('!')
+                    .trim_end_matches('>')
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|&id| id > 0)
+                    .map(UserId::new),
+                _ => None,
+            };
+            match profile_id {
+                Some(profile_id) => {
+                    let profile_id = &profile_id;
+                    let user = get_user_from_id!(state.guilds, guild_id, profile_id).clone();
+                    match data.users.get(profile_id) {
+                        Some(status) => {
+                            construct_profile(&user, status, &mut message);
+                        }
+                        None => {
+                            message.push("This user isn't taking part in the daily challenge");
+                        }
+                    }
+                }
+                None => {
+                    message
+                        .push("Usage:")
+                        .push_codeblock("/profile [@user]", None);
+                }
+            }
+            platform.say(msg.channel_id, message.build()).await?;
+        } else if msg.content == "/halloffame" {
+            construct_hall_of_fame(data, &mut state.guilds, guild_id, &mut message)?;
+            platform.say(msg.channel_id, message.build()).await?;
+        } else if msg.content.starts_with("/season") {
+            let month = match args.as_slice() {
+                ["/season"] => Ok(data.history.last()),
+                ["/season", month] if parse_month(month).is_some() => Ok(data
+                    .history
+                    .iter()
+                    .find(|archived| archived.month == *month)),
+                _ => Err(()),
+            };
+            match month {
+                Ok(Some(archived)) => {
+                    construct_standings(archived, &mut state.guilds, guild_id, &mut message)?;
+                }
+                Ok(None) if data.history.is_empty() => {
+                    message.push("No months have been archived yet, the standings are saved at the end of each month");
+                }
+                Ok(None) => {
+                    message
+                        .push(format!(
+                            "No standings were saved for {}, the archived months are ",
+                            month_name(args[1])?
+                        ))
+                        .push(
+                            data.history
+                                .iter()
+                                .map(|archived| archived.month.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                        );
+                }
+                Err(()) => {
+                    message
+                        .push("Usage:")
+                        .push_codeblock("/season [YYYY-MM]", None);
+                }
+            }
+            platform.say(msg.channel_id, message.build()).await?;
         } else if msg.content.starts_with("/channel") {
-            let channel_id = msg.content.split(' ').last().ok_or("Empty message")?;
+            let channel_id = msg.content.split(' ').next_back().ok_or("Empty message")?;
             if let Ok(channel_id) = channel_id.parse::<u64>() {
                 let channel_id = ChannelId::new(channel_id);
-                if let Ok(Channel::Guild(channel)) = channel_id.to_channel(&ctx.http).await {
-                    if channel.kind != ChannelType::Text {
-                        send_invalid_channel_id_message!(ctx, msg);
-                    } else {
-                        message
-                            .push("Successfully set channel to be ")
-                            .channel(channel_id);
-                        msg.channel_id.say(&ctx.http, message.build()).await?;
-                        data.channel_id = Some(channel_id);
-                    }
+                if platform.is_text_channel(channel_id).await {
+                    message
+                        .push("Successfully set channel to be ")
+                        .channel(channel_id);
+                    platform.say(msg.channel_id, message.build()).await?;
+                    data.channel_id = Some(channel_id);
                 } else {
-                    send_invalid_channel_id_message!(ctx, msg);
+                    send_invalid_channel_id_message!(platform, msg);
                 }
             } else if msg.channel_id != channel {
-                msg.channel_id
+                platform
                     .say(
-                        &ctx.http,
+                        msg.channel_id,
                         construct_channel_message!(
                             message,
                             current_user_id,
@@ -648,33 +1220,26 @@ pub async fn respond(
                     )
                     .await?;
             } else {
-                send_channel_usage_message!(ctx, msg.channel_id);
+                send_channel_usage_message!(platform, msg.channel_id);
             }
         } else if code_block.is_match(&msg.content) {
             if data.active_daily && msg.channel_id == data.thread_id.unwrap_or_default() {
                 let user = get_user_from_id!(data.users, *user_id);
                 if user.submitted.is_none() {
-                    user.submitted = Some(msg.link());
-                    let hour = time_till_utc_midnight()?.num_hours();
-                    let mut score: usize = if hour == 23 {
-                        5
-                    } else if hour >= 21 {
-                        4
-                    } else if hour >= 16 {
-                        3
-                    } else if hour >= 8 {
-                        2
-                    } else {
-                        1
-                    };
+                    user.submitted = Some(msg.link.clone());
+                    metrics().record_submission(*guild_id);
+                    let scoring = &config().scoring;
+                    let mut score =
+                        scoring.submission_points(time_till_utc_midnight()?.num_hours());
+                    let comeback = user.days_missed > scoring.comeback_days;
                     user.monthly_record += 1;
                     construct_summary_message!(
                         construct_reward_message!(
                             construct_congrats_message!(message, state, guild_id, user_id).push(
                                 format!(
                                     "completing today's challenge{}!",
-                                    if user.days_missed > 7 {
-                                        score += 5;
+                                    if user.days_missed > scoring.comeback_days {
+                                        score += scoring.comeback_bonus;
                                         user.score += score;
                                         format!(
                                             " and welcome back after missing it for {} days",
@@ -691,9 +1256,29 @@ pub async fn respond(
                         user
                     );
                     user.days_missed = 0;
+                    user.streak += 1;
+                    let mut badges = Vec::new();
                     if user.monthly_record == num_days_curr_month()? {
-                        construct_badge_message!(message.push("Great job"), Utc::now());
+                        construct_badge_message!(message.push("Great job"), clock().now());
+                        badges.push(Badge::PerfectMonth);
+                    }
+                    if comeback {
+                        badges.push(Badge::Comeback);
+                    }
+                    if data
+                        .difficulty
+                        .as_ref()
+                        .is_some_and(|difficulty| difficulty.eq_ignore_ascii_case("hard"))
+                    {
+                        badges.push(Badge::FirstHard);
                     }
+                    if user.streak >= STREAK_DAYS {
+                        badges.push(Badge::Streak);
+                    }
+                    construct_awarded_message(
+                        &mut message,
+                        &award(user, &badges, clock().now().date_naive()),
+                    );
                     let users_not_yet_completed = data
                         .users
                         .iter()
@@ -706,15 +1291,11 @@ pub async fn respond(
                         })
                         .collect::<Vec<_>>();
                     if let Some(poll_id) = data.poll_id {
-                        msg.channel_id
-                            .edit_message(
-                                &ctx.http,
+                        platform
+                            .edit(
+                                msg.channel_id,
                                 poll_id,
-                                EditMessage::new().content(build_submission_message(
-                                    data,
-                                    &mut state.guilds,
-                                    guild_id,
-                                )),
+                                build_submission_message(data, &mut state.guilds, guild_id),
                             )
                             .await?;
                     }
@@ -722,33 +1303,34 @@ pub async fn respond(
                         message
                             .push("Everyone has finished today's challenge, let's Grow Together!");
                     }
-                    data.poll_id = Some(poll(ctx, data, &mut state.guilds, guild_id).await?.id);
-                    msg.channel_id.say(&ctx.http, message.build()).await?;
+                    data.poll_id = Some(poll(platform, data, &mut state.guilds, guild_id).await?);
+                    platform.say(msg.channel_id, message.build()).await?;
                 }
             } else if data.active_weekly {
                 if let Some(weekly_id) = data
                     .weekly_id
                     .filter(|&weekly_id| weekly_id == msg.channel_id)
                 {
-                    let (reward, place) = match data
+                    let scoring = &config().scoring;
+                    let finished = data
                         .users
                         .values()
                         .filter(|user| user.weekly_submissions == 4)
-                        .count()
-                    {
-                        0 => (4, "1st"),
-                        1 => (3, "2nd"),
-                        2 => (2, "3rd"),
-                        _ => (1, "after top 3"),
+                        .count();
+                    let reward = scoring.contest_points(finished);
+                    let place = if finished < scoring.contest_podium.len() {
+                        ordinal(finished + 1)
+                    } else {
+                        format!("after top {}", scoring.contest_podium.len())
                     };
                     let user = get_user_from_id!(data.users, *user_id);
                     if user.weekly_submissions < 4 {
                         user.weekly_submissions += 1;
                         let (score, result, bold_text, end) = if user.weekly_submissions == 4 {
-                            (reward, "coming ", String::from(place), "")
+                            (reward, "coming ", place, "")
                         } else {
                             (
-                                1,
+                                scoring.contest_question,
                                 "finishing question ",
                                 user.weekly_submissions.to_string(),
                                 "/4",
@@ -763,8 +1345,19 @@ pub async fn respond(
                             score
                         )
                         .push(". Your current score is ")
-                        .push_bold(user.score.to_string());
-                        weekly_id.say(&ctx.http, message.build()).await?;
+                        .push_bold(user.score.to_string())
+                        .push_line("");
+                        if user.weekly_submissions == 4 {
+                            construct_awarded_message(
+                                &mut message,
+                                &award(
+                                    user,
+                                    &[Badge::ContestFullSolve],
+                                    clock().now().date_naive(),
+                                ),
+                            );
+                        }
+                        platform.say(weekly_id, message.build()).await?;
                     }
                 }
             }
@@ -797,15 +1390,18 @@ pub async fn respond(
                     } else {
                         return Ok(());
                     }
-                    channel.say(&ctx.http, message.build()).await?;
+                    platform.say(channel, message.build()).await?;
                 }
             } else if msg.content == "/poll" && msg.channel_id == thread && data.active_daily {
-                data.poll_id = Some(poll(ctx, data, &mut state.guilds, guild_id).await?.id);
+                data.poll_id = Some(poll(platform, data, &mut state.guilds, guild_id).await?);
             }
         }
     } else {
-        msg.channel_id
-            .say(&ctx.http, "Please don't slide into my dm 😜")
+        platform
+            .say(
+                msg.channel_id,
+                String::from("Please don't slide into my dm 😜"),
+            )
             .await?;
     }
     Ok(())
@@ -825,93 +1421,139 @@ fn build_submission_message(guild: &Data, guilds: &mut Guilds, guild_id: &GuildI
 }
 
 async fn poll(
-    ctx: &Context,
+    platform: &dyn ChatPlatform,
     guild: &Data,
     guilds: &mut Guilds,
     guild_id: &GuildId,
-) -> Result<Message, Box<dyn Error>> {
+) -> Result<MessageId, Box<dyn Error>> {
     let thread = get_thread_from_guild!(guild);
     if let Some(poll_id) = guild.poll_id {
-        if let Ok(message) = thread.message(&ctx.http, poll_id).await {
-            message
-                .reply(&ctx.http, "You can vote via this poll")
+        if platform.message_exists(thread, poll_id).await {
+            platform
+                .reply(thread, poll_id, String::from("You can vote via this poll"))
                 .await?;
-            Ok(message)
+            Ok(poll_id)
         } else {
-            thread.say(&ctx.http, POLL_ERROR_MESSAGE).await?;
+            platform
+                .say(thread, String::from(POLL_ERROR_MESSAGE))
+                .await?;
             Err(POLL_ERROR_MESSAGE.into())
         }
     } else {
-        let message = thread
-            .send_message(
-                &ctx.http,
-                CreateMessage::new()
-                    .content(build_submission_message(guild, guilds, guild_id))
-                    .select_menu(
-                        CreateSelectMenu::new(
-                            CUSTOM_ID,
-                            CreateSelectMenuKind::User {
-                                default_users: None,
-                            },
-                        )
-                        .placeholder("No submission selected"),
-                    ),
-            )
-            .await?;
-        message.pin(&ctx.http).await?;
-        Ok(message)
+        platform
+            .send_poll(thread, build_submission_message(guild, guilds, guild_id))
+            .await
     }
 }
 
-pub async fn vote(ctx: &Context, interaction: Interaction) -> Result<(), Box<dyn Error>> {
-    let mut data = ctx.data.write().await;
-    let state = get_shared_state!(data);
-    if let Interaction::Component(component) = interaction {
-        let guild_id = &component
-            .guild_id
-            .ok_or("This interaction was not received over the gateway")?;
-        let data = get_guild_from_id!(state, guild_id);
-        if component.data.custom_id == CUSTOM_ID
-            && data.active_daily
-            && data
-                .poll_id
-                .is_some_and(|poll_id| poll_id == component.message.id)
-        {
-            if let ComponentInteractionDataKind::UserSelect { values } = &component.data.kind {
-                let voted_for = values.first().ok_or("Did not select a single value")?;
-                if let Some(voted_for_status) = data.users.get(voted_for) {
-                    if voted_for_status.submitted.is_none() {
-                        return acknowledge_interaction!(
-                            ctx,
-                            component,
-                            "Cannot vote for someone who hasn't completed the challenge"
-                        );
-                    }
-                } else {
-                    return acknowledge_interaction!(
-                        ctx,
-                        component,
-                        "Cannot vote for someone who is not participating in the challenge"
-                    );
-                }
-                let user_id = component.user.id;
-                if *voted_for == user_id {
-                    return acknowledge_interaction!(ctx, component, "Cannot vote for yourself");
-                }
-                let user = get_user_from_id!(data.users, user_id);
-                user.voted_for = Some(*voted_for);
-                return acknowledge_interaction!(
-                    ctx,
-                    component,
-                    format!(
-                        "Successfully voted for {}",
-                        get_user_from_id!(state.guilds, guild_id, voted_for)
-                    )
-                );
-            }
+#[instrument(skip_all, fields(guild_id = guild_id.get(), user_id = user_id.get(), command = CUSTOM_ID))]
+pub fn handle_vote(
+    state: &mut SharedState,
+    guild_id: GuildId,
+    user_id: UserId,
+    message_id: MessageId,
+    voted_for: UserId,
+) -> Result<Option<String>, Box<dyn Error>> {
+    metrics().record_command(CUSTOM_ID);
+    let data = get_guild_from_id!(state, guild_id);
+    if !data.active_daily || data.poll_id != Some(message_id) {
+        return Ok(None);
+    }
+    if let Some(voted_for_status) = data.users.get(&voted_for) {
+        if voted_for_status.submitted.is_none() {
+            return Ok(Some(String::from(
+                "Cannot vote for someone who hasn't completed the challenge",
+            )));
         }
+    } else {
+        return Ok(Some(String::from(
+            "Cannot vote for someone who is not participating in the challenge",
+        )));
     }
-    Ok(())
+    if voted_for == user_id {
+        return Ok(Some(String::from("Cannot vote for yourself")));
+    }
+    get_user_from_id!(data.users, user_id).voted_for = Some(voted_for);
+    let guild_id = &guild_id;
+    let voted_for = &voted_for;
+    Ok(Some(format!(
+        "Successfully voted for {}",
+        get_user_from_id!(state.guilds, guild_id, voted_for)
+    )))
+}
+
+pub enum PageResponse {
+    Update(Page),
+    Ephemeral(Page),
+    Message(String),
+}
+
+// Previous and Next turn the page for everyone, Me answers with the page of whoever pressed it
+// Custom IDs have to be unique within a message, so they also say which button they are. They end
+// with the /scores arguments so the same leaderboard is shown on every page
+fn page_buttons(page: &Page) -> [String; 3] {
+    [
+        format!(
+            "{PAGE_ID}:{}:previous:{}",
+            page.page.saturating_sub(1),
+            page.args
+        ),
+        format!("{PAGE_ID}:{}:next:{}", page.page + 1, page.args),
+        format!("{ME_ID}:{}", page.args),
+    ]
+}
+
+// The page a button turns to, or None for the page the user is on, and the /scores arguments
+type PageButton<'a> = (Option<usize>, &'a str);
+
+fn parse_page_button(custom_id: &str) -> Result<Option<PageButton<'_>>, Box<dyn Error>> {
+    if let Some(args) = custom_id.strip_prefix(ME_ID) {
+        Ok(Some((None, args.trim_start_matches(':'))))
+    } else if let Some(rest) = custom_id.strip_prefix(PAGE_ID) {
+        let mut parts = rest.splitn(4, ':').skip(1);
+        let page = parts.next().ok_or("Invalid page button")?;
+        Ok(Some((
+            Some(page.parse::<usize>()?),
+            parts.nth(1).unwrap_or_default(),
+        )))
+    } else {
+        Ok(None)
+    }
+}
+
+#[instrument(skip_all, fields(guild_id = guild_id.get(), user_id = user_id.get(), command = PAGE_ID))]
+pub async fn handle_page(
+    platform: &dyn ChatPlatform,
+    state: &mut SharedState,
+    guild_id: GuildId,
+    user_id: UserId,
+    custom_id: &str,
+) -> Result<Option<PageResponse>, Box<dyn Error>> {
+    let Some((page, args)) = parse_page_button(custom_id)? else {
+        return Ok(None);
+    };
+    let ranking = Ranking::parse(args).ok_or("Invalid leaderboard arguments")?;
+    metrics().record_command(PAGE_ID);
+    let data = get_guild_from_id!(state, guild_id);
+    let guild_id = &guild_id;
+    let users = filter_role(platform, guild_id, &data.users, &ranking).await?;
+    Ok(Some(match page {
+        Some(page) => PageResponse::Update(
+            construct_page(&users, &mut state.guilds, guild_id, &ranking, page, None)
+                .ok_or("Failed to construct leaderboard page")?,
+        ),
+        None => match construct_page(
+            &users,
+            &mut state.guilds,
+            guild_id,
+            &ranking,
+            0,
+            Some(user_id),
+        ) {
+            Some(page) => PageResponse::Ephemeral(page),
+            None => PageResponse::Message(String::from("You aren't on this leaderboard yet")),
+        },
+    }))
 }
 
 fn default_data(users: Vec<UserId>) -> Data {
@@ -922,62 +1564,112 @@ fn default_data(users: Vec<UserId>) -> Data {
             .collect(),
         active_daily: true,
         active_weekly: true,
+        last_rollover: Some(daily_date()),
         ..Default::default()
     }
 }
 
-pub async fn initialise_guild(
-    ctx: &Context,
-    guild: Guild,
-    current_user_id: UserId,
+#[instrument(skip_all, fields(guild_id = guild_id.get()))]
+pub async fn add_guild(
+    platform: &dyn ChatPlatform,
+    state: &mut SharedState,
+    guild_id: GuildId,
+    channel_id: Option<ChannelId>,
 ) -> Result<(), Box<dyn Error>> {
-    let mut data = ctx.data.write().await;
-    let state = get_shared_state!(data);
-    if !state.database.contains_key(&guild.id) {
-        let mut data = default_data(
-            guild
-                .id
-                .members(&ctx.http, None, None)
-                .await?
-                .into_iter()
-                .filter_map(|member| {
-                    let user = member.user;
-                    if user.bot {
-                        None
-                    } else {
-                        Some(user.id)
-                    }
-                })
-                .collect::<Vec<_>>(),
+    if state.database.contains_key(&guild_id) {
+        return Ok(());
+    }
+    let channel_id = channel_id.ok_or("No available channel")?;
+    let mut data = default_data(
+        platform
+            .members(guild_id)
+            .await?
+            .into_iter()
+            .map(|user| user.id)
+            .collect::<Vec<_>>(),
+    );
+    data.channel_id = Some(channel_id);
+    let current_user_id = platform.current_user();
+    let guild_id = &guild_id;
+    initialise_guilds(platform, guild_id, state).await?;
+    let mut message = MessageBuilder::new();
+    send_help_message!(
+        platform,
+        message,
+        current_user_id,
+        channel_id,
+        channel_id,
+        data.thread_id,
+        data.site
+    );
+    send_daily_message_with_leaderboard!(
+        platform,
+        &mut state.guilds,
+        guild_id,
+        data,
+        MessageBuilder::new()
+    );
+    send_random_leetcode_question_message(platform, channel_id, vec![], data.site).await?;
+    state.database.insert(*guild_id, data);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(page: usize, pages: usize) -> Page {
+        Page {
+            title: String::new(),
+            content: String::new(),
+            page,
+            pages,
+            args: Ranking::default().args(),
+        }
+    }
+
+    #[test]
+    fn page_buttons_turn_to_the_pages_next_to_it() {
+        let [previous, next, me] = page_buttons(&page(1, 3));
+        assert_eq!(
+            parse_page_button(&previous).unwrap(),
+            Some((Some(0), "by:score"))
         );
-        for channel in guild.channels.values() {
-            if channel.kind == ChannelType::Text {
-                data.channel_id = Some(channel.id);
-                let guild_id = &guild.id;
-                initialise_guilds(ctx, guild_id, state).await?;
-                let mut message = MessageBuilder::new();
-                send_help_message!(
-                    ctx,
-                    message,
-                    current_user_id,
-                    channel,
-                    channel.id,
-                    data.thread_id
-                );
-                send_daily_message_with_leaderboard!(
-                    ctx,
-                    state,
-                    guild_id,
-                    data,
-                    MessageBuilder::new()
-                );
-                send_random_leetcode_question_message(ctx, channel.id, vec![]).await?;
-                state.database.insert(*guild_id, data);
-                return Ok(());
-            }
+        assert_eq!(
+            parse_page_button(&next).unwrap(),
+            Some((Some(2), "by:score"))
+        );
+        assert_eq!(parse_page_button(&me).unwrap(), Some((None, "by:score")));
+        // Previous on the first page stays there rather than underflowing
+        let [previous, next, _] = page_buttons(&page(0, 1));
+        assert_eq!(
+            parse_page_button(&previous).unwrap(),
+            Some((Some(0), "by:score"))
+        );
+        assert_eq!(
+            parse_page_button(&next).unwrap(),
+            Some((Some(1), "by:score"))
+        );
+    }
+
+    #[test]
+    fn other_custom_ids_are_not_page_buttons() {
+        assert_eq!(parse_page_button(CUSTOM_ID).unwrap(), None);
+        assert!(parse_page_button(&format!("{PAGE_ID}:x:next:by:score")).is_err());
+        assert!(parse_page_button(PAGE_ID).is_err());
+    }
+
+    #[test]
+    fn page_buttons_keep_arguments_with_colons() {
+        let ranking = Ranking::parse("by:monthly role:<@&42> top:15").unwrap();
+        let page = Page {
+            args: ranking.args(),
+            ..page(1, 2)
+        };
+        for button in page_buttons(&page) {
+            let (_, args) = parse_page_button(&button).unwrap().unwrap();
+            assert_eq!(args, "by:monthly role:42 top:15");
+            assert_eq!(Ranking::parse(args), Some(ranking));
         }
-        Err("No available channel".into())
-    } else {
-        Ok(())
     }
 }
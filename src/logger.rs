@@ -0,0 +1,128 @@
+use crate::{config, Format, Level};
+use std::{
+    error::Error,
+    fs::{rename, File, OpenOptions},
+    io::{stdout, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{
+    filter::Targets,
+    fmt::{
+        self,
+        time::{ChronoLocal, ChronoUtc},
+        writer::BoxMakeWriter,
+    },
+    prelude::*,
+    registry,
+};
+
+const LOCAL_TIME_FORMAT: &str = "%d/%m/%Y %I:%M:%S%p";
+
+struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: &Path, max_size: u64, max_files: usize) -> Result<Self, Box<dyn Error>> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_size,
+            max_files,
+            size: file.metadata()?.len(),
+            file,
+        })
+    }
+
+    fn backup(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        for index in (1..self.max_files).rev() {
+            let from = self.backup(index);
+            if from.exists() {
+                rename(from, self.backup(index + 1))?;
+            }
+        }
+        if self.max_files > 0 {
+            rename(&self.path, self.backup(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl From<Level> for LevelFilter {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Error => LevelFilter::ERROR,
+            Level::Warn => LevelFilter::WARN,
+            Level::Info => LevelFilter::INFO,
+            Level::Debug => LevelFilter::DEBUG,
+            Level::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+pub fn init_logger() -> Result<(), Box<dyn Error>> {
+    let log = &config().log;
+    let writer = match &log.output {
+        Some(path) => BoxMakeWriter::new(Mutex::new(RotatingFile::open(
+            path,
+            log.max_size,
+            log.max_files,
+        )?)),
+        None => BoxMakeWriter::new(stdout),
+    };
+    let layer = fmt::layer()
+        .with_writer(writer)
+        .with_ansi(log.output.is_none());
+    let layer = match (log.format, log.local_time) {
+        (Format::Text, true) => layer
+            .with_timer(ChronoLocal::new(LOCAL_TIME_FORMAT.to_string()))
+            .boxed(),
+        (Format::Text, false) => layer.with_timer(ChronoUtc::rfc_3339()).boxed(),
+        (Format::Json, true) => layer.json().with_timer(ChronoLocal::rfc_3339()).boxed(),
+        (Format::Json, false) => layer.json().with_timer(ChronoUtc::rfc_3339()).boxed(),
+    };
+    registry()
+        .with(
+            layer.with_filter(
+                Targets::new()
+                    .with_target(env!("CARGO_CRATE_NAME"), LevelFilter::from(log.level))
+                    .with_default(LevelFilter::WARN.min(log.level.into())),
+            ),
+        )
+        .try_init()?;
+    Ok(())
+}
@@ -1,27 +1,35 @@
-use chrono::Utc;
+mod cli;
+
+use clap::Parser;
+use cli::Cli;
 use leetcode_daily::{
-    initialise_guild, log, respond, save_to_database, schedule_daily_question, schedule_thread,
-    schedule_weekly_contest, setup, vote, write_to_database, Database, SharedState, State,
+    config, init_logger, initialise_guild, read_database, respond, run_matrix, run_slack,
+    run_telegram, run_webhooks, save_to_database, schedule_daily_question, schedule_thread,
+    schedule_weekly_contest, serve, set_config, set_platform, setup, shutdown, turn_page, vote,
+    Backend, ChatPlatform, Discord, Matrix, Platform, SharedState, Slack, State, Telegram,
 };
-use serenity::{async_trait, model::prelude::*, prelude::*};
+use serenity::{all::ShardManager, async_trait, model::prelude::*, prelude::*};
 use std::{
-    collections::HashMap,
-    env::var,
-    error::Error,
-    fs::{File, OpenOptions},
-    io::{Read, Seek, SeekFrom, Write},
+    collections::HashMap, error::Error, fs::OpenOptions, future::Future, io::Read, sync::Arc,
+};
+use tokio::{
+    main, select,
+    signal::{
+        ctrl_c,
+        unix::{signal, SignalKind},
+    },
+    spawn,
 };
-use tokio::{main, spawn};
+use tracing::{error, info};
 
 struct Handler;
 
 #[async_trait]
 impl EventHandler for Handler {
     async fn guild_create(&self, ctx: Context, guild: Guild, _is_new: Option<bool>) {
-        let current_user_id = ctx.cache.current_user().id;
         if _is_new == Some(true) {
-            if let Err(why) = initialise_guild(&ctx, guild, current_user_id).await {
-                log!("Error initialising guild: {why}");
+            if let Err(why) = initialise_guild(&ctx, guild).await {
+                error!("Error initialising guild: {why}");
             }
             save_to_database!(ctx);
         }
@@ -29,8 +37,10 @@ impl EventHandler for Handler {
 
     async fn ready(&self, ctx: Context, ready: Ready) {
         if setup(&ctx, ready).await.is_ok() {
-            schedule_thread!(ctx, schedule_daily_question);
-            schedule_thread!(ctx, schedule_weekly_contest);
+            let platform: Arc<dyn ChatPlatform> = Arc::new(Discord::new(&ctx));
+            set_platform(platform.clone());
+            schedule_thread!(platform, ctx.data, schedule_daily_question);
+            schedule_thread!(platform, ctx.data, schedule_weekly_contest);
             save_to_database!(ctx);
         }
     }
@@ -38,65 +48,160 @@ impl EventHandler for Handler {
     async fn message(&self, ctx: Context, msg: Message) {
         let current_user_id = ctx.cache.current_user().id;
         if msg.author.id != current_user_id {
-            if let Err(why) = respond(&ctx, msg, current_user_id).await {
-                log!("Error responding to messages: {why}");
+            if let Err(why) = respond(&ctx, msg).await {
+                error!("Error responding to messages: {why}");
             }
             save_to_database!(ctx);
         }
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Err(why) = vote(&ctx, interaction).await {
-            log!("Error responding to vote interaction: {why}");
+        if let Err(why) = vote(&ctx, &interaction).await {
+            error!("Error responding to vote interaction: {why}");
+        }
+        if let Err(why) = turn_page(&ctx, &interaction).await {
+            error!("Error responding to leaderboard page interaction: {why}");
         }
         save_to_database!(ctx);
     }
 }
 
-fn default_database(file: File, contents: &mut str) -> Result<Database, Box<dyn Error>> {
-    let mut state = SharedState {
+async fn wait_for_signal() -> Result<(), Box<dyn Error>> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    select! {
+        _ = terminate.recv() => info!("Received SIGTERM"),
+        result = ctrl_c() => {
+            result?;
+            info!("Received SIGINT");
+        }
+    }
+    Ok(())
+}
+
+fn type_map(state: SharedState) -> Arc<RwLock<TypeMap>> {
+    let mut data = TypeMap::new();
+    data.insert::<State>(state);
+    Arc::new(RwLock::new(data))
+}
+
+fn load_database() -> Result<SharedState, Box<dyn Error>> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&config().database.path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    if contents.is_empty() {
+        contents = String::from("{}");
+    }
+    Ok(SharedState {
         ready: false,
+        shutting_down: false,
         guilds: HashMap::new(),
         file,
-        database: HashMap::new(),
-    };
-    write_to_database!(state);
-    serde_json::from_str(contents).map_err(|err| err.into())
+        database: match config().database.backend {
+            // The file is never written over here, so a database that doesn't parse can still be fixed
+            Backend::Json => serde_json::from_str(&contents).map_err(|why| {
+                format!(
+                    "Could not parse the database {:?}: {why}, check it with `leetcode_daily db validate` then fix it or restore a backup with `leetcode_daily db import`",
+                    config().database.path
+                )
+            })?,
+        },
+    })
+}
+
+async fn run(
+    bot: impl Future<Output = Result<(), Box<dyn Error>>>,
+    data: Arc<RwLock<TypeMap>>,
+    shard_manager: Option<Arc<ShardManager>>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(address) = config().http.address {
+        let data = data.clone();
+        let shard_manager = shard_manager.clone();
+        spawn(async move {
+            if let Err(why) = serve(address, data, shard_manager).await {
+                error!("Error serving HTTP: {why}");
+            }
+        });
+    }
+    select! {
+        result = bot => result?,
+        result = wait_for_signal() => {
+            if let Err(why) = result {
+                error!("Error waiting for shutdown signal: {why}");
+            }
+            info!("Shutting down");
+            shutdown(data, shard_manager).await?;
+            info!("Shut down cleanly");
+        }
+    }
+    Ok(())
 }
 
 #[main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let token = var("DISCORD_TOKEN")?;
-    let mut client = Client::builder(
-        token,
-        GatewayIntents::DIRECT_MESSAGES
-            | GatewayIntents::GUILDS
-            | GatewayIntents::GUILD_MESSAGES
-            | GatewayIntents::MESSAGE_CONTENT
-            | GatewayIntents::GUILD_MEMBERS,
-    )
-    .event_handler(Handler)
-    .await?;
-    {
-        let mut file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open("database.json")?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        if contents.is_empty() {
-            contents = String::from("{}");
+    let cli = Cli::parse();
+    set_config(cli.load_config()?)?;
+    // Subcommands print to stdout so they run without the logger, only db opens the database
+    if let Some(command) = &cli.command {
+        return command.run().await;
+    }
+    if cli.check {
+        let path = &config().database.path;
+        let guilds = if path.exists() {
+            read_database(path)?.len()
+        } else {
+            0
+        };
+        println!("Loaded the config and {guilds} guilds from {path:?}");
+        return Ok(());
+    }
+    init_logger()?;
+    // Credentials are checked before the database is opened, so a bad start leaves it untouched
+    match config().platform {
+        Platform::Discord => {
+            let token = cli
+                .token
+                .ok_or("Missing Discord token, set DISCORD_TOKEN or pass --token")?;
+            let mut client = Client::builder(
+                token,
+                GatewayIntents::DIRECT_MESSAGES
+                    | GatewayIntents::GUILDS
+                    | GatewayIntents::GUILD_MESSAGES
+                    | GatewayIntents::MESSAGE_CONTENT
+                    | GatewayIntents::GUILD_MEMBERS,
+            )
+            .event_handler(Handler)
+            .await?;
+            client.data.write().await.insert::<State>(load_database()?);
+            let data = client.data.clone();
+            let shard_manager = client.shard_manager.clone();
+            run(
+                async move { Ok(client.start().await?) },
+                data,
+                Some(shard_manager),
+            )
+            .await
         }
-        let mut data = client.data.write().await;
-        data.insert::<State>(SharedState {
-            ready: false,
-            guilds: HashMap::new(),
-            file: file.try_clone()?,
-            database: serde_json::from_str(&contents)
-                .unwrap_or(default_database(file, &mut contents)?),
-        });
+        Platform::Slack => {
+            let platform = Arc::new(Slack::from_config().await?);
+            let data = type_map(load_database()?);
+            run(run_slack(platform, data.clone()), data, None).await
+        }
+        Platform::Matrix => {
+            let platform = Arc::new(Matrix::from_config().await?);
+            let data = type_map(load_database()?);
+            run(run_matrix(platform, data.clone()), data, None).await
+        }
+        Platform::Telegram => {
+            let platform = Arc::new(Telegram::from_config().await?);
+            let data = type_map(load_database()?);
+            run(run_telegram(platform, data.clone()), data, None).await
+        }
+        // Webhooks only post the question and keep no scores, so the database is left alone
+        Platform::Webhook => run(run_webhooks(), Arc::new(RwLock::new(TypeMap::new())), None).await,
     }
-    client.start().await.map_err(|e| e.into())
 }
@@ -0,0 +1,970 @@
+use crate::{
+    add_guild, config, get_shared_state, handle_message, handle_vote, initialise_guilds, metrics,
+    platform::{content_type, ChatPlatform, Embed, Incoming, Page, PlatformResult},
+    registry::Registry,
+    schedule_daily_question, schedule_thread, schedule_weekly_contest, set_platform,
+    write_to_database, State,
+};
+use regex::{Captures, Regex};
+use reqwest::{Client, Method, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use serenity::{
+    async_trait,
+    model::prelude::*,
+    prelude::{RwLock, TypeMap},
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::{Seek, SeekFrom, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{spawn, time::sleep};
+use tracing::{error, info, warn};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const SYNC_TIMEOUT: u64 = 30000;
+const POLL_OPTIONS: &str = "leetcode_daily.poll_options";
+const SYNC_FILTER: &str = r#"{"presence":{"types":[]},"account_data":{"types":[]},"room":{"state":{"types":[]},"ephemeral":{"types":[]},"account_data":{"types":[]},"timeline":{"types":["m.room.message","m.reaction"]}}}"#;
+
+#[derive(Deserialize)]
+struct WhoAmI {
+    user_id: String,
+}
+
+#[derive(Deserialize)]
+struct EventId {
+    event_id: String,
+}
+
+#[derive(Deserialize)]
+struct Uploaded {
+    content_uri: String,
+}
+
+#[derive(Deserialize)]
+struct RoomId {
+    room_id: String,
+}
+
+#[derive(Deserialize)]
+struct JoinedRooms {
+    joined_rooms: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Profile {
+    display_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JoinedMembers {
+    joined: HashMap<String, Profile>,
+}
+
+#[derive(Deserialize, Default)]
+struct PinnedEvents {
+    pinned: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Sync {
+    next_batch: String,
+    #[serde(default)]
+    rooms: Rooms,
+}
+
+#[derive(Deserialize, Default)]
+struct Rooms {
+    #[serde(default)]
+    join: HashMap<String, JoinedRoom>,
+    #[serde(default)]
+    invite: HashMap<String, Value>,
+}
+
+#[derive(Deserialize, Default)]
+struct JoinedRoom {
+    #[serde(default)]
+    timeline: Timeline,
+}
+
+#[derive(Deserialize, Default)]
+struct Timeline {
+    #[serde(default)]
+    events: Vec<RoomEvent>,
+}
+
+#[derive(Deserialize)]
+struct RoomEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    sender: String,
+    event_id: String,
+    #[serde(default)]
+    content: Value,
+}
+
+#[derive(Deserialize)]
+struct Relation {
+    rel_type: Option<String>,
+    event_id: Option<String>,
+    key: Option<String>,
+    #[serde(rename = "m.in_reply_to")]
+    in_reply_to: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Content {
+    msgtype: Option<String>,
+    #[serde(default)]
+    body: String,
+    #[serde(rename = "m.relates_to")]
+    relates_to: Option<Relation>,
+}
+
+#[derive(Default)]
+struct Rendered {
+    body: String,
+    html: String,
+    mentions: Vec<String>,
+    room: bool,
+}
+
+impl Rendered {
+    fn push(&mut self, text: &str) {
+        self.body.push_str(text);
+        self.html.push_str(&escape(text).replace('\n', "<br>"));
+    }
+
+    fn link(&mut self, url: &str, text: &str) {
+        self.body.push_str(text);
+        self.html.push_str(&format!(
+            r#"<a href="{}">{}</a>"#,
+            escape(url),
+            escape(text)
+        ));
+    }
+
+    fn into_content(self) -> Value {
+        json!({
+            "msgtype": "m.text",
+            "body": self.body,
+            "format": "org.matrix.custom.html",
+            "formatted_body": self.html,
+            "m.mentions": {"user_ids": self.mentions, "room": self.room},
+        })
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn permalink(room: &str, event: Option<&str>) -> String {
+    match event {
+        Some(event) => format!("https://matrix.to/#/{room}/{event}"),
+        None => format!("https://matrix.to/#/{room}"),
+    }
+}
+
+// Reaction used to vote for the submission at index, keycaps for the first ten then letters
+fn option(index: usize) -> Option<String> {
+    match index {
+        0..=8 => Some(format!("{}\u{fe0f}\u{20e3}", index + 1)),
+        9 => Some(String::from("\u{1f51f}")),
+        _ => char::from_u32(0x1f1e6 + u32::try_from(index - 10).ok()?)
+            .filter(|letter| *letter <= '\u{1f1ff}')
+            .map(String::from),
+    }
+}
+
+// Rooms are both guilds and channels, threads and messages are keyed by the room and the event ID
+// of their first message, the same as that message
+pub struct Matrix {
+    client: Client,
+    homeserver: String,
+    token: String,
+    registry: Registry,
+    bot_user: String,
+    user_id: UserId,
+    transaction: AtomicU64,
+}
+
+impl Matrix {
+    pub async fn from_config() -> Result<Self, Box<dyn Error>> {
+        let matrix = &config().matrix;
+        let homeserver = matrix.homeserver.as_deref().ok_or(
+            "Missing Matrix homeserver, set MATRIX_HOMESERVER or pass --matrix-homeserver",
+        )?;
+        let token = matrix.access_token.as_deref().ok_or(
+            "Missing Matrix access token, set MATRIX_ACCESS_TOKEN or pass --matrix-access-token",
+        )?;
+        Self::new(homeserver, token).await
+    }
+
+    pub async fn new(homeserver: &str, token: &str) -> Result<Self, Box<dyn Error>> {
+        let registry = Registry::open(&config().matrix.ids)?;
+        let mut matrix = Self {
+            client: Client::new(),
+            homeserver: homeserver.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            registry,
+            bot_user: String::new(),
+            user_id: UserId::default(),
+            transaction: AtomicU64::new(0),
+        };
+        let whoami = matrix
+            .call::<WhoAmI>(Method::GET, &["account", "whoami"], &[], None)
+            .await?;
+        matrix.user_id = UserId::new(matrix.registry.id(&whoami.user_id));
+        matrix.bot_user = whoami.user_id;
+        Ok(matrix)
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &[&str],
+        query: &[(&str, String)],
+        body: Option<Value>,
+    ) -> Result<T, Box<dyn Error>> {
+        let mut url = Url::parse(&format!("{}/_matrix/client/v3", self.homeserver))?;
+        url.path_segments_mut()
+            .map_err(|_| "Invalid Matrix homeserver URL")?
+            .extend(path);
+        let mut request = self
+            .client
+            .request(method, url)
+            .bearer_auth(&self.token)
+            .query(query);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let response = response.json::<Value>().await?;
+        if !status.is_success() {
+            return Err(format!(
+                "Matrix {} failed with {status}: {} {}",
+                path.join("/"),
+                response["errcode"],
+                response["error"]
+            )
+            .into());
+        }
+        Ok(serde_json::from_value(response)?)
+    }
+
+    async fn upload(&self, file_name: &str, data: Vec<u8>) -> Result<String, Box<dyn Error>> {
+        let mut url = Url::parse(&format!("{}/_matrix/media/v3/upload", self.homeserver))?;
+        url.query_pairs_mut().append_pair("filename", file_name);
+        let response = self
+            .client
+            .post(url)
+            .bearer_auth(&self.token)
+            .header("Content-Type", content_type(file_name))
+            .body(data)
+            .send()
+            .await?;
+        let status = response.status();
+        let response = response.json::<Value>().await?;
+        if !status.is_success() {
+            return Err(format!(
+                "Matrix upload failed with {status}: {} {}",
+                response["errcode"], response["error"]
+            )
+            .into());
+        }
+        Ok(serde_json::from_value::<Uploaded>(response)?.content_uri)
+    }
+
+    fn transaction_id(&self) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis());
+        format!("{now}.{}", self.transaction.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn channel(&self, channel_id: ChannelId) -> Result<(String, Option<String>), Box<dyn Error>> {
+        let native = self
+            .registry
+            .native(channel_id.get())
+            .ok_or("Unknown Matrix room")?;
+        Ok(match native.split_once('/') {
+            Some((room, event)) => (room.to_string(), Some(event.to_string())),
+            None => (native, None),
+        })
+    }
+
+    fn message(&self, message_id: MessageId) -> Result<(String, String), Box<dyn Error>> {
+        let native = self
+            .registry
+            .native(message_id.get())
+            .ok_or("Unknown Matrix event")?;
+        let (room, event) = native.split_once('/').ok_or("Not a Matrix event")?;
+        Ok((room.to_string(), event.to_string()))
+    }
+
+    fn message_id(&self, room: &str, event: &str) -> MessageId {
+        MessageId::new(self.registry.recent_id(&format!("{room}/{event}")))
+    }
+
+    fn channel_id(&self, room: &str, thread: Option<&str>) -> ChannelId {
+        ChannelId::new(match thread {
+            Some(event) => self.registry.recent_id(&format!("{room}/{event}")),
+            None => self.registry.id(room),
+        })
+    }
+
+    fn guild_id(&self, room: &str) -> GuildId {
+        GuildId::new(self.registry.id(room))
+    }
+
+    // Discord markdown to Matrix HTML with a markdown body, spoilers map onto Matrix spoilers
+    // and the spoilered submission template becomes a code block showing what to type
+    fn render(&self, content: &str) -> Result<Value, Box<dyn Error>> {
+        let pattern = Regex::new(
+            r"(?s)<@!?(\d+)>|<#(\d+)>|\[([^\]\n]+)\]\(([^)\s]+)\)|\*\*(.+?)\*\*|``\|\|(.+?)\|\|``|\|\|(.+?)\|\||```(?:[\w+\-]*\n)?(.+?)```|`([^`\n]+)`|@everyone",
+        )?;
+        let mut rendered = Rendered::default();
+        self.convert(&pattern, content, &mut rendered);
+        Ok(rendered.into_content())
+    }
+
+    fn convert(&self, pattern: &Regex, text: &str, rendered: &mut Rendered) {
+        let mut last = 0;
+        for caps in pattern.captures_iter(text) {
+            let Some(whole) = caps.get(0) else {
+                continue;
+            };
+            rendered.push(&text[last..whole.start()]);
+            last = whole.end();
+            if let Some(user_id) = caps.get(1) {
+                match user_id
+                    .as_str()
+                    .parse()
+                    .ok()
+                    .and_then(|id| self.registry.native(id))
+                {
+                    Some(user) => {
+                        rendered.link(&permalink(&user, None), &user);
+                        rendered.mentions.push(user);
+                    }
+                    None => rendered.push("@unknown"),
+                }
+            } else if let Some(channel_id) = caps.get(2) {
+                match channel_id
+                    .as_str()
+                    .parse()
+                    .map_err(Box::<dyn Error>::from)
+                    .and_then(|id| self.channel(ChannelId::new(id)))
+                {
+                    Ok((room, Some(event))) => {
+                        rendered.link(&permalink(&room, Some(&event)), "this thread")
+                    }
+                    Ok((room, None)) => rendered.link(&permalink(&room, None), &room),
+                    Err(_) => rendered.push("#unknown"),
+                }
+            } else if let (Some(name), Some(url)) = (caps.get(3), caps.get(4)) {
+                rendered
+                    .body
+                    .push_str(&format!("{} ({})", name.as_str(), url.as_str()));
+                rendered.html.push_str(&format!(
+                    r#"<a href="{}">{}</a>"#,
+                    escape(url.as_str()),
+                    escape(name.as_str())
+                ));
+            } else if let Some(bold) = caps.get(5) {
+                rendered.html.push_str("<strong>");
+                self.convert(pattern, bold.as_str(), rendered);
+                rendered.html.push_str("</strong>");
+            } else if let Some(template) = caps.get(6) {
+                rendered.body.push_str(template.as_str());
+                rendered.html.push_str(&format!(
+                    "<pre><code>{}</code></pre>",
+                    escape(template.as_str())
+                ));
+            } else if let Some(spoiler) = caps.get(7) {
+                rendered.html.push_str("<span data-mx-spoiler>");
+                self.convert(pattern, spoiler.as_str(), rendered);
+                rendered.html.push_str("</span>");
+            } else if let Some(code) = caps.get(8) {
+                rendered.body.push_str(whole.as_str());
+                rendered.html.push_str(&format!(
+                    "<pre><code>{}</code></pre>",
+                    escape(code.as_str())
+                ));
+            } else if let Some(code) = caps.get(9) {
+                rendered.body.push_str(whole.as_str());
+                rendered
+                    .html
+                    .push_str(&format!("<code>{}</code>", escape(code.as_str())));
+            } else {
+                rendered.push("@room");
+                rendered.room = true;
+            }
+        }
+        rendered.push(&text[last..]);
+    }
+
+    // Numbers each submission in the poll and records who each number votes for in the event
+    fn render_poll(&self, content: &str) -> Result<Value, Box<dyn Error>> {
+        let mention = Regex::new(r"^<@!?(\d+)>")?;
+        let mut options = Vec::new();
+        let mut lines = Vec::new();
+        for line in content.lines() {
+            match mention
+                .captures(line)
+                .and_then(|caps| Some((caps[1].parse::<u64>().ok()?, option(options.len())?)))
+            {
+                Some((user_id, option)) => {
+                    options.push(user_id);
+                    lines.push(format!("{option} {line}"));
+                }
+                None => lines.push(line.to_string()),
+            }
+        }
+        lines.push(String::from(
+            "React with the number of your favourite submission to vote for it",
+        ));
+        let mut rendered = self.render(&lines.join("\n"))?;
+        rendered[POLL_OPTIONS] = json!(options);
+        Ok(rendered)
+    }
+
+    // Incoming messages to what the core understands, commands can also start with ! since most
+    // clients intercept unknown slash commands and room IDs or aliases become bare IDs for /channel
+    async fn parse(&self, content: &Content) -> Result<String, Box<dyn Error>> {
+        let mut body = content.body.as_str();
+        if content
+            .relates_to
+            .as_ref()
+            .is_some_and(|relation| relation.in_reply_to.is_some())
+        {
+            while let Some(line) = body.strip_prefix("> ") {
+                body = line.split_once('\n').map_or("", |(_, rest)| rest);
+            }
+            body = body.trim_start_matches('\n');
+        }
+        let mut body = match body.strip_prefix('!') {
+            Some(command)
+                if command
+                    .split_whitespace()
+                    .next()
+                    .is_some_and(|command| !command.is_empty() && !command.contains(':')) =>
+            {
+                format!("/{command}")
+            }
+            _ => body.to_string(),
+        };
+        let alias = Regex::new(r"#[\w.=\-]+:[\w.\-]+(?::\d+)?")?;
+        let mut rooms = HashMap::new();
+        for alias in alias.find_iter(&body) {
+            if let Ok(room) = self
+                .call::<RoomId>(
+                    Method::GET,
+                    &["directory", "room", alias.as_str()],
+                    &[],
+                    None,
+                )
+                .await
+            {
+                rooms.insert(alias.as_str().to_string(), room.room_id);
+            }
+        }
+        body = alias
+            .replace_all(&body, |caps: &Captures| match rooms.get(&caps[0]) {
+                Some(room) => self.registry.id(room).to_string(),
+                None => caps[0].to_string(),
+            })
+            .into_owned();
+        Ok(Regex::new(r"![\w.=\-]+:[\w.\-]+(?::\d+)?")?
+            .replace_all(&body, |caps: &Captures| {
+                self.registry.id(&caps[0]).to_string()
+            })
+            .into_owned())
+    }
+
+    async fn post(
+        &self,
+        channel_id: ChannelId,
+        mut content: Value,
+        reply_to: Option<String>,
+    ) -> Result<(String, String), Box<dyn Error>> {
+        let (room, thread) = self.channel(channel_id)?;
+        match (thread, reply_to) {
+            (Some(thread), reply_to) => {
+                content["m.relates_to"] = json!({
+                    "rel_type": "m.thread",
+                    "event_id": thread,
+                    "is_falling_back": reply_to.is_none(),
+                    "m.in_reply_to": {"event_id": reply_to.unwrap_or(thread)},
+                });
+            }
+            (None, Some(reply_to)) => {
+                content["m.relates_to"] = json!({"m.in_reply_to": {"event_id": reply_to}});
+            }
+            (None, None) => {}
+        }
+        let transaction = self.transaction_id();
+        let sent = self
+            .call::<EventId>(
+                Method::PUT,
+                &["rooms", &room, "send", "m.room.message", &transaction],
+                &[],
+                Some(content),
+            )
+            .await?;
+        Ok((room, sent.event_id))
+    }
+
+    async fn pin(&self, room: &str, event: &str) -> Result<(), Box<dyn Error>> {
+        let path = ["rooms", room, "state", "m.room.pinned_events"];
+        let mut pinned = self
+            .call::<PinnedEvents>(Method::GET, &path, &[], None)
+            .await
+            .unwrap_or_default()
+            .pinned;
+        pinned.push(event.to_string());
+        self.call::<Value>(Method::PUT, &path, &[], Some(json!({ "pinned": pinned })))
+            .await?;
+        Ok(())
+    }
+
+    async fn event(&self, room: &str, event: &str) -> Result<Value, Box<dyn Error>> {
+        self.call::<Value>(Method::GET, &["rooms", room, "event", event], &[], None)
+            .await
+    }
+
+    // The options of the latest edit of a poll and the thread it was posted in
+    async fn poll_options(
+        &self,
+        room: &str,
+        event: &str,
+    ) -> Result<(Vec<u64>, Option<String>), Box<dyn Error>> {
+        let poll = self.event(room, event).await?;
+        let thread = poll
+            .pointer("/content/m.relates_to")
+            .filter(|relation| relation["rel_type"] == "m.thread")
+            .and_then(|relation| relation["event_id"].as_str())
+            .map(String::from);
+        // Servers bundle the latest edit, older ones only include its ID
+        let content = match poll.pointer("/unsigned/m.relations/m.replace") {
+            Some(edit) if edit.get("content").is_some() => {
+                edit.pointer("/content/m.new_content").cloned()
+            }
+            Some(edit) => {
+                let edit_id = edit["event_id"].as_str().ok_or("Invalid edit")?;
+                self.event(room, edit_id)
+                    .await?
+                    .pointer("/content/m.new_content")
+                    .cloned()
+            }
+            None => poll.get("content").cloned(),
+        }
+        .ok_or("Poll has no content")?;
+        Ok((
+            serde_json::from_value(content[POLL_OPTIONS].clone())?,
+            thread,
+        ))
+    }
+}
+
+#[async_trait]
+impl ChatPlatform for Matrix {
+    fn current_user(&self) -> UserId {
+        self.user_id
+    }
+
+    fn native_user_id(&self, user_id: UserId) -> String {
+        self.registry
+            .native(user_id.get())
+            .unwrap_or_else(|| user_id.to_string())
+    }
+
+    async fn say(&self, channel_id: ChannelId, content: String) -> PlatformResult<MessageId> {
+        let content = self.render(&content)?;
+        let (room, event) = self.post(channel_id, content, None).await?;
+        Ok(self.message_id(&room, &event))
+    }
+
+    async fn send_file(
+        &self,
+        channel_id: ChannelId,
+        content: String,
+        file_name: String,
+        data: Vec<u8>,
+    ) -> PlatformResult<()> {
+        self.say(channel_id, content).await?;
+        let size = data.len();
+        let url = self.upload(&file_name, data).await?;
+        self.post(
+            channel_id,
+            json!({
+                "msgtype": "m.file",
+                "body": file_name,
+                "filename": file_name,
+                "url": url,
+                "info": {"mimetype": content_type(&file_name), "size": size},
+            }),
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn send_embed(
+        &self,
+        channel_id: ChannelId,
+        content: String,
+        embed: Embed,
+    ) -> PlatformResult<MessageId> {
+        let mut message = self.render(&content)?;
+        let mut body = format!(
+            "{}\n> {} ({})",
+            message["body"].as_str().unwrap_or_default(),
+            embed.title,
+            embed.url
+        );
+        let mut html = format!(
+            r##"{}<blockquote><a href="{}"><font data-mx-color="#{}"><strong>{}</strong></font></a>"##,
+            message["formatted_body"].as_str().unwrap_or_default(),
+            escape(&embed.url),
+            embed.colour.hex(),
+            escape(&embed.title)
+        );
+        for (name, value) in embed.fields {
+            body.push_str(&format!("\n> {name}: {value}"));
+            html.push_str(&format!(
+                "<br><strong>{}</strong>: {}",
+                escape(&name),
+                escape(&value)
+            ));
+        }
+        html.push_str("</blockquote>");
+        message["body"] = json!(body);
+        message["formatted_body"] = json!(html);
+        let (room, event) = self.post(channel_id, message, None).await?;
+        Ok(self.message_id(&room, &event))
+    }
+
+    // There are no buttons to turn pages with, /scores takes the page instead
+    async fn send_page(&self, channel_id: ChannelId, page: Page) -> PlatformResult<MessageId> {
+        self.say(channel_id, page.text()).await
+    }
+
+    async fn send_poll(&self, channel_id: ChannelId, content: String) -> PlatformResult<MessageId> {
+        let content = self.render_poll(&content)?;
+        let (room, event) = self.post(channel_id, content, None).await?;
+        self.registry.recent_id(&format!("poll/{room}/{event}"));
+        // Pinning needs a higher power level than sending, the poll works without it
+        if let Err(why) = self.pin(&room, &event).await {
+            warn!("Could not pin the poll in {room}: {why}");
+        }
+        Ok(self.message_id(&room, &event))
+    }
+
+    async fn edit(
+        &self,
+        _channel_id: ChannelId,
+        message_id: MessageId,
+        content: String,
+    ) -> PlatformResult<()> {
+        let (room, event) = self.message(message_id)?;
+        let new_content = if self.registry.contains(&format!("poll/{room}/{event}")) {
+            self.render_poll(&content)?
+        } else {
+            self.render(&content)?
+        };
+        let transaction = self.transaction_id();
+        self.call::<EventId>(
+            Method::PUT,
+            &["rooms", &room, "send", "m.room.message", &transaction],
+            &[],
+            Some(json!({
+                "msgtype": "m.text",
+                "body": format!("* {}", new_content["body"].as_str().unwrap_or_default()),
+                "m.new_content": new_content,
+                "m.relates_to": {"rel_type": "m.replace", "event_id": event},
+            })),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn reply(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: String,
+    ) -> PlatformResult<()> {
+        let (_, event) = self.message(message_id)?;
+        let content = self.render(&content)?;
+        self.post(channel_id, content, Some(event)).await?;
+        Ok(())
+    }
+
+    async fn message_exists(&self, _channel_id: ChannelId, message_id: MessageId) -> bool {
+        let Ok((room, event)) = self.message(message_id) else {
+            return false;
+        };
+        // Redacted events are still returned but without their content
+        self.event(&room, &event).await.is_ok_and(|event| {
+            event["content"]
+                .as_object()
+                .is_some_and(|content| !content.is_empty())
+        })
+    }
+
+    async fn create_thread(
+        &self,
+        _channel_id: ChannelId,
+        message_id: MessageId,
+        _name: String,
+    ) -> PlatformResult<ChannelId> {
+        let (room, event) = self.message(message_id)?;
+        Ok(self.channel_id(&room, Some(&event)))
+    }
+
+    async fn is_text_channel(&self, channel_id: ChannelId) -> bool {
+        let Ok((room, None)) = self.channel(channel_id) else {
+            return false;
+        };
+        self.call::<JoinedRooms>(Method::GET, &["joined_rooms"], &[], None)
+            .await
+            .is_ok_and(|joined| joined.joined_rooms.contains(&room))
+    }
+
+    async fn members(&self, guild_id: GuildId) -> PlatformResult<Vec<User>> {
+        let room = self
+            .registry
+            .native(guild_id.get())
+            .ok_or("Unknown Matrix room")?;
+        let members = self
+            .call::<JoinedMembers>(Method::GET, &["rooms", &room, "joined_members"], &[], None)
+            .await?;
+        Ok(members
+            .joined
+            .into_iter()
+            .filter(|(member, _)| *member != self.bot_user)
+            .map(|(member, profile)| {
+                let mut user = User::default();
+                user.id = UserId::new(self.registry.id(&member));
+                user.name = profile.display_name.unwrap_or_else(|| {
+                    member
+                        .trim_start_matches('@')
+                        .split(':')
+                        .next()
+                        .unwrap_or_default()
+                        .to_string()
+                });
+                user
+            })
+            .collect())
+    }
+
+    async fn role_members(
+        &self,
+        _guild_id: GuildId,
+        _role_id: RoleId,
+    ) -> PlatformResult<Vec<UserId>> {
+        Err("Roles are not supported on Matrix".into())
+    }
+
+    async fn set_role(
+        &self,
+        _guild_id: GuildId,
+        _user_id: UserId,
+        _role_id: RoleId,
+        _assigned: bool,
+    ) -> PlatformResult<()> {
+        Err("Role rewards are not supported on Matrix".into())
+    }
+}
+
+async fn ready(platform: &Arc<Matrix>, data: &Arc<RwLock<TypeMap>>) -> Result<(), Box<dyn Error>> {
+    {
+        let mut data = data.write().await;
+        let state = get_shared_state!(data);
+        if state.ready {
+            return Ok(());
+        }
+        let rooms = platform
+            .call::<JoinedRooms>(Method::GET, &["joined_rooms"], &[], None)
+            .await?
+            .joined_rooms;
+        state.ready = true;
+        info!("Setting up Matrix rooms {rooms:?}");
+        for room in rooms {
+            let guild_id = platform.guild_id(&room);
+            let result = if state.database.contains_key(&guild_id) {
+                initialise_guilds(platform.as_ref(), &guild_id, state).await
+            } else {
+                let channel_id = platform.channel_id(&room, None);
+                add_guild(platform.as_ref(), state, guild_id, Some(channel_id)).await
+            };
+            if let Err(why) = result {
+                error!("Error setting up Matrix room {room}: {why}");
+            }
+        }
+        write_to_database!(state);
+    }
+    let platform: Arc<dyn ChatPlatform> = platform.clone();
+    set_platform(platform.clone());
+    schedule_thread!(platform, data, schedule_daily_question);
+    schedule_thread!(platform, data, schedule_weekly_contest);
+    Ok(())
+}
+
+async fn join(platform: &Matrix, data: &RwLock<TypeMap>, room: &str) -> Result<(), Box<dyn Error>> {
+    platform
+        .call::<RoomId>(Method::POST, &["rooms", room, "join"], &[], Some(json!({})))
+        .await?;
+    info!("Joined Matrix room {room}");
+    let mut data = data.write().await;
+    let state = get_shared_state!(data);
+    let channel_id = platform.channel_id(room, None);
+    add_guild(platform, state, platform.guild_id(room), Some(channel_id)).await?;
+    write_to_database!(state);
+    Ok(())
+}
+
+async fn dispatch(
+    platform: &Matrix,
+    data: &RwLock<TypeMap>,
+    room: &str,
+    event: RoomEvent,
+) -> Result<(), Box<dyn Error>> {
+    if event.sender == platform.bot_user {
+        return Ok(());
+    }
+    let Ok(content) = serde_json::from_value::<Content>(event.content) else {
+        return Ok(());
+    };
+    let relation = content.relates_to.as_ref();
+    let mut data = data.write().await;
+    let state = get_shared_state!(data);
+    match event.kind.as_str() {
+        "m.room.message" => {
+            let rel_type = relation.and_then(|relation| relation.rel_type.as_deref());
+            if content.msgtype.as_deref() != Some("m.text") || rel_type == Some("m.replace") {
+                return Ok(());
+            }
+            let thread = relation
+                .filter(|_| rel_type == Some("m.thread"))
+                .and_then(|relation| relation.event_id.as_deref());
+            let incoming = Incoming {
+                guild_id: Some(platform.guild_id(room)),
+                channel_id: platform.channel_id(room, thread),
+                author: UserId::new(platform.registry.id(&event.sender)),
+                content: platform.parse(&content).await?,
+                link: permalink(room, Some(&event.event_id)),
+            };
+            handle_message(platform, state, incoming).await?;
+        }
+        "m.reaction" => {
+            let Some((Some(poll), Some(key))) = relation
+                .filter(|relation| relation.rel_type.as_deref() == Some("m.annotation"))
+                .map(|relation| (relation.event_id.as_deref(), relation.key.as_deref()))
+            else {
+                return Ok(());
+            };
+            if !platform.registry.contains(&format!("poll/{room}/{poll}")) {
+                return Ok(());
+            }
+            let (options, thread) = platform.poll_options(room, poll).await?;
+            let Some(voted_for) = options
+                .iter()
+                .enumerate()
+                .find(|(index, _)| option(*index).as_deref() == Some(key))
+                .map(|(_, &user_id)| UserId::new(user_id))
+            else {
+                return Ok(());
+            };
+            let user_id = UserId::new(platform.registry.id(&event.sender));
+            let response = handle_vote(
+                state,
+                platform.guild_id(room),
+                user_id,
+                platform.message_id(room, poll),
+                voted_for,
+            )?;
+            // Reactions are public anyway so the response goes in the thread instead of privately
+            if let Some(response) = response {
+                let content = platform.render(&format!("<@{user_id}> {response}"))?;
+                platform
+                    .post(
+                        platform.channel_id(room, thread.as_deref()),
+                        content,
+                        Some(event.event_id),
+                    )
+                    .await?;
+            }
+        }
+        _ => return Ok(()),
+    }
+    write_to_database!(state);
+    Ok(())
+}
+
+async fn sync(
+    platform: &Arc<Matrix>,
+    data: &Arc<RwLock<TypeMap>>,
+    since: &mut Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let mut query = vec![("filter", String::from(SYNC_FILTER))];
+    if let Some(since) = since {
+        query.push(("since", since.clone()));
+        query.push(("timeout", SYNC_TIMEOUT.to_string()));
+    }
+    let sync = platform
+        .call::<Sync>(Method::GET, &["sync"], &query, None)
+        .await?;
+    metrics().set_gateway_connected(true);
+    for room in sync.rooms.invite.keys() {
+        if let Err(why) = join(platform, data, room).await {
+            error!("Error joining Matrix room {room}: {why}");
+        }
+    }
+    // The first sync only catches up, messages sent while offline are ignored like on Discord
+    if since.is_some() {
+        for (room, joined) in sync.rooms.join {
+            for event in joined.timeline.events {
+                if let Err(why) = dispatch(platform, data, &room, event).await {
+                    error!("Error responding to Matrix event: {why}");
+                }
+            }
+        }
+    } else {
+        info!("Connected to Matrix");
+        ready(platform, data).await?;
+    }
+    *since = Some(sync.next_batch);
+    Ok(())
+}
+
+pub async fn run_matrix(
+    platform: Arc<Matrix>,
+    data: Arc<RwLock<TypeMap>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut since = None;
+    loop {
+        // The error is dropped before sleeping so the future stays Send
+        if let Err(why) = sync(&platform, &data, &mut since).await {
+            error!("Error syncing with Matrix: {why}");
+        } else {
+            continue;
+        }
+        metrics().set_gateway_connected(false);
+        warn!("Disconnected from Matrix, reconnecting");
+        sleep(RECONNECT_DELAY).await;
+    }
+}
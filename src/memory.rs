@@ -0,0 +1,294 @@
+use crate::platform::{ChatPlatform, Embed, Page, PlatformResult};
+use serenity::{async_trait, model::prelude::*};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Mutex, MutexGuard},
+};
+
+#[derive(Clone, Debug)]
+pub struct Sent {
+    pub id: MessageId,
+    pub channel_id: ChannelId,
+    pub content: String,
+    pub embed: Option<Embed>,
+    pub poll: bool,
+    pub replies: Vec<String>,
+    pub file: Option<(String, Vec<u8>)>,
+    pub page: Option<Page>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Thread {
+    pub id: ChannelId,
+    pub parent: ChannelId,
+    pub message_id: MessageId,
+    pub name: String,
+}
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    messages: Vec<Sent>,
+    threads: Vec<Thread>,
+    text_channels: HashSet<ChannelId>,
+    members: HashMap<GuildId, Vec<User>>,
+    roles: HashMap<(GuildId, UserId), HashSet<RoleId>>,
+}
+
+impl Inner {
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn push(
+        &mut self,
+        channel_id: ChannelId,
+        content: String,
+        embed: Option<Embed>,
+        poll: bool,
+    ) -> MessageId {
+        let id = MessageId::new(self.next_id());
+        self.messages.push(Sent {
+            id,
+            channel_id,
+            content,
+            embed,
+            poll,
+            replies: Vec::new(),
+            file: None,
+            page: None,
+        });
+        id
+    }
+
+    fn message(&mut self, channel_id: ChannelId, message_id: MessageId) -> Option<&mut Sent> {
+        self.messages
+            .iter_mut()
+            .find(|sent| sent.id == message_id && sent.channel_id == channel_id)
+    }
+}
+
+pub struct MemoryPlatform {
+    user_id: UserId,
+    inner: Mutex<Inner>,
+}
+
+impl Default for MemoryPlatform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryPlatform {
+    pub fn new() -> Self {
+        let mut inner = Inner::default();
+        let user_id = UserId::new(inner.next_id());
+        Self {
+            user_id,
+            inner: Mutex::new(inner),
+        }
+    }
+
+    fn inner(&self) -> MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn add_text_channel(&self) -> ChannelId {
+        let mut inner = self.inner();
+        let channel_id = ChannelId::new(inner.next_id());
+        inner.text_channels.insert(channel_id);
+        channel_id
+    }
+
+    pub fn add_member(&self, guild_id: GuildId, name: &str) -> UserId {
+        let mut inner = self.inner();
+        let mut user = User::default();
+        user.id = UserId::new(inner.next_id());
+        user.name = name.to_string();
+        let user_id = user.id;
+        inner.members.entry(guild_id).or_default().push(user);
+        user_id
+    }
+
+    pub fn messages(&self, channel_id: ChannelId) -> Vec<Sent> {
+        self.inner()
+            .messages
+            .iter()
+            .filter(|sent| sent.channel_id == channel_id)
+            .cloned()
+            .collect()
+    }
+
+    pub fn last_message(&self, channel_id: ChannelId) -> Option<Sent> {
+        self.messages(channel_id).pop()
+    }
+
+    pub fn threads(&self) -> Vec<Thread> {
+        self.inner().threads.clone()
+    }
+
+    pub fn roles(&self, guild_id: GuildId, user_id: UserId) -> Vec<RoleId> {
+        let mut roles = self
+            .inner()
+            .roles
+            .get(&(guild_id, user_id))
+            .map(|roles| roles.iter().copied().collect::<Vec<_>>())
+            .unwrap_or_default();
+        roles.sort();
+        roles
+    }
+
+    pub fn delete_message(&self, message_id: MessageId) {
+        self.inner().messages.retain(|sent| sent.id != message_id);
+    }
+}
+
+#[async_trait]
+impl ChatPlatform for MemoryPlatform {
+    fn current_user(&self) -> UserId {
+        self.user_id
+    }
+
+    async fn say(&self, channel_id: ChannelId, content: String) -> PlatformResult<MessageId> {
+        Ok(self.inner().push(channel_id, content, None, false))
+    }
+
+    async fn send_embed(
+        &self,
+        channel_id: ChannelId,
+        content: String,
+        embed: Embed,
+    ) -> PlatformResult<MessageId> {
+        Ok(self.inner().push(channel_id, content, Some(embed), false))
+    }
+
+    async fn send_poll(&self, channel_id: ChannelId, content: String) -> PlatformResult<MessageId> {
+        Ok(self.inner().push(channel_id, content, None, true))
+    }
+
+    async fn send_page(&self, channel_id: ChannelId, page: Page) -> PlatformResult<MessageId> {
+        let mut inner = self.inner();
+        let message_id = inner.push(channel_id, page.text(), None, false);
+        inner
+            .message(channel_id, message_id)
+            .ok_or("Unknown message")?
+            .page = Some(page);
+        Ok(message_id)
+    }
+
+    async fn send_file(
+        &self,
+        channel_id: ChannelId,
+        content: String,
+        file_name: String,
+        data: Vec<u8>,
+    ) -> PlatformResult<()> {
+        let mut inner = self.inner();
+        let message_id = inner.push(channel_id, content, None, false);
+        inner
+            .message(channel_id, message_id)
+            .ok_or("Unknown message")?
+            .file = Some((file_name, data));
+        Ok(())
+    }
+
+    async fn edit(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: String,
+    ) -> PlatformResult<()> {
+        self.inner()
+            .message(channel_id, message_id)
+            .ok_or("Unknown message")?
+            .content = content;
+        Ok(())
+    }
+
+    async fn reply(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: String,
+    ) -> PlatformResult<()> {
+        self.inner()
+            .message(channel_id, message_id)
+            .ok_or("Unknown message")?
+            .replies
+            .push(content);
+        Ok(())
+    }
+
+    async fn message_exists(&self, channel_id: ChannelId, message_id: MessageId) -> bool {
+        self.inner().message(channel_id, message_id).is_some()
+    }
+
+    async fn create_thread(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        name: String,
+    ) -> PlatformResult<ChannelId> {
+        let mut inner = self.inner();
+        inner
+            .message(channel_id, message_id)
+            .ok_or("Unknown message")?;
+        let id = ChannelId::new(inner.next_id());
+        inner.threads.push(Thread {
+            id,
+            parent: channel_id,
+            message_id,
+            name,
+        });
+        Ok(id)
+    }
+
+    async fn is_text_channel(&self, channel_id: ChannelId) -> bool {
+        self.inner().text_channels.contains(&channel_id)
+    }
+
+    async fn members(&self, guild_id: GuildId) -> PlatformResult<Vec<User>> {
+        Ok(self
+            .inner()
+            .members
+            .get(&guild_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn role_members(
+        &self,
+        guild_id: GuildId,
+        role_id: RoleId,
+    ) -> PlatformResult<Vec<UserId>> {
+        let mut members = self
+            .inner()
+            .roles
+            .iter()
+            .filter(|((guild, _), roles)| *guild == guild_id && roles.contains(&role_id))
+            .map(|((_, user_id), _)| *user_id)
+            .collect::<Vec<_>>();
+        members.sort();
+        Ok(members)
+    }
+
+    async fn set_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+        assigned: bool,
+    ) -> PlatformResult<()> {
+        let mut inner = self.inner();
+        let roles = inner.roles.entry((guild_id, user_id)).or_default();
+        if assigned {
+            roles.insert(role_id);
+        } else {
+            roles.remove(&role_id);
+        }
+        Ok(())
+    }
+}
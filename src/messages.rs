@@ -1,23 +1,40 @@
 #[macro_export]
 macro_rules! send_message_with_leaderboard {
-    ($ctx:ident, $guilds:expr, $guild_id:ident, $channel_id:expr, $users:expr, $message:expr) => {
-        $channel_id
-            .say(
-                &$ctx.http,
-                construct_leaderboard($users, $guilds, $guild_id, &mut $message).build(),
-            )
-            .await?
+    ($platform:ident, $guilds:expr, $guild_id:ident, $channel_id:expr, $users:expr, $message:expr) => {
+        let channel_id = $channel_id;
+        let mut message = $message.clone();
+        let sent = match render_leaderboard($users, $guilds, $guild_id).await {
+            Some(png) => $platform
+                .send_file(
+                    channel_id,
+                    message.push("The current leaderboard:").build(),
+                    String::from("leaderboard.png"),
+                    png,
+                )
+                .await
+                .map_err(|why| warn!("Could not send the leaderboard image: {why}"))
+                .is_ok(),
+            None => false,
+        };
+        if !sent {
+            $platform
+                .say(
+                    channel_id,
+                    construct_leaderboard($users, $guilds, $guild_id, &mut $message).build(),
+                )
+                .await?;
+        }
     };
 }
 
 #[macro_export]
 macro_rules! send_help_message {
-    ($ctx:ident, $message:expr, $user_id:ident, $channel:expr, $default_channel:expr, $thread:expr) => {
-        $channel.say(&$ctx.http, construct_format_message!(
+    ($platform:ident, $message:expr, $user_id:ident, $channel:expr, $default_channel:expr, $thread:expr, $site:expr) => {
+        $platform.say($channel, construct_format_message!(
         construct_channel_message!(
             $message
                 .push("Hi I'm LeetCode Daily, here to motivate you to do ")
-                .push_named_link("LeetCode", "https://leetcode.com/problemset")
+                .push_named_link("LeetCode", format!("{}/problemset", $site.url()))
                 .push_line(" questions every single day 🤓\n\nI operate on a default channel and I create a thread in that channel when a new daily question comes out"),
             $user_id,
             $default_channel,
@@ -27,10 +44,16 @@ macro_rules! send_help_message {
         .push_line("\
 * `/help`: Shows this help message
 * `/random [free | paid | easy | medium | hard] ...`: Send a random question with optional fields to filter by difficulty or whether it is subscription only, if not run in a thread it will create a thread for it
-* `/scores`: Shows the current leaderboard
+* `/scores [page|me] [by:score|monthly|streak|votes|weekly] [role:@role] [top:N]`: Shows the current leaderboard 10 people at a time, starting from the first page or the one you are on. It can be sorted by score, questions completed this month, streak, votes received or weekly contest questions, limited to the members of a role or cut off after the top N
+* `/season [YYYY-MM]`: Shows the final standings of last month or any earlier month
+* `/halloffame`: Shows the champions of past seasons and everyone's all-time points
+* `/profile [@user]`: Shows your or another user's scores, streak and badges
+* `/export [scores|history|monthly] [csv|json]`: Uploads the scores, past months or this month's records of the server as a CSV or JSON file
 * `/top [number]`: Shows the top 3 or any number up to 10 scores and monthly records across all servers
 * `/poll`: Start a poll for today's submissions or reply to an existing one if it has already started, has to be run in the current daily thread
 * `/active [weekly|daily] [toggle]`: Check whether some features of the bot are currently active or toggle them on and off
+* `/site [com|cn]`: Check or change whether questions are taken from leetcode.com or leetcode.cn
+* `/roles [add @role [rank number|streak days|badge name]|remove @role]`: Check or change which roles are given to the top ranks of the leaderboard, to streaks of some number of days or to the holders of a badge like `first_hard`, they are updated after every daily rollover
         \n")
         .push("To share your code you have to put it in a spoiler tag and wrap it with ")
         .push_safe("```code```")
@@ -63,41 +86,41 @@ macro_rules! construct_badge_message {
 
 #[macro_export]
 macro_rules! send_daily_message_with_leaderboard {
-    ($ctx:ident, $state:expr, $guild_id:ident, $data:ident, $message:expr) => {
+    ($platform:ident, $guilds:expr, $guild_id:ident, $data:ident, $message:expr) => {
         let channel_id = get_channel_from_guild!($data);
         $data.poll_id = None;
-        let message_id = send_leetcode_daily_question_message($ctx, channel_id)
-            .await?
-            .id;
+        let (message_id, difficulty) =
+            send_leetcode_daily_question_message($platform, channel_id, $data.site).await?;
+        $data.difficulty = Some(difficulty);
         create_thread_from_message!(
-            $ctx,
-            $state,
+            $platform,
+            $guilds,
             $guild_id,
             $data,
             $message,
             channel_id,
             message_id,
             $data.thread_id,
-            Utc::now().format("%d/%m/%Y").to_string()
+            $crate::clock().now().format("%d/%m/%Y").to_string()
         )
     };
 }
 
 #[macro_export]
 macro_rules! send_invalid_channel_id_message {
-    ($ctx:ident, $msg:ident) => {
-        $msg.channel_id
-            .say(&$ctx.http, "Invalid channel ID")
+    ($platform:ident, $msg:ident) => {
+        $platform
+            .say($msg.channel_id, String::from("Invalid channel ID"))
             .await?;
     };
 }
 
 #[macro_export]
 macro_rules! send_channel_usage_message {
-    ($ctx:ident, $channel:expr) => {
-        $channel
+    ($platform:ident, $channel:expr) => {
+        $platform
             .say(
-                &$ctx.http,
+                $channel,
                 MessageBuilder::new()
                     .push("Usage:")
                     .push_codeblock("/channel channel_id", None)
@@ -109,24 +132,17 @@ macro_rules! send_channel_usage_message {
 
 #[macro_export]
 macro_rules! create_thread_from_message {
-    ($ctx:ident, $channel_id:ident, $message_id:ident, $thread_name:expr) => {
-        $channel_id
-            .create_thread_from_message(
-                &$ctx.http,
-                $message_id,
-                CreateThread::new($thread_name)
-                    .kind(ChannelType::PublicThread)
-                    .auto_archive_duration(AutoArchiveDuration::OneDay),
-            )
+    ($platform:ident, $channel_id:ident, $message_id:ident, $thread_name:expr) => {
+        $platform
+            .create_thread($channel_id, $message_id, $thread_name.to_string())
             .await
-            .map(|channel| channel.id)
             .ok()
     };
-    ($ctx:ident, $state:expr, $guild_id:ident, $data:ident, $message:expr, $channel_id:ident, $message_id:ident, $thread_id:expr, $thread_name:expr) => {
-        $thread_id = create_thread_from_message!($ctx, $channel_id, $message_id, $thread_name);
+    ($platform:ident, $guilds:expr, $guild_id:ident, $data:ident, $message:expr, $channel_id:ident, $message_id:ident, $thread_id:expr, $thread_name:expr) => {
+        $thread_id = create_thread_from_message!($platform, $channel_id, $message_id, $thread_name);
         send_message_with_leaderboard!(
-            $ctx,
-            &mut $state.guilds,
+            $platform,
+            $guilds,
             $guild_id,
             $thread_id.ok_or("Failed to create thread")?,
             &$data.users,
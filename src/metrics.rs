@@ -0,0 +1,260 @@
+use chrono::Utc;
+use serenity::model::prelude::GuildId;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+#[derive(Default)]
+struct Summary {
+    count: u64,
+    sum: f64,
+}
+
+impl Summary {
+    fn observe(&mut self, duration: Duration) {
+        self.count += 1;
+        self.sum += duration.as_secs_f64();
+    }
+}
+
+#[derive(Default)]
+struct Values {
+    commands: BTreeMap<String, u64>,
+    submissions: BTreeMap<GuildId, u64>,
+    submissions_today: BTreeMap<GuildId, u64>,
+    leetcode_requests: BTreeMap<String, Summary>,
+    leetcode_failures: BTreeMap<String, u64>,
+    scheduler_last_run: BTreeMap<String, i64>,
+    scheduler_alive: BTreeMap<String, bool>,
+    database_writes: Summary,
+    database_size: u64,
+    database_last_write: Option<i64>,
+    gateway_connected: bool,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    values: Mutex<Values>,
+}
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::default)
+}
+
+macro_rules! with_values {
+    ($self:ident, $values:ident => $body:expr) => {
+        if let Ok(mut $values) = $self.values.lock() {
+            $body;
+        }
+    };
+}
+
+impl Metrics {
+    pub fn record_command(&self, command: &str) {
+        with_values!(self, values => *values.commands.entry(command.to_string()).or_default() += 1);
+    }
+
+    pub fn record_submission(&self, guild_id: GuildId) {
+        with_values!(self, values => {
+            *values.submissions.entry(guild_id).or_default() += 1;
+            *values.submissions_today.entry(guild_id).or_default() += 1;
+        });
+    }
+
+    pub fn reset_submissions_today(&self, guild_id: GuildId) {
+        with_values!(self, values => values.submissions_today.insert(guild_id, 0));
+    }
+
+    pub fn record_leetcode_request(&self, operation: &str, duration: Duration, success: bool) {
+        with_values!(self, values => {
+            values
+                .leetcode_requests
+                .entry(operation.to_string())
+                .or_default()
+                .observe(duration);
+            if !success {
+                *values
+                    .leetcode_failures
+                    .entry(operation.to_string())
+                    .or_default() += 1;
+            }
+        });
+    }
+
+    pub fn record_scheduler_run(&self, scheduler: &str) {
+        with_values!(self, values => values
+            .scheduler_last_run
+            .insert(scheduler.to_string(), Utc::now().timestamp()));
+    }
+
+    pub fn record_database_write(&self, duration: Duration, size: usize) {
+        with_values!(self, values => {
+            values.database_writes.observe(duration);
+            values.database_size = size as u64;
+            values.database_last_write = Some(Utc::now().timestamp());
+        });
+    }
+
+    pub fn set_scheduler_alive(&self, scheduler: &str, alive: bool) {
+        with_values!(self, values => values
+            .scheduler_alive
+            .insert(scheduler.to_string(), alive));
+    }
+
+    pub fn set_gateway_connected(&self, connected: bool) {
+        with_values!(self, values => values.gateway_connected = connected);
+    }
+
+    pub fn gateway_connected(&self) -> bool {
+        self.values
+            .lock()
+            .is_ok_and(|values| values.gateway_connected)
+    }
+
+    pub fn schedulers(&self) -> BTreeMap<String, bool> {
+        self.values
+            .lock()
+            .map(|values| values.scheduler_alive.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn scheduler_last_runs(&self) -> BTreeMap<String, i64> {
+        self.values
+            .lock()
+            .map(|values| values.scheduler_last_run.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn database_last_write(&self) -> Option<i64> {
+        self.values
+            .lock()
+            .ok()
+            .and_then(|values| values.database_last_write)
+    }
+
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        if let Ok(values) = self.values.lock() {
+            render_family(
+                &mut output,
+                "leetcode_daily_commands_total",
+                "counter",
+                "Commands handled by type",
+                values
+                    .commands
+                    .iter()
+                    .map(|(command, count)| ("", format!("command=\"{command}\""), *count as f64)),
+            );
+            render_family(
+                &mut output,
+                "leetcode_daily_submissions_total",
+                "counter",
+                "Daily submissions accepted per guild",
+                values.submissions.iter().map(|(guild_id, count)| {
+                    ("", format!("guild_id=\"{guild_id}\""), *count as f64)
+                }),
+            );
+            render_family(
+                &mut output,
+                "leetcode_daily_submissions_today",
+                "gauge",
+                "Daily submissions accepted per guild since the last rollover",
+                values.submissions_today.iter().map(|(guild_id, count)| {
+                    ("", format!("guild_id=\"{guild_id}\""), *count as f64)
+                }),
+            );
+            render_family(
+                &mut output,
+                "leetcode_daily_leetcode_request_duration_seconds",
+                "summary",
+                "Latency of requests to the LeetCode API",
+                values
+                    .leetcode_requests
+                    .iter()
+                    .flat_map(|(operation, summary)| {
+                        [
+                            ("_sum", format!("operation=\"{operation}\""), summary.sum),
+                            (
+                                "_count",
+                                format!("operation=\"{operation}\""),
+                                summary.count as f64,
+                            ),
+                        ]
+                    }),
+            );
+            render_family(
+                &mut output,
+                "leetcode_daily_leetcode_request_failures_total",
+                "counter",
+                "Failed requests to the LeetCode API",
+                values.leetcode_failures.iter().map(|(operation, count)| {
+                    ("", format!("operation=\"{operation}\""), *count as f64)
+                }),
+            );
+            render_family(
+                &mut output,
+                "leetcode_daily_scheduler_last_run_timestamp_seconds",
+                "gauge",
+                "Unix time each scheduler last completed a run",
+                values.scheduler_last_run.iter().map(|(scheduler, time)| {
+                    ("", format!("scheduler=\"{scheduler}\""), *time as f64)
+                }),
+            );
+            render_family(
+                &mut output,
+                "leetcode_daily_scheduler_alive",
+                "gauge",
+                "Whether each scheduler is still running",
+                values.scheduler_alive.iter().map(|(scheduler, &alive)| {
+                    (
+                        "",
+                        format!("scheduler=\"{scheduler}\""),
+                        u8::from(alive).into(),
+                    )
+                }),
+            );
+            render_family(
+                &mut output,
+                "leetcode_daily_database_write_duration_seconds",
+                "summary",
+                "Time taken to write the database to disk",
+                [
+                    ("_sum", String::new(), values.database_writes.sum),
+                    ("_count", String::new(), values.database_writes.count as f64),
+                ]
+                .into_iter(),
+            );
+            render_family(
+                &mut output,
+                "leetcode_daily_database_size_bytes",
+                "gauge",
+                "Size of the database after the last write",
+                [("", String::new(), values.database_size as f64)].into_iter(),
+            );
+        }
+        output
+    }
+}
+
+fn render_family(
+    output: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    samples: impl Iterator<Item = (&'static str, String, f64)>,
+) {
+    let _ = writeln!(output, "# HELP {name} {help}");
+    let _ = writeln!(output, "# TYPE {name} {kind}");
+    for (suffix, labels, value) in samples {
+        if labels.is_empty() {
+            let _ = writeln!(output, "{name}{suffix} {value}");
+        } else {
+            let _ = writeln!(output, "{name}{suffix}{{{labels}}} {value}");
+        }
+    }
+}
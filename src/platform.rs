@@ -0,0 +1,269 @@
+use serenity::{
+    async_trait,
+    model::prelude::{ChannelId, Colour, GuildId, MessageId, RoleId, User, UserId},
+};
+use std::error::Error;
+
+pub type PlatformResult<T> = Result<T, Box<dyn Error>>;
+
+#[derive(Clone, Debug, Default)]
+pub struct Embed {
+    pub title: String,
+    pub url: String,
+    pub colour: Colour,
+    pub fields: Vec<(String, String)>,
+}
+
+// One page of the leaderboard, platforms with buttons let people move between pages in place
+#[derive(Clone, Debug, Default)]
+pub struct Page {
+    pub title: String,
+    pub content: String,
+    pub page: usize,
+    pub pages: usize,
+    // The /scores arguments other than the page, so turning the page keeps the same ranking
+    pub args: String,
+}
+
+impl Page {
+    pub fn footer(&self) -> String {
+        format!("Page {} of {}", self.page + 1, self.pages)
+    }
+
+    pub fn text(&self) -> String {
+        format!(
+            "**{}**\n{}\n{}, run `/scores [page|me] {}` to see another page",
+            self.title,
+            self.content.trim_end(),
+            self.footer(),
+            self.args
+        )
+    }
+}
+
+pub fn content_type(file_name: &str) -> &'static str {
+    match file_name.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("csv") => "text/csv",
+        Some("json") => "application/json",
+        Some("png") => "image/png",
+        _ => "application/octet-stream",
+    }
+}
+
+const FENCE: &str = "```";
+
+// Splits content into messages of at most limit characters at line breaks, a code block that is
+// cut in two is closed and reopened so both halves still render
+pub fn split_message(content: &str, limit: usize) -> Vec<String> {
+    if content.chars().count() <= limit {
+        return vec![content.to_string()];
+    }
+    // Room for closing and reopening a code block with its language
+    let limit = limit.saturating_sub(2 * FENCE.len() + 16).max(1);
+    let mut messages = Vec::new();
+    let mut message = String::new();
+    let mut length = 0;
+    for line in content.split_inclusive('\n') {
+        // Lines that don't fit in a message on their own are cut wherever they have to be
+        for piece in line.chars().collect::<Vec<_>>().chunks(limit) {
+            if length + piece.len() > limit {
+                // The language after the opening fence is kept for the reopened block
+                let code_block = (message.matches(FENCE).count() % 2 == 1).then(|| {
+                    message
+                        .rsplit(FENCE)
+                        .next()
+                        .and_then(|rest| rest.lines().next())
+                        .unwrap_or_default()
+                        .to_string()
+                });
+                if code_block.is_some() {
+                    message.push_str(FENCE);
+                }
+                messages.push(std::mem::take(&mut message));
+                length = 0;
+                if let Some(language) = code_block {
+                    message = format!("{FENCE}{language}\n");
+                    length = message.chars().count();
+                }
+            }
+            message.extend(piece);
+            length += piece.len();
+        }
+    }
+    messages.push(message);
+    messages.retain(|message| !message.trim().is_empty());
+    if messages.is_empty() {
+        messages.push(content.to_string());
+    }
+    messages
+}
+
+// For messages that are edited in place and can't be split
+pub fn truncate_message(content: &str, limit: usize) -> String {
+    if content.chars().count() <= limit {
+        return content.to_string();
+    }
+    let messages = split_message(content, limit.saturating_sub(1));
+    format!("{}…", messages[0].trim_end())
+}
+
+#[derive(Clone, Debug)]
+pub struct Incoming {
+    pub guild_id: Option<GuildId>,
+    pub channel_id: ChannelId,
+    pub author: UserId,
+    pub content: String,
+    pub link: String,
+}
+
+// Message content uses Discord markdown and mentions, adapters translate it
+#[async_trait]
+pub trait ChatPlatform: Send + Sync {
+    fn current_user(&self) -> UserId;
+
+    // The ID the platform itself shows for a user, which is what admins are configured with
+    fn native_user_id(&self, user_id: UserId) -> String {
+        user_id.to_string()
+    }
+
+    async fn say(&self, channel_id: ChannelId, content: String) -> PlatformResult<MessageId>;
+
+    async fn send_embed(
+        &self,
+        channel_id: ChannelId,
+        content: String,
+        embed: Embed,
+    ) -> PlatformResult<MessageId>;
+
+    async fn send_poll(&self, channel_id: ChannelId, content: String) -> PlatformResult<MessageId>;
+
+    async fn send_page(&self, channel_id: ChannelId, page: Page) -> PlatformResult<MessageId>;
+
+    async fn send_file(
+        &self,
+        channel_id: ChannelId,
+        content: String,
+        file_name: String,
+        data: Vec<u8>,
+    ) -> PlatformResult<()>;
+
+    async fn edit(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: String,
+    ) -> PlatformResult<()>;
+
+    async fn reply(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: String,
+    ) -> PlatformResult<()>;
+
+    async fn message_exists(&self, channel_id: ChannelId, message_id: MessageId) -> bool;
+
+    async fn create_thread(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        name: String,
+    ) -> PlatformResult<ChannelId>;
+
+    async fn is_text_channel(&self, channel_id: ChannelId) -> bool;
+
+    async fn members(&self, guild_id: GuildId) -> PlatformResult<Vec<User>>;
+
+    async fn role_members(&self, guild_id: GuildId, role_id: RoleId)
+        -> PlatformResult<Vec<UserId>>;
+
+    async fn set_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+        assigned: bool,
+    ) -> PlatformResult<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lengths(messages: &[String]) -> Vec<usize> {
+        messages
+            .iter()
+            .map(|message| message.chars().count())
+            .collect()
+    }
+
+    #[test]
+    fn messages_at_the_limit_are_kept_whole() {
+        let content = format!("{}\n{}", "a".repeat(49), "b".repeat(50));
+        assert_eq!(split_message(&content, 100), vec![content.clone()]);
+        assert_eq!(truncate_message(&content, 100), content);
+        let messages = split_message(&format!("{content}c"), 100);
+        assert_eq!(messages.len(), 2);
+        assert!(lengths(&messages).iter().all(|&length| length <= 100));
+        assert_eq!(messages.concat(), format!("{content}c"));
+    }
+
+    #[test]
+    fn long_lines_are_cut_anywhere() {
+        let content = "x".repeat(250);
+        let messages = split_message(&content, 100);
+        assert!(messages.len() > 2);
+        assert!(lengths(&messages).iter().all(|&length| length <= 100));
+        assert_eq!(messages.concat(), content);
+    }
+
+    #[test]
+    fn multibyte_characters_count_once() {
+        // Cutting by bytes would land inside a character
+        let content = "é😀".repeat(60);
+        let messages = split_message(&content, 100);
+        assert!(lengths(&messages).iter().all(|&length| length <= 100));
+        assert_eq!(messages.concat(), content);
+        let truncated = truncate_message(&content, 100);
+        assert!(truncated.chars().count() <= 100);
+        assert!(truncated.ends_with("😀…") || truncated.ends_with("é…"));
+    }
+
+    #[test]
+    fn code_blocks_are_reopened_after_a_split() {
+        let code = (0..30)
+            .map(|line| format!("let x{line} = {line};\n"))
+            .collect::<String>();
+        let content = format!("Solution:\n```rust\n{code}```\nDone");
+        let messages = split_message(&content, 200);
+        assert!(messages.len() > 1);
+        assert!(lengths(&messages).iter().all(|&length| length <= 200));
+        for message in &messages {
+            assert_eq!(message.matches(FENCE).count() % 2, 0, "{message}");
+        }
+        assert!(messages[1].starts_with("```rust\n"));
+        assert!(messages[0].ends_with("```"));
+        assert!(messages.last().unwrap().ends_with("```\nDone"));
+        // Dropping the added fences gives back the content
+        let rejoined = messages
+            .iter()
+            .enumerate()
+            .map(|(index, message)| {
+                let message = if index > 0 {
+                    message.strip_prefix("```rust\n").unwrap()
+                } else {
+                    message
+                };
+                if index + 1 < messages.len() {
+                    message.strip_suffix(FENCE).unwrap()
+                } else {
+                    message
+                }
+            })
+            .collect::<String>();
+        assert_eq!(rejoined, content);
+        let truncated = truncate_message(&content, 200);
+        assert!(truncated.chars().count() <= 200);
+        assert!(truncated.ends_with("```…"));
+    }
+}
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fs::{read_to_string, write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+use tokio::task::spawn_blocking;
+use tracing::error;
+
+// The database only refers to the threads and polls of the current day and week, so older
+// messages can be forgotten
+const RECENT: usize = 1000;
+
+#[derive(Default, Serialize, Deserialize)]
+struct Ids {
+    next: u64,
+    ids: HashMap<String, u64>,
+    #[serde(default)]
+    recent: VecDeque<(String, u64)>,
+    #[serde(skip)]
+    recent_ids: HashMap<String, u64>,
+    #[serde(skip)]
+    natives: HashMap<u64, String>,
+    #[serde(skip)]
+    generation: u64,
+}
+
+// Maps the string IDs other platforms use onto the numeric IDs stored in the database. Users,
+// channels and guilds are kept for good while messages and threads only until enough newer ones
+// come along
+pub struct Registry {
+    path: PathBuf,
+    ids: Mutex<Ids>,
+    // Generation of the last snapshot saved, so a write that was overtaken is dropped
+    saved: Arc<Mutex<u64>>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Registry {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut ids = if path.exists() {
+            serde_json::from_str::<Ids>(&read_to_string(path)?)?
+        } else {
+            Ids::default()
+        };
+        ids.recent_ids = ids.recent.iter().cloned().collect();
+        ids.natives = ids
+            .ids
+            .iter()
+            .chain(&ids.recent_ids)
+            .map(|(native, &id)| (id, native.clone()))
+            .collect();
+        Ok(Self {
+            path: path.to_path_buf(),
+            ids: Mutex::new(ids),
+            saved: Arc::new(Mutex::new(0)),
+        })
+    }
+
+    // Writes on a blocking thread, the snapshot is taken here so the writes land in order
+    fn save(&self, ids: &mut Ids) {
+        ids.generation += 1;
+        let generation = ids.generation;
+        let contents = match serde_json::to_string_pretty(&*ids) {
+            Ok(contents) => contents,
+            Err(why) => {
+                error!("Error saving IDs to {}: {why}", self.path.display());
+                return;
+            }
+        };
+        let path = self.path.clone();
+        let saved = self.saved.clone();
+        spawn_blocking(move || {
+            let mut saved = lock(&saved);
+            if *saved >= generation {
+                return;
+            }
+            match write(&path, contents) {
+                Ok(()) => *saved = generation,
+                Err(why) => error!("Error saving IDs to {}: {why}", path.display()),
+            }
+        });
+    }
+
+    pub fn id(&self, native: &str) -> u64 {
+        let mut ids = lock(&self.ids);
+        if let Some(&id) = ids.ids.get(native) {
+            return id;
+        }
+        ids.next += 1;
+        let id = ids.next;
+        ids.ids.insert(native.to_string(), id);
+        ids.natives.insert(id, native.to_string());
+        self.save(&mut ids);
+        id
+    }
+
+    // IDs of messages and threads, which stop resolving once RECENT newer ones were handed out
+    pub fn recent_id(&self, native: &str) -> u64 {
+        let mut ids = lock(&self.ids);
+        if let Some(&id) = ids.ids.get(native).or(ids.recent_ids.get(native)) {
+            return id;
+        }
+        ids.next += 1;
+        let id = ids.next;
+        ids.recent.push_back((native.to_string(), id));
+        ids.recent_ids.insert(native.to_string(), id);
+        ids.natives.insert(id, native.to_string());
+        while ids.recent.len() > RECENT {
+            if let Some((native, id)) = ids.recent.pop_front() {
+                ids.recent_ids.remove(&native);
+                ids.natives.remove(&id);
+            }
+        }
+        self.save(&mut ids);
+        id
+    }
+
+    pub fn contains(&self, native: &str) -> bool {
+        let ids = lock(&self.ids);
+        ids.ids.contains_key(native) || ids.recent_ids.contains_key(native)
+    }
+
+    pub fn native(&self, id: u64) -> Option<String> {
+        lock(&self.ids).natives.get(&id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::runtime::Runtime;
+
+    // Dropping the runtime waits for the writes still running on its blocking threads
+    fn with_registry(path: &Path, run: impl FnOnce(&Registry)) {
+        let runtime = Runtime::new().unwrap();
+        runtime.block_on(async { run(&Registry::open(path).unwrap()) });
+        drop(runtime);
+    }
+
+    #[test]
+    fn ids_survive_a_restart() {
+        let path = tempfile::tempdir().unwrap().into_path().join("ids.json");
+        let (mut user, mut thread) = (0, 0);
+        with_registry(&path, |registry| {
+            user = registry.id("U1");
+            thread = registry.recent_id("C1/1.0");
+            assert_eq!(registry.id("U1"), user);
+            assert_eq!(registry.recent_id("C1/1.0"), thread);
+            assert_ne!(user, thread);
+        });
+        with_registry(&path, |registry| {
+            assert_eq!(registry.id("U1"), user);
+            assert_eq!(registry.native(thread).as_deref(), Some("C1/1.0"));
+            assert!(registry.contains("C1/1.0"));
+            // IDs are never handed out twice
+            assert!(registry.id("U2") > thread);
+        });
+    }
+
+    #[test]
+    fn only_recent_messages_are_kept() {
+        let path = tempfile::tempdir().unwrap().into_path().join("ids.json");
+        with_registry(&path, |registry| {
+            let user = registry.id("U1");
+            let first = registry.recent_id("C1/0");
+            for message in 1..=RECENT {
+                registry.recent_id(&format!("C1/{message}"));
+            }
+            assert!(registry.native(first).is_none());
+            assert!(!registry.contains("C1/0"));
+            assert!(registry.contains(&format!("C1/{RECENT}")));
+            assert_eq!(registry.native(user).as_deref(), Some("U1"));
+        });
+        let saved = serde_json::from_str::<Ids>(&read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.recent.len(), RECENT);
+        assert_eq!(saved.recent.front().unwrap().0, "C1/1");
+        assert_eq!(saved.ids.len(), 1);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{badges::Badge, platform::ChatPlatform, Data, UserInfo};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    model::prelude::{GuildId, RoleId, UserId},
+    utils::MessageBuilder,
+};
+use std::cmp::Reverse;
+use tracing::warn;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum Reward {
+    Rank(usize),
+    Streak(u32),
+    Badge(Badge),
+}
+
+impl Reward {
+    pub fn parse(kind: &str, value: &str) -> Option<Self> {
+        match kind {
+            "rank" => value
+                .parse()
+                .ok()
+                .filter(|&rank| rank > 0)
+                .map(Reward::Rank),
+            "streak" => value
+                .parse()
+                .ok()
+                .filter(|&days| days > 0)
+                .map(Reward::Streak),
+            "badge" => Badge::parse(value).map(Reward::Badge),
+            _ => None,
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            Reward::Rank(1) => String::from("first place on the leaderboard"),
+            Reward::Rank(rank) => format!("the top {rank} of the leaderboard"),
+            Reward::Streak(days) => format!(
+                "a streak of at least {days} {}",
+                if *days > 1 { "days" } else { "day" }
+            ),
+            Reward::Badge(badge) => format!("the {} badge", badge.name()),
+        }
+    }
+
+    fn qualified(&self, users: &UserInfo) -> Vec<UserId> {
+        let mut qualified: Vec<UserId> = match self {
+            Reward::Rank(rank) => {
+                // Same order as the leaderboard, users without points don't rank
+                let mut leaderboard = users
+                    .iter()
+                    .filter(|(_, status)| status.score > 0)
+                    .collect::<Vec<_>>();
+                leaderboard.sort_by_key(|(id, status)| {
+                    (Reverse(status.score), Reverse(status.monthly_record), **id)
+                });
+                leaderboard
+                    .into_iter()
+                    .take(*rank)
+                    .map(|(id, _)| *id)
+                    .collect()
+            }
+            Reward::Streak(days) => users
+                .iter()
+                .filter(|(_, status)| status.streak >= *days)
+                .map(|(id, _)| *id)
+                .collect(),
+            Reward::Badge(badge) => users
+                .iter()
+                .filter(|(_, status)| status.badges.iter().any(|earned| earned.badge == *badge))
+                .map(|(id, _)| *id)
+                .collect(),
+        };
+        qualified.sort();
+        qualified
+    }
+}
+
+// The users the bot gave the role to are kept so it is only taken back from them
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RoleReward {
+    pub role_id: RoleId,
+    pub reward: Reward,
+    #[serde(default)]
+    pub holders: Vec<UserId>,
+}
+
+pub fn parse_role(mention: &str) -> Option<RoleId> {
+    mention
+        .trim_start_matches("<@&")
+        .trim_end_matches('>')
+        .parse::<u64>()
+        .ok()
+        .filter(|&id| id > 0)
+        .map(RoleId::new)
+}
+
+pub fn construct_role_rewards<'a>(
+    data: &Data,
+    message: &'a mut MessageBuilder,
+) -> &'a mut MessageBuilder {
+    if data.role_rewards.is_empty() {
+        return message.push("No role rewards have been set up");
+    }
+    message.push_line("Role rewards, updated after every daily rollover:");
+    for role_reward in &data.role_rewards {
+        message.role(role_reward.role_id).push_line(format!(
+            " for {} ({} {})",
+            role_reward.reward.description(),
+            role_reward.holders.len(),
+            if role_reward.holders.len() == 1 {
+                "member"
+            } else {
+                "members"
+            }
+        ));
+    }
+    message
+}
+
+pub async fn remove_role_reward(
+    platform: &dyn ChatPlatform,
+    guild_id: &GuildId,
+    data: &mut Data,
+    role_id: RoleId,
+) -> bool {
+    let Some(index) = data
+        .role_rewards
+        .iter()
+        .position(|role_reward| role_reward.role_id == role_id)
+    else {
+        return false;
+    };
+    let role_reward = data.role_rewards.remove(index);
+    for user_id in role_reward.holders {
+        if let Err(why) = platform.set_role(*guild_id, user_id, role_id, false).await {
+            warn!("Could not remove role {role_id} from {user_id}: {why}");
+        }
+    }
+    true
+}
+
+// Gives the role to everyone who qualifies and takes it back from everyone who no longer does
+pub async fn update_role_rewards(platform: &dyn ChatPlatform, guild_id: &GuildId, data: &mut Data) {
+    for role_reward in data.role_rewards.iter_mut() {
+        let qualified = role_reward.reward.qualified(&data.users);
+        let role_id = role_reward.role_id;
+        let mut holders = Vec::new();
+        for &user_id in role_reward.holders.iter() {
+            if qualified.contains(&user_id) {
+                holders.push(user_id);
+            } else if let Err(why) = platform.set_role(*guild_id, user_id, role_id, false).await {
+                warn!("Could not remove role {role_id} from {user_id}: {why}");
+                holders.push(user_id);
+            }
+        }
+        for user_id in qualified {
+            if role_reward.holders.contains(&user_id) {
+                continue;
+            }
+            match platform.set_role(*guild_id, user_id, role_id, true).await {
+                Ok(()) => holders.push(user_id),
+                Err(why) => warn!("Could not give role {role_id} to {user_id}: {why}"),
+            }
+        }
+        holders.sort();
+        role_reward.holders = holders;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{badges::EarnedBadge, MemoryPlatform, Status};
+    use chrono::NaiveDate;
+
+    const GUILD: GuildId = GuildId::new(1);
+    const ROLE: RoleId = RoleId::new(100);
+
+    fn user(id: u64) -> UserId {
+        UserId::new(id)
+    }
+
+    fn rewarded(reward: Reward, statuses: Vec<Status>) -> Data {
+        let mut data = Data::default();
+        for (index, status) in statuses.into_iter().enumerate() {
+            data.users.insert(user(index as u64 + 1), status);
+        }
+        data.role_rewards.push(RoleReward {
+            role_id: ROLE,
+            reward,
+            holders: Vec::new(),
+        });
+        data
+    }
+
+    fn scored(score: usize) -> Status {
+        Status {
+            score,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn rank_roles_follow_the_leaderboard() {
+        let platform = MemoryPlatform::new();
+        let mut data = rewarded(Reward::Rank(1), vec![scored(5), scored(3), scored(0)]);
+        update_role_rewards(&platform, &GUILD, &mut data).await;
+        assert_eq!(platform.role_members(GUILD, ROLE).await.unwrap(), [user(1)]);
+        assert_eq!(data.role_rewards[0].holders, [user(1)]);
+
+        // Overtaking first place moves the role over
+        data.users.get_mut(&user(2)).unwrap().score = 10;
+        update_role_rewards(&platform, &GUILD, &mut data).await;
+        assert_eq!(platform.role_members(GUILD, ROLE).await.unwrap(), [user(2)]);
+        assert_eq!(data.role_rewards[0].holders, [user(2)]);
+
+        // No one without points ranks
+        for status in data.users.values_mut() {
+            status.score = 0;
+        }
+        update_role_rewards(&platform, &GUILD, &mut data).await;
+        assert!(platform.role_members(GUILD, ROLE).await.unwrap().is_empty());
+        assert!(data.role_rewards[0].holders.is_empty());
+    }
+
+    #[tokio::test]
+    async fn roles_given_by_someone_else_are_left_alone() {
+        let platform = MemoryPlatform::new();
+        platform.set_role(GUILD, user(2), ROLE, true).await.unwrap();
+        let mut data = rewarded(Reward::Rank(1), vec![scored(5), scored(3)]);
+        update_role_rewards(&platform, &GUILD, &mut data).await;
+        assert_eq!(
+            platform.role_members(GUILD, ROLE).await.unwrap(),
+            [user(1), user(2)]
+        );
+        assert_eq!(data.role_rewards[0].holders, [user(1)]);
+
+        assert!(remove_role_reward(&platform, &GUILD, &mut data, ROLE).await);
+        assert_eq!(platform.role_members(GUILD, ROLE).await.unwrap(), [user(2)]);
+        assert!(data.role_rewards.is_empty());
+    }
+
+    #[tokio::test]
+    async fn streak_and_badge_roles_go_to_everyone_who_qualifies() {
+        let platform = MemoryPlatform::new();
+        let streak = |streak| Status {
+            streak,
+            ..Default::default()
+        };
+        let mut data = rewarded(Reward::Streak(7), vec![streak(7), streak(6), streak(30)]);
+        update_role_rewards(&platform, &GUILD, &mut data).await;
+        assert_eq!(data.role_rewards[0].holders, [user(1), user(3)]);
+        data.users.get_mut(&user(1)).unwrap().streak = 0;
+        update_role_rewards(&platform, &GUILD, &mut data).await;
+        assert_eq!(platform.role_members(GUILD, ROLE).await.unwrap(), [user(3)]);
+
+        let platform = MemoryPlatform::new();
+        let earned = Status {
+            badges: vec![EarnedBadge {
+                badge: Badge::Comeback,
+                date: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+            }],
+            ..Default::default()
+        };
+        let mut data = rewarded(Reward::Badge(Badge::Comeback), vec![scored(1), earned]);
+        update_role_rewards(&platform, &GUILD, &mut data).await;
+        assert_eq!(platform.role_members(GUILD, ROLE).await.unwrap(), [user(2)]);
+    }
+
+    #[test]
+    fn rewards_need_a_positive_value_or_known_badge() {
+        assert!(Reward::parse("rank", "3") == Some(Reward::Rank(3)));
+        assert!(Reward::parse("rank", "0").is_none());
+        assert!(Reward::parse("streak", "-1").is_none());
+        assert!(Reward::parse("badge", "first_hard") == Some(Reward::Badge(Badge::FirstHard)));
+        assert!(Reward::parse("badge", "first").is_none());
+        assert!(Reward::parse("score", "1").is_none());
+    }
+}
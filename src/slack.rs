@@ -0,0 +1,887 @@
+use crate::{
+    add_guild, config, get_shared_state, handle_message, handle_vote, initialise_guilds, metrics,
+    platform::{ChatPlatform, Embed, Incoming, Page, PlatformResult},
+    registry::Registry,
+    schedule_daily_question, schedule_thread, schedule_weekly_contest, set_platform,
+    write_to_database, State, CUSTOM_ID,
+};
+use futures_util::{SinkExt, StreamExt};
+use regex::{Captures, Regex};
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use serenity::{
+    async_trait,
+    model::prelude::*,
+    prelude::{RwLock, TypeMap},
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::{Seek, SeekFrom, Write},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{spawn, time::sleep};
+use tokio_tungstenite::{connect_async, tungstenite::Message as Frame};
+use tracing::{error, info, warn};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct AuthTest {
+    user_id: String,
+    team_id: String,
+}
+
+#[derive(Deserialize)]
+struct ConnectionsOpen {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct Posted {
+    channel: String,
+    ts: String,
+}
+
+#[derive(Deserialize)]
+struct UploadUrl {
+    upload_url: String,
+    file_id: String,
+}
+
+#[derive(Deserialize)]
+struct Permalink {
+    permalink: String,
+}
+
+#[derive(Deserialize, Default)]
+struct Metadata {
+    #[serde(default)]
+    next_cursor: String,
+}
+
+#[derive(Deserialize)]
+struct Conversation {
+    id: String,
+    #[serde(default)]
+    is_channel: bool,
+    #[serde(default)]
+    is_group: bool,
+    #[serde(default)]
+    is_member: bool,
+    #[serde(default)]
+    is_archived: bool,
+}
+
+#[derive(Deserialize)]
+struct ConversationInfo {
+    channel: Conversation,
+}
+
+#[derive(Deserialize)]
+struct ConversationList {
+    channels: Vec<Conversation>,
+    #[serde(default)]
+    response_metadata: Metadata,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    ts: String,
+}
+
+#[derive(Deserialize)]
+struct Replies {
+    messages: Vec<Reply>,
+}
+
+#[derive(Deserialize, Default)]
+struct Profile {
+    #[serde(default)]
+    display_name: String,
+    #[serde(default)]
+    real_name: String,
+}
+
+#[derive(Deserialize)]
+struct Member {
+    id: String,
+    name: String,
+    #[serde(default)]
+    deleted: bool,
+    #[serde(default)]
+    is_bot: bool,
+    #[serde(default)]
+    profile: Profile,
+}
+
+#[derive(Deserialize)]
+struct MemberList {
+    members: Vec<Member>,
+    #[serde(default)]
+    response_metadata: Metadata,
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    #[serde(rename = "type")]
+    kind: String,
+    envelope_id: Option<String>,
+    #[serde(default)]
+    payload: Value,
+}
+
+#[derive(Deserialize)]
+struct EventCallback {
+    team_id: String,
+    event: Event,
+}
+
+#[derive(Deserialize)]
+struct Event {
+    #[serde(rename = "type")]
+    kind: String,
+    subtype: Option<String>,
+    channel: Option<String>,
+    channel_type: Option<String>,
+    user: Option<String>,
+    #[serde(default)]
+    text: String,
+    ts: Option<String>,
+    thread_ts: Option<String>,
+    bot_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SlashCommand {
+    team_id: String,
+    channel_id: String,
+    user_id: String,
+    command: String,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct Id {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct Container {
+    message_ts: Option<String>,
+    channel_id: Option<String>,
+    thread_ts: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Action {
+    action_id: String,
+    selected_user: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BlockActions {
+    #[serde(rename = "type")]
+    kind: String,
+    user: Id,
+    team: Id,
+    container: Container,
+    #[serde(default)]
+    actions: Vec<Action>,
+}
+
+async fn call<T: DeserializeOwned>(
+    client: &Client,
+    token: &str,
+    method: &str,
+    params: &[(&str, String)],
+) -> Result<T, Box<dyn Error>> {
+    let response = client
+        .post(format!("{}/{method}", config().slack.api_url))
+        .bearer_auth(token)
+        .form(params)
+        .send()
+        .await?
+        .json::<Value>()
+        .await?;
+    if response["ok"].as_bool() != Some(true) {
+        return Err(format!("Slack {method} failed: {}", response["error"]).into());
+    }
+    Ok(serde_json::from_value(response)?)
+}
+
+// Threads are keyed by the channel and timestamp of their first message, the same as that message
+pub struct Slack {
+    client: Client,
+    app_token: String,
+    token: String,
+    registry: Registry,
+    bot_user: String,
+    user_id: UserId,
+    guild_id: GuildId,
+    thread_links: Mutex<HashMap<ChannelId, String>>,
+}
+
+impl Slack {
+    pub async fn from_config() -> Result<Self, Box<dyn Error>> {
+        let slack = &config().slack;
+        let app_token = slack
+            .app_token
+            .as_deref()
+            .ok_or("Missing Slack app token, set SLACK_APP_TOKEN or pass --slack-app-token")?;
+        let bot_token = slack
+            .bot_token
+            .as_deref()
+            .ok_or("Missing Slack bot token, set SLACK_BOT_TOKEN or pass --slack-bot-token")?;
+        Self::new(app_token, bot_token).await
+    }
+
+    pub async fn new(app_token: &str, token: &str) -> Result<Self, Box<dyn Error>> {
+        let client = Client::new();
+        let registry = Registry::open(&config().slack.ids)?;
+        let auth = call::<AuthTest>(&client, token, "auth.test", &[]).await?;
+        Ok(Self {
+            user_id: UserId::new(registry.id(&auth.user_id)),
+            guild_id: GuildId::new(registry.id(&auth.team_id)),
+            bot_user: auth.user_id,
+            client,
+            app_token: app_token.to_string(),
+            token: token.to_string(),
+            registry,
+            thread_links: Mutex::new(HashMap::new()),
+        })
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &[(&str, String)],
+    ) -> Result<T, Box<dyn Error>> {
+        call(&self.client, &self.token, method, params).await
+    }
+
+    fn channel(&self, channel_id: ChannelId) -> Result<(String, Option<String>), Box<dyn Error>> {
+        let native = self
+            .registry
+            .native(channel_id.get())
+            .ok_or("Unknown Slack channel")?;
+        Ok(match native.split_once('/') {
+            Some((channel, ts)) => (channel.to_string(), Some(ts.to_string())),
+            None => (native, None),
+        })
+    }
+
+    fn message(&self, message_id: MessageId) -> Result<(String, String), Box<dyn Error>> {
+        let native = self
+            .registry
+            .native(message_id.get())
+            .ok_or("Unknown Slack message")?;
+        let (channel, ts) = native.split_once('/').ok_or("Not a Slack message")?;
+        Ok((channel.to_string(), ts.to_string()))
+    }
+
+    fn message_id(&self, channel: &str, ts: &str) -> MessageId {
+        MessageId::new(self.registry.recent_id(&format!("{channel}/{ts}")))
+    }
+
+    fn channel_id(&self, channel: &str, thread_ts: Option<&str>) -> ChannelId {
+        ChannelId::new(match thread_ts {
+            Some(ts) => self.registry.recent_id(&format!("{channel}/{ts}")),
+            None => self.registry.id(channel),
+        })
+    }
+
+    async fn permalink(&self, channel: &str, ts: &str) -> Result<String, Box<dyn Error>> {
+        Ok(self
+            .call::<Permalink>(
+                "chat.getPermalink",
+                &[
+                    ("channel", channel.to_string()),
+                    ("message_ts", ts.to_string()),
+                ],
+            )
+            .await?
+            .permalink)
+    }
+
+    async fn thread_link(&self, channel_id: ChannelId) -> Option<String> {
+        if let Some(link) = self.thread_links.lock().ok()?.get(&channel_id) {
+            return Some(link.clone());
+        }
+        let (channel, Some(ts)) = self.channel(channel_id).ok()? else {
+            return None;
+        };
+        let link = self.permalink(&channel, &ts).await.ok()?;
+        self.thread_links
+            .lock()
+            .ok()?
+            .insert(channel_id, link.clone());
+        Some(link)
+    }
+
+    // Discord markdown to Slack mrkdwn, spoilers have no equivalent so only their contents are kept,
+    // including the inline code around the spoilered submission template
+    async fn to_mrkdwn(&self, content: &str) -> Result<String, Box<dyn Error>> {
+        let mut links = HashMap::new();
+        for caps in Regex::new(r"<#(\d+)>")?.captures_iter(content) {
+            let channel_id = ChannelId::new(caps[1].parse()?);
+            if let Some(link) = self.thread_link(channel_id).await {
+                links.insert(channel_id, link);
+            }
+        }
+        let pattern = Regex::new(
+            r"(?s)<@!?(\d+)>|<#(\d+)>|\[([^\]\n]+)\]\(([^)\s]+)\)|\*\*(.+?)\*\*|``\|\|(.+?)\|\|``|\|\|(.+?)\|\||@everyone|[&<>]",
+        )?;
+        Ok(self.convert(&pattern, &links, content))
+    }
+
+    fn convert(&self, pattern: &Regex, links: &HashMap<ChannelId, String>, text: &str) -> String {
+        pattern
+            .replace_all(text, |caps: &Captures| {
+                if let Some(user_id) = caps.get(1) {
+                    user_id
+                        .as_str()
+                        .parse()
+                        .ok()
+                        .and_then(|id| self.registry.native(id))
+                        .map_or_else(|| String::from("@unknown"), |user| format!("<@{user}>"))
+                } else if let Some(channel_id) = caps.get(2) {
+                    let Ok(id) = channel_id.as_str().parse() else {
+                        return String::from("#unknown");
+                    };
+                    if let Some(link) = links.get(&ChannelId::new(id)) {
+                        format!("<{link}|this thread>")
+                    } else if let Ok((channel, _)) = self.channel(ChannelId::new(id)) {
+                        format!("<#{channel}>")
+                    } else {
+                        String::from("#unknown")
+                    }
+                } else if let (Some(name), Some(url)) = (caps.get(3), caps.get(4)) {
+                    format!("<{}|{}>", url.as_str(), name.as_str())
+                } else if let Some(bold) = caps.get(5) {
+                    format!("*{}*", self.convert(pattern, links, bold.as_str()))
+                } else if let Some(spoiler) = caps.get(6).or_else(|| caps.get(7)) {
+                    self.convert(pattern, links, spoiler.as_str())
+                } else {
+                    match &caps[0] {
+                        "@everyone" => "<!channel>",
+                        "&" => "&amp;",
+                        "<" => "&lt;",
+                        _ => "&gt;",
+                    }
+                    .to_string()
+                }
+            })
+            .into_owned()
+    }
+
+    // Slack mrkdwn to the mentions the core understands, channel mentions become bare IDs for /channel
+    fn parse_mrkdwn(&self, text: &str) -> Result<String, Box<dyn Error>> {
+        let pattern = Regex::new(
+            r"<@([UW]\w+)(?:\|[^>]*)?>|<#([CG]\w+)(?:\|[^>]*)?>|<(https?://[^|>]+)(?:\|[^>]*)?>|&amp;|&lt;|&gt;",
+        )?;
+        Ok(pattern
+            .replace_all(text, |caps: &Captures| {
+                if let Some(user) = caps.get(1) {
+                    format!("<@{}>", self.registry.id(user.as_str()))
+                } else if let Some(channel) = caps.get(2) {
+                    self.registry.id(channel.as_str()).to_string()
+                } else if let Some(url) = caps.get(3) {
+                    url.as_str().to_string()
+                } else {
+                    match &caps[0] {
+                        "&amp;" => "&",
+                        "&lt;" => "<",
+                        _ => ">",
+                    }
+                    .to_string()
+                }
+            })
+            .into_owned())
+    }
+
+    async fn post(
+        &self,
+        channel_id: ChannelId,
+        content: &str,
+        extra: &[(&str, String)],
+    ) -> Result<(String, String), Box<dyn Error>> {
+        let (channel, thread_ts) = self.channel(channel_id)?;
+        let mut params = vec![
+            ("channel", channel),
+            ("text", self.to_mrkdwn(content).await?),
+        ];
+        if let Some(ts) = thread_ts {
+            params.push(("thread_ts", ts));
+        }
+        params.extend_from_slice(extra);
+        let posted = self.call::<Posted>("chat.postMessage", &params).await?;
+        Ok((posted.channel, posted.ts))
+    }
+
+    async fn poll_blocks(&self, content: &str) -> Result<String, Box<dyn Error>> {
+        Ok(json!([
+            {
+                "type": "section",
+                "text": {"type": "mrkdwn", "text": self.to_mrkdwn(content).await?},
+            },
+            {
+                "type": "actions",
+                "elements": [{
+                    "type": "users_select",
+                    "action_id": CUSTOM_ID,
+                    "placeholder": {"type": "plain_text", "text": "No submission selected"},
+                }],
+            },
+        ])
+        .to_string())
+    }
+
+    async fn ephemeral(
+        &self,
+        channel: String,
+        thread_ts: Option<String>,
+        user: String,
+        content: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut params = vec![
+            ("channel", channel),
+            ("user", user),
+            ("text", self.to_mrkdwn(content).await?),
+        ];
+        if let Some(ts) = thread_ts {
+            params.push(("thread_ts", ts));
+        }
+        self.call::<Value>("chat.postEphemeral", &params).await?;
+        Ok(())
+    }
+
+    async fn default_channel(&self) -> Result<Option<ChannelId>, Box<dyn Error>> {
+        let mut cursor = String::new();
+        loop {
+            let list = self
+                .call::<ConversationList>(
+                    "conversations.list",
+                    &[
+                        ("types", String::from("public_channel,private_channel")),
+                        ("exclude_archived", String::from("true")),
+                        ("limit", String::from("200")),
+                        ("cursor", cursor),
+                    ],
+                )
+                .await?;
+            if let Some(channel) = list.channels.iter().find(|channel| channel.is_member) {
+                return Ok(Some(self.channel_id(&channel.id, None)));
+            }
+            cursor = list.response_metadata.next_cursor;
+            if cursor.is_empty() {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ChatPlatform for Slack {
+    fn current_user(&self) -> UserId {
+        self.user_id
+    }
+
+    fn native_user_id(&self, user_id: UserId) -> String {
+        self.registry
+            .native(user_id.get())
+            .unwrap_or_else(|| user_id.to_string())
+    }
+
+    async fn say(&self, channel_id: ChannelId, content: String) -> PlatformResult<MessageId> {
+        let (channel, ts) = self.post(channel_id, &content, &[]).await?;
+        Ok(self.message_id(&channel, &ts))
+    }
+
+    async fn send_embed(
+        &self,
+        channel_id: ChannelId,
+        content: String,
+        embed: Embed,
+    ) -> PlatformResult<MessageId> {
+        let attachments = json!([{
+            "color": format!("#{}", embed.colour.hex()),
+            "title": embed.title,
+            "title_link": embed.url,
+            "fields": embed
+                .fields
+                .iter()
+                .map(|(title, value)| json!({"title": title, "value": value, "short": true}))
+                .collect::<Vec<_>>(),
+        }]);
+        let (channel, ts) = self
+            .post(
+                channel_id,
+                &content,
+                &[("attachments", attachments.to_string())],
+            )
+            .await?;
+        Ok(self.message_id(&channel, &ts))
+    }
+
+    // Files are uploaded to a URL from Slack first and shared into the channel afterwards
+    async fn send_file(
+        &self,
+        channel_id: ChannelId,
+        content: String,
+        file_name: String,
+        data: Vec<u8>,
+    ) -> PlatformResult<()> {
+        let (channel, thread_ts) = self.channel(channel_id)?;
+        let upload = self
+            .call::<UploadUrl>(
+                "files.getUploadURLExternal",
+                &[
+                    ("filename", file_name.clone()),
+                    ("length", data.len().to_string()),
+                ],
+            )
+            .await?;
+        self.client
+            .post(&upload.upload_url)
+            .bearer_auth(&self.token)
+            .body(data)
+            .send()
+            .await?
+            .error_for_status()?;
+        let mut params = vec![
+            (
+                "files",
+                json!([{"id": upload.file_id, "title": file_name}]).to_string(),
+            ),
+            ("channel_id", channel),
+            ("initial_comment", self.to_mrkdwn(&content).await?),
+        ];
+        if let Some(ts) = thread_ts {
+            params.push(("thread_ts", ts));
+        }
+        self.call::<Value>("files.completeUploadExternal", &params)
+            .await?;
+        Ok(())
+    }
+
+    // There are no buttons to turn pages with, /scores takes the page instead
+    async fn send_page(&self, channel_id: ChannelId, page: Page) -> PlatformResult<MessageId> {
+        self.say(channel_id, page.text()).await
+    }
+
+    async fn send_poll(&self, channel_id: ChannelId, content: String) -> PlatformResult<MessageId> {
+        let blocks = self.poll_blocks(&content).await?;
+        let (channel, ts) = self
+            .post(channel_id, &content, &[("blocks", blocks)])
+            .await?;
+        self.registry.recent_id(&format!("poll/{channel}/{ts}"));
+        self.call::<Value>(
+            "pins.add",
+            &[("channel", channel.clone()), ("timestamp", ts.clone())],
+        )
+        .await?;
+        Ok(self.message_id(&channel, &ts))
+    }
+
+    async fn edit(
+        &self,
+        _channel_id: ChannelId,
+        message_id: MessageId,
+        content: String,
+    ) -> PlatformResult<()> {
+        let (channel, ts) = self.message(message_id)?;
+        let mut params = vec![
+            ("text", self.to_mrkdwn(&content).await?),
+            ("channel", channel.clone()),
+            ("ts", ts.clone()),
+        ];
+        // Updating only the text of a message drops its blocks, which would remove the poll
+        if self.registry.contains(&format!("poll/{channel}/{ts}")) {
+            params.push(("blocks", self.poll_blocks(&content).await?));
+        }
+        self.call::<Value>("chat.update", &params).await?;
+        Ok(())
+    }
+
+    async fn reply(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: String,
+    ) -> PlatformResult<()> {
+        let (channel, ts) = self.message(message_id)?;
+        let link = self.permalink(&channel, &ts).await?;
+        self.post(channel_id, &format!("{content}\n{link}"), &[])
+            .await?;
+        Ok(())
+    }
+
+    async fn message_exists(&self, _channel_id: ChannelId, message_id: MessageId) -> bool {
+        let Ok((channel, ts)) = self.message(message_id) else {
+            return false;
+        };
+        self.call::<Replies>(
+            "conversations.replies",
+            &[
+                ("channel", channel),
+                ("ts", ts.clone()),
+                ("inclusive", String::from("true")),
+                ("limit", String::from("1")),
+            ],
+        )
+        .await
+        .is_ok_and(|replies| replies.messages.iter().any(|reply| reply.ts == ts))
+    }
+
+    async fn create_thread(
+        &self,
+        _channel_id: ChannelId,
+        message_id: MessageId,
+        _name: String,
+    ) -> PlatformResult<ChannelId> {
+        let (channel, ts) = self.message(message_id)?;
+        Ok(self.channel_id(&channel, Some(&ts)))
+    }
+
+    async fn is_text_channel(&self, channel_id: ChannelId) -> bool {
+        let Ok((channel, None)) = self.channel(channel_id) else {
+            return false;
+        };
+        self.call::<ConversationInfo>("conversations.info", &[("channel", channel)])
+            .await
+            .is_ok_and(|info| {
+                (info.channel.is_channel || info.channel.is_group) && !info.channel.is_archived
+            })
+    }
+
+    async fn members(&self, _guild_id: GuildId) -> PlatformResult<Vec<User>> {
+        let mut users = Vec::new();
+        let mut cursor = String::new();
+        loop {
+            let list = self
+                .call::<MemberList>(
+                    "users.list",
+                    &[("limit", String::from("200")), ("cursor", cursor)],
+                )
+                .await?;
+            for member in list.members {
+                if member.deleted || member.is_bot || member.id == "USLACKBOT" {
+                    continue;
+                }
+                let mut user = User::default();
+                user.id = UserId::new(self.registry.id(&member.id));
+                user.name = [member.profile.display_name, member.profile.real_name]
+                    .into_iter()
+                    .find(|name| !name.is_empty())
+                    .unwrap_or(member.name);
+                users.push(user);
+            }
+            cursor = list.response_metadata.next_cursor;
+            if cursor.is_empty() {
+                return Ok(users);
+            }
+        }
+    }
+
+    async fn role_members(
+        &self,
+        _guild_id: GuildId,
+        _role_id: RoleId,
+    ) -> PlatformResult<Vec<UserId>> {
+        Err("Roles are not supported on Slack".into())
+    }
+
+    async fn set_role(
+        &self,
+        _guild_id: GuildId,
+        _user_id: UserId,
+        _role_id: RoleId,
+        _assigned: bool,
+    ) -> PlatformResult<()> {
+        Err("Role rewards are not supported on Slack".into())
+    }
+}
+
+async fn ready(platform: &Arc<Slack>, data: &Arc<RwLock<TypeMap>>) -> Result<(), Box<dyn Error>> {
+    {
+        let mut data = data.write().await;
+        let state = get_shared_state!(data);
+        if state.ready {
+            return Ok(());
+        }
+        state.ready = true;
+        let guild_id = platform.guild_id;
+        let result = if state.database.contains_key(&guild_id) {
+            initialise_guilds(platform.as_ref(), &guild_id, state).await
+        } else {
+            let channel_id = platform.default_channel().await?;
+            add_guild(platform.as_ref(), state, guild_id, channel_id).await
+        };
+        if let Err(why) = result {
+            error!("Error setting up Slack workspace: {why}");
+        }
+        write_to_database!(state);
+    }
+    let platform: Arc<dyn ChatPlatform> = platform.clone();
+    set_platform(platform.clone());
+    schedule_thread!(platform, data, schedule_daily_question);
+    schedule_thread!(platform, data, schedule_weekly_contest);
+    Ok(())
+}
+
+async fn dispatch(
+    platform: Arc<Slack>,
+    data: Arc<RwLock<TypeMap>>,
+    envelope: Envelope,
+) -> Result<(), Box<dyn Error>> {
+    let mut data = data.write().await;
+    let state = get_shared_state!(data);
+    match envelope.kind.as_str() {
+        "events_api" => {
+            let callback = serde_json::from_value::<EventCallback>(envelope.payload)?;
+            let event = callback.event;
+            let guild_id = GuildId::new(platform.registry.id(&callback.team_id));
+            let (Some(channel), Some(user)) = (event.channel, event.user) else {
+                return Ok(());
+            };
+            match event.kind.as_str() {
+                "message" if event.subtype.is_none() && event.bot_id.is_none() => {
+                    let content = platform.parse_mrkdwn(&event.text)?;
+                    let link = match &event.ts {
+                        Some(ts) if content.contains("```") => {
+                            platform.permalink(&channel, ts).await.unwrap_or_default()
+                        }
+                        _ => String::new(),
+                    };
+                    let incoming = Incoming {
+                        guild_id: Some(guild_id)
+                            .filter(|_| event.channel_type.as_deref() != Some("im")),
+                        channel_id: platform.channel_id(&channel, event.thread_ts.as_deref()),
+                        author: UserId::new(platform.registry.id(&user)),
+                        content,
+                        link,
+                    };
+                    handle_message(platform.as_ref(), state, incoming).await?;
+                }
+                "member_joined_channel" if user == platform.bot_user => {
+                    let channel_id = platform.channel_id(&channel, None);
+                    add_guild(platform.as_ref(), state, guild_id, Some(channel_id)).await?;
+                }
+                _ => return Ok(()),
+            }
+        }
+        "slash_commands" => {
+            let command = serde_json::from_value::<SlashCommand>(envelope.payload)?;
+            let incoming = Incoming {
+                guild_id: Some(GuildId::new(platform.registry.id(&command.team_id))),
+                channel_id: platform.channel_id(&command.channel_id, None),
+                author: UserId::new(platform.registry.id(&command.user_id)),
+                content: platform
+                    .parse_mrkdwn(&format!("{} {}", command.command, command.text))?
+                    .trim_end()
+                    .to_string(),
+                link: String::new(),
+            };
+            handle_message(platform.as_ref(), state, incoming).await?;
+        }
+        "interactive" => {
+            let actions = serde_json::from_value::<BlockActions>(envelope.payload)?;
+            let (Some(channel), Some(ts)) =
+                (actions.container.channel_id, actions.container.message_ts)
+            else {
+                return Ok(());
+            };
+            if actions.kind != "block_actions" {
+                return Ok(());
+            }
+            for action in actions.actions {
+                let Some(voted_for) = action
+                    .selected_user
+                    .filter(|_| action.action_id == CUSTOM_ID)
+                else {
+                    continue;
+                };
+                let response = handle_vote(
+                    state,
+                    GuildId::new(platform.registry.id(&actions.team.id)),
+                    UserId::new(platform.registry.id(&actions.user.id)),
+                    platform.message_id(&channel, &ts),
+                    UserId::new(platform.registry.id(&voted_for)),
+                )?;
+                if let Some(response) = response {
+                    platform
+                        .ephemeral(
+                            channel.clone(),
+                            actions.container.thread_ts.clone(),
+                            actions.user.id.clone(),
+                            &response,
+                        )
+                        .await?;
+                }
+            }
+        }
+        _ => return Ok(()),
+    }
+    write_to_database!(state);
+    Ok(())
+}
+
+async fn connect(platform: &Arc<Slack>, data: &Arc<RwLock<TypeMap>>) -> Result<(), Box<dyn Error>> {
+    let open = call::<ConnectionsOpen>(
+        &platform.client,
+        &platform.app_token,
+        "apps.connections.open",
+        &[],
+    )
+    .await?;
+    let (mut socket, _) = connect_async(open.url.as_str()).await?;
+    while let Some(frame) = socket.next().await {
+        let Frame::Text(text) = frame? else {
+            continue;
+        };
+        let envelope = serde_json::from_str::<Envelope>(&text)?;
+        if let Some(envelope_id) = &envelope.envelope_id {
+            socket
+                .send(Frame::Text(
+                    json!({ "envelope_id": envelope_id }).to_string(),
+                ))
+                .await?;
+        }
+        match envelope.kind.as_str() {
+            "hello" => {
+                info!("Connected to Slack");
+                metrics().set_gateway_connected(true);
+                ready(platform, data).await?;
+            }
+            "disconnect" => break,
+            _ => {
+                let platform = platform.clone();
+                let data = data.clone();
+                spawn(async move {
+                    if let Err(why) = dispatch(platform, data, envelope).await {
+                        error!("Error responding to Slack event: {why}");
+                    }
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn run_slack(
+    platform: Arc<Slack>,
+    data: Arc<RwLock<TypeMap>>,
+) -> Result<(), Box<dyn Error>> {
+    loop {
+        if let Err(why) = connect(&platform, &data).await {
+            error!("Error in Slack connection: {why}");
+        }
+        metrics().set_gateway_connected(false);
+        warn!("Disconnected from Slack, reconnecting");
+        sleep(RECONNECT_DELAY).await;
+    }
+}
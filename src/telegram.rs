@@ -0,0 +1,996 @@
+use crate::{
+    add_guild, config, get_shared_state, handle_message, handle_vote, initialise_guilds, metrics,
+    platform::{
+        content_type, split_message, truncate_message, ChatPlatform, Embed, Incoming, Page,
+        PlatformResult,
+    },
+    registry::Registry,
+    schedule_daily_question, schedule_thread, schedule_weekly_contest, set_platform,
+    write_to_database, State, CUSTOM_ID,
+};
+use regex::{Captures, Regex};
+use reqwest::{
+    multipart::{Form, Part},
+    Client,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use serenity::{
+    async_trait,
+    model::prelude::*,
+    prelude::{RwLock, TypeMap},
+};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, VecDeque},
+    error::Error,
+    io::{Seek, SeekFrom, Write},
+    iter::repeat_n,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{spawn, time::sleep};
+use tracing::{error, info, warn};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const POLL_TIMEOUT: u64 = 30;
+const ALLOWED_UPDATES: [&str; 3] = ["message", "callback_query", "my_chat_member"];
+const MESSAGE_LIMIT: usize = 4096;
+const CAPTION_LIMIT: usize = 1024;
+const REPLY_CHAINS: usize = 1000;
+
+#[derive(Deserialize, Clone)]
+struct Account {
+    id: i64,
+    #[serde(default)]
+    is_bot: bool,
+    first_name: String,
+    last_name: Option<String>,
+    username: Option<String>,
+}
+
+impl Account {
+    fn name(&self) -> String {
+        match &self.last_name {
+            Some(last_name) => format!("{} {last_name}", self.first_name),
+            None => self.first_name.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Chat {
+    id: i64,
+    #[serde(rename = "type")]
+    kind: String,
+    username: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Entity {
+    #[serde(rename = "type")]
+    kind: String,
+    offset: usize,
+    length: usize,
+    language: Option<String>,
+    user: Option<Account>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    message_id: i64,
+    chat: Chat,
+    from: Option<Account>,
+    text: Option<String>,
+    caption: Option<String>,
+    #[serde(default)]
+    entities: Vec<Entity>,
+    #[serde(default)]
+    caption_entities: Vec<Entity>,
+    reply_to_message: Option<Box<Message>>,
+}
+
+#[derive(Deserialize)]
+struct Sent {
+    message_id: i64,
+    chat: Chat,
+}
+
+#[derive(Deserialize)]
+struct CallbackQuery {
+    id: String,
+    from: Account,
+    message: Option<Sent>,
+    data: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatMember {
+    status: String,
+    user: Account,
+}
+
+#[derive(Deserialize)]
+struct ChatMemberUpdated {
+    chat: Chat,
+    old_chat_member: ChatMember,
+    new_chat_member: ChatMember,
+}
+
+#[derive(Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+    callback_query: Option<CallbackQuery>,
+    my_chat_member: Option<ChatMemberUpdated>,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Links to messages only exist in supergroups and public groups
+fn message_link(chat: &Chat, message_id: i64) -> String {
+    match (&chat.username, chat.id.to_string().strip_prefix("-100")) {
+        (Some(username), _) => format!("https://t.me/{username}/{message_id}"),
+        (None, Some(internal)) => format!("https://t.me/c/{internal}/{message_id}"),
+        (None, None) => String::new(),
+    }
+}
+
+// Telegram formatting entities back to the Discord markdown the core expects, so code sent as a
+// code block, optionally hidden behind a spoiler, counts as a submission
+fn markdown(text: &str, entities: &[Entity]) -> String {
+    let mut offsets = Vec::new();
+    for (index, character) in text.char_indices() {
+        offsets.extend(repeat_n(index, character.len_utf16()));
+    }
+    offsets.push(text.len());
+    let mut spans = entities
+        .iter()
+        .filter_map(|entity| {
+            let priority = match entity.kind.as_str() {
+                "spoiler" => 0,
+                "bold" => 1,
+                "pre" | "code" => 2,
+                "text_mention" => 3,
+                _ => return None,
+            };
+            let start = *offsets.get(entity.offset)?;
+            let end = *offsets.get(entity.offset + entity.length)?;
+            Some((start, end, priority, entity))
+        })
+        .collect::<Vec<_>>();
+    spans.sort_by_key(|&(start, end, priority, _)| (start, Reverse(end), priority));
+    let mut result = String::new();
+    let mut closing: Vec<(usize, &str)> = Vec::new();
+    let mut position = 0;
+    for (start, end, _, entity) in spans {
+        if start < position {
+            continue;
+        }
+        while let Some(&(close_at, close)) = closing.last() {
+            if close_at > start {
+                break;
+            }
+            result.push_str(&text[position..close_at]);
+            result.push_str(close);
+            position = close_at;
+            closing.pop();
+        }
+        result.push_str(&text[position..start]);
+        position = start;
+        let (open, close) = match entity.kind.as_str() {
+            "spoiler" => (String::from("||"), "||"),
+            "bold" => (String::from("**"), "**"),
+            "pre" => (
+                format!("```{}\n", entity.language.as_deref().unwrap_or_default()),
+                "\n```",
+            ),
+            "code" => (String::from("`"), "`"),
+            _ => {
+                if let Some(user) = &entity.user {
+                    result.push_str(&format!("<@{}>", user.id));
+                }
+                position = end;
+                continue;
+            }
+        };
+        result.push_str(&open);
+        closing.push((end, close));
+    }
+    while let Some((close_at, close)) = closing.pop() {
+        result.push_str(&text[position..close_at]);
+        result.push_str(close);
+        position = close_at;
+    }
+    result.push_str(&text[position..]);
+    result
+}
+
+// Replies only say which message they answer, so the thread of the latest messages sent or
+// received in one is kept for the replies to them
+#[derive(Default)]
+struct Threads {
+    order: VecDeque<(i64, i64)>,
+    roots: HashMap<(i64, i64), i64>,
+}
+
+// Chats are both guilds and channels, threads are reply chains keyed by the chat and the ID of the
+// bot message at their root, the same as that message
+pub struct Telegram {
+    client: Client,
+    token: String,
+    registry: Registry,
+    bot_id: i64,
+    username: String,
+    user_id: UserId,
+    names: Mutex<HashMap<UserId, String>>,
+    threads: Mutex<Threads>,
+}
+
+impl Telegram {
+    pub async fn from_config() -> Result<Self, Box<dyn Error>> {
+        let token =
+            config().telegram.token.as_deref().ok_or(
+                "Missing Telegram bot token, set TELEGRAM_BOT_TOKEN or pass --telegram-token",
+            )?;
+        Self::new(token).await
+    }
+
+    pub async fn new(token: &str) -> Result<Self, Box<dyn Error>> {
+        let client = Client::new();
+        let registry = Registry::open(&config().telegram.ids)?;
+        let me = call::<Account>(&client, token, "getMe", json!({})).await?;
+        let user_id = UserId::new(registry.id(&format!("user/{}", me.id)));
+        Ok(Self {
+            client,
+            token: token.to_string(),
+            registry,
+            bot_id: me.id,
+            username: me.username.clone().unwrap_or_default(),
+            user_id,
+            names: Mutex::new(HashMap::from([(user_id, me.name())])),
+            threads: Mutex::new(Threads::default()),
+        })
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, Box<dyn Error>> {
+        call(&self.client, &self.token, method, params).await
+    }
+
+    fn user(&self, account: &Account) -> User {
+        let mut user = User::default();
+        user.id = UserId::new(self.registry.id(&format!("user/{}", account.id)));
+        user.name = account.name();
+        if let Ok(mut names) = self.names.lock() {
+            names.insert(user.id, user.name.clone());
+        }
+        user
+    }
+
+    fn name(&self, user_id: UserId) -> String {
+        self.names
+            .lock()
+            .ok()
+            .and_then(|names| names.get(&user_id).cloned())
+            .unwrap_or_else(|| String::from("someone"))
+    }
+
+    // Anything sent in a thread leads back to its root, other messages from the bot like the daily
+    // question start one
+    fn thread(&self, chat: i64, reply: &Message) -> Option<i64> {
+        self.threads
+            .lock()
+            .ok()
+            .and_then(|threads| threads.roots.get(&(chat, reply.message_id)).copied())
+            .or_else(|| {
+                reply
+                    .from
+                    .as_ref()
+                    .is_some_and(|from| from.id == self.bot_id)
+                    .then_some(reply.message_id)
+            })
+    }
+
+    fn remember(&self, chat: i64, message: i64, thread: Option<i64>) {
+        let (Some(thread), Ok(mut threads)) = (thread, self.threads.lock()) else {
+            return;
+        };
+        threads.order.push_back((chat, message));
+        threads.roots.insert((chat, message), thread);
+        while threads.order.len() > REPLY_CHAINS {
+            if let Some(oldest) = threads.order.pop_front() {
+                threads.roots.remove(&oldest);
+            }
+        }
+    }
+
+    fn chat(&self, channel_id: ChannelId) -> Result<(i64, Option<i64>), Box<dyn Error>> {
+        let native = self
+            .registry
+            .native(channel_id.get())
+            .ok_or("Unknown Telegram chat")?;
+        Ok(match native.split_once('/') {
+            Some((chat, message)) => (chat.parse()?, Some(message.parse()?)),
+            None => (native.parse()?, None),
+        })
+    }
+
+    fn message(&self, message_id: MessageId) -> Result<(i64, i64), Box<dyn Error>> {
+        let native = self
+            .registry
+            .native(message_id.get())
+            .ok_or("Unknown Telegram message")?;
+        let (chat, message) = native.split_once('/').ok_or("Not a Telegram message")?;
+        Ok((chat.parse()?, message.parse()?))
+    }
+
+    fn message_id(&self, chat: i64, message: i64) -> MessageId {
+        MessageId::new(self.registry.recent_id(&format!("{chat}/{message}")))
+    }
+
+    fn channel_id(&self, chat: i64, thread: Option<i64>) -> ChannelId {
+        ChannelId::new(match thread {
+            Some(message) => self.registry.recent_id(&format!("{chat}/{message}")),
+            None => self.registry.id(&chat.to_string()),
+        })
+    }
+
+    fn guild_id(&self, chat: i64) -> GuildId {
+        GuildId::new(self.registry.id(&chat.to_string()))
+    }
+
+    // Discord markdown to Telegram HTML, the spoilered submission template is kept as is since
+    // Telegram clients understand the same markdown
+    fn to_html(&self, content: &str) -> Result<String, Box<dyn Error>> {
+        let pattern = Regex::new(
+            r"(?s)<@!?(\d+)>|<#(\d+)>|\[([^\]\n]+)\]\(([^)\s]+)\)|\*\*(.+?)\*\*|``(\|\|.+?\|\|)``|\|\|(.+?)\|\||```(?:([\w+\-]+)?\n)?(.+?)```|`([^`\n]+)`|@everyone|[&<>]",
+        )?;
+        Ok(self.convert(&pattern, content))
+    }
+
+    fn convert(&self, pattern: &Regex, text: &str) -> String {
+        pattern
+            .replace_all(text, |caps: &Captures| {
+                if let Some(user_id) = caps.get(1) {
+                    let Ok(id) = user_id.as_str().parse() else {
+                        return String::from("someone");
+                    };
+                    let user_id = UserId::new(id);
+                    let name = escape(&self.name(user_id));
+                    match self
+                        .registry
+                        .native(id)
+                        .as_deref()
+                        .and_then(|native| native.strip_prefix("user/"))
+                    {
+                        Some(user) => format!(r#"<a href="tg://user?id={user}">{name}</a>"#),
+                        None => name,
+                    }
+                } else if let Some(channel_id) = caps.get(2) {
+                    match channel_id.as_str().parse().map(ChannelId::new) {
+                        Ok(channel_id) => match self.chat(channel_id) {
+                            Ok((_, Some(_))) => String::from("the daily question message"),
+                            _ => String::from("this chat"),
+                        },
+                        Err(_) => String::from("this chat"),
+                    }
+                } else if let (Some(name), Some(url)) = (caps.get(3), caps.get(4)) {
+                    format!(
+                        r#"<a href="{}">{}</a>"#,
+                        escape(url.as_str()),
+                        escape(name.as_str())
+                    )
+                } else if let Some(bold) = caps.get(5) {
+                    format!("<b>{}</b>", self.convert(pattern, bold.as_str()))
+                } else if let Some(template) = caps.get(6) {
+                    format!("<pre>{}</pre>", escape(template.as_str()))
+                } else if let Some(spoiler) = caps.get(7) {
+                    format!(
+                        "<tg-spoiler>{}</tg-spoiler>",
+                        self.convert(pattern, spoiler.as_str())
+                    )
+                } else if let Some(code) = caps.get(9) {
+                    match caps.get(8) {
+                        Some(language) => format!(
+                            r#"<pre><code class="language-{}">{}</code></pre>"#,
+                            language.as_str(),
+                            escape(code.as_str())
+                        ),
+                        None => format!("<pre>{}</pre>", escape(code.as_str())),
+                    }
+                } else if let Some(code) = caps.get(10) {
+                    format!("<code>{}</code>", escape(code.as_str()))
+                } else {
+                    match &caps[0] {
+                        "@everyone" => "everyone",
+                        "&" => "&amp;",
+                        "<" => "&lt;",
+                        _ => "&gt;",
+                    }
+                    .to_string()
+                }
+            })
+            .into_owned()
+    }
+
+    // Callback answers are plain text, so mentions become names
+    fn plain(&self, content: &str) -> Result<String, Box<dyn Error>> {
+        Ok(Regex::new(r"<@!?(\d+)>")?
+            .replace_all(content, |caps: &Captures| {
+                caps[1]
+                    .parse()
+                    .map_or_else(|_| String::from("someone"), |id| self.name(UserId::new(id)))
+            })
+            .into_owned())
+    }
+
+    // A button for each submission in the poll, votes come back as callback queries
+    fn poll_keyboard(&self, content: &str) -> Result<Value, Box<dyn Error>> {
+        let mention = Regex::new(r"(?m)^<@!?(\d+)>")?;
+        let buttons = mention
+            .captures_iter(content)
+            .filter_map(|caps| caps[1].parse::<u64>().ok())
+            .map(|id| {
+                json!([{
+                    "text": self.name(UserId::new(id)),
+                    "callback_data": format!("{CUSTOM_ID}:{id}"),
+                }])
+            })
+            .collect::<Vec<_>>();
+        Ok(json!({ "inline_keyboard": buttons }))
+    }
+
+    async fn send(
+        &self,
+        channel_id: ChannelId,
+        content: &str,
+        mut params: Value,
+    ) -> Result<(i64, i64), Box<dyn Error>> {
+        let (chat, thread) = self.chat(channel_id)?;
+        params["chat_id"] = json!(chat);
+        params["parse_mode"] = json!("HTML");
+        if params.get("reply_parameters").is_none() {
+            if let Some(message) = thread {
+                params["reply_parameters"] =
+                    json!({"message_id": message, "allow_sending_without_reply": true});
+            }
+        }
+        // Only the last part of a long message gets the keyboard or link preview
+        let mut messages = split_message(content, MESSAGE_LIMIT);
+        let last = messages.pop().unwrap_or_default();
+        for message in messages {
+            let mut leading = json!({"chat_id": chat, "parse_mode": "HTML"});
+            leading["text"] = json!(self.to_html(&message)?);
+            if let Some(reply) = params.get("reply_parameters") {
+                leading["reply_parameters"] = reply.clone();
+            }
+            let sent = self.call::<Sent>("sendMessage", leading).await?;
+            self.remember(chat, sent.message_id, thread);
+        }
+        params["text"] = json!(self.to_html(&last)?);
+        let sent = self.call::<Sent>("sendMessage", params).await?;
+        self.remember(chat, sent.message_id, thread);
+        Ok((sent.chat.id, sent.message_id))
+    }
+}
+
+async fn call<T: DeserializeOwned>(
+    client: &Client,
+    token: &str,
+    method: &str,
+    params: Value,
+) -> Result<T, Box<dyn Error>> {
+    let response = client
+        .post(format!("{}/bot{token}/{method}", config().telegram.api_url))
+        .json(&params)
+        .send()
+        .await?
+        .json::<Value>()
+        .await?;
+    unwrap_result(method, response)
+}
+
+fn unwrap_result<T: DeserializeOwned>(method: &str, response: Value) -> Result<T, Box<dyn Error>> {
+    if response["ok"].as_bool() != Some(true) {
+        return Err(format!("Telegram {method} failed: {}", response["description"]).into());
+    }
+    Ok(serde_json::from_value(response["result"].clone())?)
+}
+
+#[async_trait]
+impl ChatPlatform for Telegram {
+    fn current_user(&self) -> UserId {
+        self.user_id
+    }
+
+    fn native_user_id(&self, user_id: UserId) -> String {
+        self.registry
+            .native(user_id.get())
+            .and_then(|native| native.strip_prefix("user/").map(str::to_string))
+            .unwrap_or_else(|| user_id.to_string())
+    }
+
+    async fn say(&self, channel_id: ChannelId, content: String) -> PlatformResult<MessageId> {
+        let (chat, message) = self.send(channel_id, &content, json!({})).await?;
+        Ok(self.message_id(chat, message))
+    }
+
+    async fn send_embed(
+        &self,
+        channel_id: ChannelId,
+        content: String,
+        embed: Embed,
+    ) -> PlatformResult<MessageId> {
+        let mut text = format!("{content}\n\n**[{}]({})**", embed.title, embed.url);
+        for (name, value) in embed.fields {
+            text.push_str(&format!("\n{name}: {value}"));
+        }
+        let (chat, message) = self
+            .send(
+                channel_id,
+                &text,
+                json!({"link_preview_options": {"url": embed.url}}),
+            )
+            .await?;
+        Ok(self.message_id(chat, message))
+    }
+
+    async fn send_file(
+        &self,
+        channel_id: ChannelId,
+        content: String,
+        file_name: String,
+        data: Vec<u8>,
+    ) -> PlatformResult<()> {
+        let mut captions = split_message(&content, CAPTION_LIMIT);
+        let caption = captions.pop().unwrap_or_default();
+        for message in captions {
+            self.send(channel_id, &message, json!({})).await?;
+        }
+        let (chat, thread) = self.chat(channel_id)?;
+        let document = Part::bytes(data)
+            .file_name(file_name.clone())
+            .mime_str(content_type(&file_name))?;
+        let mut form = Form::new()
+            .text("chat_id", chat.to_string())
+            .text("caption", self.to_html(&caption)?)
+            .text("parse_mode", "HTML")
+            .part("document", document);
+        if let Some(message) = thread {
+            form = form.text(
+                "reply_parameters",
+                json!({"message_id": message, "allow_sending_without_reply": true}).to_string(),
+            );
+        }
+        let response = self
+            .client
+            .post(format!(
+                "{}/bot{}/sendDocument",
+                config().telegram.api_url,
+                self.token
+            ))
+            .multipart(form)
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+        let sent = unwrap_result::<Sent>("sendDocument", response)?;
+        self.remember(chat, sent.message_id, thread);
+        Ok(())
+    }
+
+    // There are no buttons to turn pages with, /scores takes the page instead
+    async fn send_page(&self, channel_id: ChannelId, page: Page) -> PlatformResult<MessageId> {
+        self.say(channel_id, page.text()).await
+    }
+
+    async fn send_poll(&self, channel_id: ChannelId, content: String) -> PlatformResult<MessageId> {
+        let keyboard = self.poll_keyboard(&content)?;
+        let (chat, message) = self
+            .send(channel_id, &content, json!({ "reply_markup": keyboard }))
+            .await?;
+        self.registry.recent_id(&format!("poll/{chat}/{message}"));
+        // Bots can only pin messages in groups where they are an admin, the poll works without it
+        if let Err(why) = self
+            .call::<Value>(
+                "pinChatMessage",
+                json!({"chat_id": chat, "message_id": message, "disable_notification": true}),
+            )
+            .await
+        {
+            warn!("Could not pin the poll in {chat}: {why}");
+        }
+        Ok(self.message_id(chat, message))
+    }
+
+    async fn edit(
+        &self,
+        _channel_id: ChannelId,
+        message_id: MessageId,
+        content: String,
+    ) -> PlatformResult<()> {
+        let (chat, message) = self.message(message_id)?;
+        let mut params = json!({
+            "chat_id": chat,
+            "message_id": message,
+            "text": self.to_html(&truncate_message(&content, MESSAGE_LIMIT))?,
+            "parse_mode": "HTML",
+        });
+        // Editing the text without the keyboard would remove the poll
+        if self.registry.contains(&format!("poll/{chat}/{message}")) {
+            params["reply_markup"] = self.poll_keyboard(&content)?;
+        }
+        self.call::<Value>("editMessageText", params).await?;
+        Ok(())
+    }
+
+    async fn reply(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: String,
+    ) -> PlatformResult<()> {
+        let (_, message) = self.message(message_id)?;
+        self.send(
+            channel_id,
+            &content,
+            json!({"reply_parameters": {"message_id": message, "allow_sending_without_reply": true}}),
+        )
+        .await?;
+        Ok(())
+    }
+
+    // The Bot API can't fetch messages, so the ones still in the registry count as existing and
+    // replies to them are sent without the reply if they were deleted
+    async fn message_exists(&self, _channel_id: ChannelId, message_id: MessageId) -> bool {
+        self.message(message_id).is_ok()
+    }
+
+    async fn create_thread(
+        &self,
+        _channel_id: ChannelId,
+        message_id: MessageId,
+        _name: String,
+    ) -> PlatformResult<ChannelId> {
+        let (chat, message) = self.message(message_id)?;
+        Ok(self.channel_id(chat, Some(message)))
+    }
+
+    async fn is_text_channel(&self, channel_id: ChannelId) -> bool {
+        let Ok((chat, None)) = self.chat(channel_id) else {
+            return false;
+        };
+        self.call::<Chat>("getChat", json!({ "chat_id": chat }))
+            .await
+            .is_ok_and(|chat| matches!(chat.kind.as_str(), "group" | "supergroup"))
+    }
+
+    // Bots can't list the members of a group, so this starts with the admins and everyone else is
+    // added once they send a message
+    async fn members(&self, guild_id: GuildId) -> PlatformResult<Vec<User>> {
+        let chat = self
+            .registry
+            .native(guild_id.get())
+            .ok_or("Unknown Telegram chat")?;
+        Ok(self
+            .call::<Vec<ChatMember>>("getChatAdministrators", json!({ "chat_id": chat }))
+            .await?
+            .iter()
+            .filter(|member| !member.user.is_bot)
+            .map(|member| self.user(&member.user))
+            .collect())
+    }
+
+    async fn role_members(
+        &self,
+        _guild_id: GuildId,
+        _role_id: RoleId,
+    ) -> PlatformResult<Vec<UserId>> {
+        Err("Roles are not supported on Telegram".into())
+    }
+
+    async fn set_role(
+        &self,
+        _guild_id: GuildId,
+        _user_id: UserId,
+        _role_id: RoleId,
+        _assigned: bool,
+    ) -> PlatformResult<()> {
+        Err("Role rewards are not supported on Telegram".into())
+    }
+}
+
+async fn ready(
+    platform: &Arc<Telegram>,
+    data: &Arc<RwLock<TypeMap>>,
+) -> Result<(), Box<dyn Error>> {
+    {
+        let mut data = data.write().await;
+        let state = get_shared_state!(data);
+        if state.ready {
+            return Ok(());
+        }
+        state.ready = true;
+        let guilds = state
+            .database
+            .keys()
+            .filter(|guild_id| {
+                platform
+                    .registry
+                    .native(guild_id.get())
+                    .is_some_and(|native| native.parse::<i64>().is_ok())
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        info!("Setting up Telegram chats {guilds:?}");
+        for guild_id in guilds {
+            if let Err(why) = initialise_guilds(platform.as_ref(), &guild_id, state).await {
+                error!("Error setting up Telegram chat: {why}");
+            }
+        }
+    }
+    let platform: Arc<dyn ChatPlatform> = platform.clone();
+    set_platform(platform.clone());
+    schedule_thread!(platform, data, schedule_daily_question);
+    schedule_thread!(platform, data, schedule_weekly_contest);
+    Ok(())
+}
+
+async fn dispatch(
+    platform: &Telegram,
+    data: &RwLock<TypeMap>,
+    update: Update,
+) -> Result<(), Box<dyn Error>> {
+    let mut data = data.write().await;
+    let state = get_shared_state!(data);
+    if let Some(message) = update.message {
+        let Some(from) = message.from.as_ref().filter(|from| !from.is_bot) else {
+            return Ok(());
+        };
+        let (Some(text), entities) = (match &message.text {
+            Some(text) => (Some(text), &message.entities),
+            None => (message.caption.as_ref(), &message.caption_entities),
+        }) else {
+            return Ok(());
+        };
+        let guild_id = Some(platform.guild_id(message.chat.id))
+            .filter(|_| matches!(message.chat.kind.as_str(), "group" | "supergroup"));
+        let user = platform.user(from);
+        let author = user.id;
+        if let Some(users) = guild_id.and_then(|guild_id| state.guilds.get_mut(&guild_id)) {
+            users.entry(author).or_insert(user);
+        }
+        let thread = message
+            .reply_to_message
+            .as_ref()
+            .and_then(|reply| platform.thread(message.chat.id, reply));
+        platform.remember(message.chat.id, message.message_id, thread);
+        let mut content = Regex::new(r"<@(\d+)>")?
+            .replace_all(&markdown(text, entities), |caps: &Captures| {
+                format!("<@{}>", platform.registry.id(&format!("user/{}", &caps[1])))
+            })
+            .into_owned();
+        // Commands in groups can be addressed to a bot with /command@username
+        if let Some((command, rest)) = content.split_once(&format!("@{}", platform.username)) {
+            if command.starts_with('/') && !command.contains(char::is_whitespace) {
+                content = format!("{command}{rest}");
+            }
+        }
+        let incoming = Incoming {
+            guild_id,
+            channel_id: platform.channel_id(message.chat.id, thread),
+            author,
+            content,
+            link: message_link(&message.chat, message.message_id),
+        };
+        handle_message(platform, state, incoming).await?;
+    } else if let Some(query) = update.callback_query {
+        let voted_for = query
+            .data
+            .as_deref()
+            .and_then(|data| data.strip_prefix(CUSTOM_ID)?.strip_prefix(':'))
+            .and_then(|id| id.parse().ok());
+        let response = match (query.message, voted_for) {
+            (Some(message), Some(voted_for)) => handle_vote(
+                state,
+                platform.guild_id(message.chat.id),
+                platform.user(&query.from).id,
+                platform.message_id(message.chat.id, message.message_id),
+                UserId::new(voted_for),
+            )?,
+            _ => None,
+        };
+        // The response is only shown to the voter, like an ephemeral message on Discord
+        let mut params = json!({ "callback_query_id": query.id });
+        if let Some(response) = response {
+            params["text"] = json!(platform.plain(&response)?);
+        }
+        platform
+            .call::<Value>("answerCallbackQuery", params)
+            .await?;
+    } else if let Some(member) = update.my_chat_member {
+        let joined = matches!(
+            member.new_chat_member.status.as_str(),
+            "member" | "administrator"
+        ) && matches!(member.old_chat_member.status.as_str(), "left" | "kicked")
+            && member.new_chat_member.user.id == platform.bot_id
+            && matches!(member.chat.kind.as_str(), "group" | "supergroup");
+        if !joined {
+            return Ok(());
+        }
+        info!("Added to Telegram chat {}", member.chat.id);
+        let channel_id = platform.channel_id(member.chat.id, None);
+        add_guild(
+            platform,
+            state,
+            platform.guild_id(member.chat.id),
+            Some(channel_id),
+        )
+        .await?;
+    } else {
+        return Ok(());
+    }
+    write_to_database!(state);
+    Ok(())
+}
+
+async fn poll(
+    platform: &Arc<Telegram>,
+    data: &Arc<RwLock<TypeMap>>,
+    offset: &mut Option<i64>,
+) -> Result<(), Box<dyn Error>> {
+    let updates = platform
+        .call::<Vec<Update>>(
+            "getUpdates",
+            json!({
+                "offset": offset,
+                "timeout": if offset.is_some() { POLL_TIMEOUT } else { 0 },
+                "allowed_updates": ALLOWED_UPDATES,
+            }),
+        )
+        .await?;
+    metrics().set_gateway_connected(true);
+    let first = offset.is_none();
+    for update in updates {
+        *offset = Some(update.update_id + 1);
+        // Messages sent while offline are ignored like on Discord, being added to a chat isn't
+        if first && update.my_chat_member.is_none() {
+            continue;
+        }
+        if let Err(why) = dispatch(platform, data, update).await {
+            error!("Error responding to Telegram update: {why}");
+        }
+    }
+    if first {
+        offset.get_or_insert(0);
+        info!("Connected to Telegram");
+        ready(platform, data).await?;
+    }
+    Ok(())
+}
+
+pub async fn run_telegram(
+    platform: Arc<Telegram>,
+    data: Arc<RwLock<TypeMap>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut offset = None;
+    loop {
+        // The error is dropped before sleeping so the future stays Send
+        if let Err(why) = poll(&platform, &data, &mut offset).await {
+            error!("Error polling Telegram: {why}");
+        } else {
+            continue;
+        }
+        metrics().set_gateway_connected(false);
+        warn!("Disconnected from Telegram, reconnecting");
+        sleep(RECONNECT_DELAY).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entities(entities: Value) -> Vec<Entity> {
+        serde_json::from_value(entities).unwrap()
+    }
+
+    #[test]
+    fn offsets_count_utf16_units() {
+        // Emoji outside the basic plane take two units, before and inside entities
+        let text = "😀 fn main() {} 🎉";
+        assert_eq!(
+            markdown(
+                text,
+                &entities(json!([{"type": "pre", "offset": 3, "length": 12}]))
+            ),
+            "😀 ```\nfn main() {}\n``` 🎉"
+        );
+        assert_eq!(
+            markdown(
+                "a😀b c",
+                &entities(json!([{"type": "bold", "offset": 0, "length": 4}]))
+            ),
+            "**a😀b** c"
+        );
+        // Entities past the end of the text are dropped
+        assert_eq!(
+            markdown(
+                "😀",
+                &entities(json!([{"type": "bold", "offset": 1, "length": 4}]))
+            ),
+            "😀"
+        );
+    }
+
+    #[test]
+    fn nested_and_adjacent_entities() {
+        assert_eq!(
+            markdown(
+                "abc",
+                &entities(json!([
+                    {"type": "code", "offset": 1, "length": 1},
+                    {"type": "bold", "offset": 0, "length": 3},
+                ]))
+            ),
+            "**a`b`c**"
+        );
+        assert_eq!(
+            markdown(
+                "ab",
+                &entities(json!([
+                    {"type": "bold", "offset": 0, "length": 1},
+                    {"type": "code", "offset": 1, "length": 1},
+                ]))
+            ),
+            "**a**`b`"
+        );
+        assert_eq!(
+            markdown(
+                "hi Alice",
+                &entities(
+                    json!([{"type": "text_mention", "offset": 3, "length": 5, "user": {
+                        "id": 42,
+                        "first_name": "Alice",
+                    }}])
+                )
+            ),
+            "hi <@42>"
+        );
+    }
+
+    #[test]
+    fn spoilered_code_is_a_submission() {
+        let text = "🦀\nfn main() {}";
+        assert_eq!(
+            markdown(
+                text,
+                &entities(json!([
+                    {"type": "pre", "offset": 3, "length": 12, "language": "rust"},
+                    {"type": "spoiler", "offset": 0, "length": 15},
+                ]))
+            ),
+            "||🦀\n```rust\nfn main() {}\n```||"
+        );
+        // The whole spoiler being code opens both at the same place, the spoiler outside
+        assert_eq!(
+            markdown(
+                "fn main() {}",
+                &entities(json!([
+                    {"type": "pre", "offset": 0, "length": 12, "language": "rust"},
+                    {"type": "spoiler", "offset": 0, "length": 12},
+                ]))
+            ),
+            "||```rust\nfn main() {}\n```||"
+        );
+    }
+}
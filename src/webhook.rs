@@ -0,0 +1,143 @@
+use crate::{
+    clock, config,
+    config::{WebhookConfig, WebhookKind},
+    leetcode::daily_question_embed,
+    metrics,
+    platform::Embed,
+    time_till_utc_midnight,
+};
+use regex::{Captures, Regex};
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    error::Error,
+    time::Duration,
+};
+use tracing::{debug, error, info};
+
+// Discord markdown links and mentions to Slack mrkdwn
+fn to_mrkdwn(content: &str) -> Result<String, Box<dyn Error>> {
+    let content = content
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    Ok(Regex::new(r"\[([^\]\n]+)\]\(([^)\s]+)\)|@everyone")?
+        .replace_all(&content, |caps: &Captures| {
+            match (caps.get(1), caps.get(2)) {
+                (Some(name), Some(url)) => format!("<{}|{}>", url.as_str(), name.as_str()),
+                _ => String::from("<!channel>"),
+            }
+        })
+        .into_owned())
+}
+
+fn payload(kind: WebhookKind, content: &str, embed: &Embed) -> Result<Value, Box<dyn Error>> {
+    Ok(match kind {
+        WebhookKind::Discord => json!({
+            "content": content,
+            "embeds": [{
+                "title": embed.title,
+                "url": embed.url,
+                "color": embed.colour.0,
+                "fields": embed
+                    .fields
+                    .iter()
+                    .map(|(name, value)| json!({"name": name, "value": value, "inline": true}))
+                    .collect::<Vec<_>>(),
+            }],
+        }),
+        WebhookKind::Slack => json!({
+            "text": to_mrkdwn(content)?,
+            "attachments": [{
+                "color": format!("#{}", embed.colour.hex()),
+                "title": embed.title,
+                "title_link": embed.url,
+                "fields": embed
+                    .fields
+                    .iter()
+                    .map(|(title, value)| json!({"title": title, "value": value, "short": true}))
+                    .collect::<Vec<_>>(),
+            }],
+        }),
+        WebhookKind::Json => json!({
+            "content": content,
+            "date": clock().now().date_naive(),
+            "title": embed.title,
+            "url": embed.url,
+            "colour": format!("#{}", embed.colour.hex()),
+            "fields": embed
+                .fields
+                .iter()
+                .map(|(name, value)| json!({"name": name, "value": value}))
+                .collect::<Vec<_>>(),
+        }),
+    })
+}
+
+async fn deliver(
+    client: &Client,
+    webhook: &WebhookConfig,
+    content: &str,
+    embed: &Embed,
+) -> Result<(), Box<dyn Error>> {
+    client
+        .post(&webhook.url)
+        .json(&payload(webhook.kind, content, embed)?)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|why| why.without_url())?;
+    Ok(())
+}
+
+pub async fn post_daily_question() {
+    let client = Client::new();
+    let mut embeds = HashMap::new();
+    for (index, webhook) in config().webhooks.iter().enumerate() {
+        let (content, embed) = match embeds.entry(webhook.site) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => match daily_question_embed(webhook.site).await {
+                Ok(daily) => entry.insert(daily),
+                Err(why) => {
+                    error!(
+                        "Error fetching the {:?} daily question: {why}",
+                        webhook.site
+                    );
+                    continue;
+                }
+            },
+        };
+        // Webhook URLs contain their secret, so they are referred to by position
+        match deliver(&client, webhook, content, embed).await {
+            Ok(()) => info!("Delivered the daily question to webhook {index}"),
+            Err(why) => error!("Error delivering the daily question to webhook {index}: {why}"),
+        }
+    }
+}
+
+async fn schedule_webhooks() -> Result<(), Box<dyn Error>> {
+    loop {
+        let duration: u64 = time_till_utc_midnight()?.num_seconds().try_into()?;
+        debug!("{duration} seconds until the next daily question is delivered");
+        clock().sleep(Duration::from_secs(duration)).await;
+        post_daily_question().await;
+        metrics().record_scheduler_run("webhook_daily");
+    }
+}
+
+pub async fn run_webhooks() -> Result<(), Box<dyn Error>> {
+    if config().webhooks.is_empty() {
+        return Err("No webhooks configured, add them as [[webhooks]] in the config file".into());
+    }
+    info!(
+        "Delivering the daily question to {} webhooks",
+        config().webhooks.len()
+    );
+    // There is no gateway to connect to, health only depends on the scheduler
+    metrics().set_gateway_connected(true);
+    metrics().set_scheduler_alive("schedule_webhooks", true);
+    let result = schedule_webhooks().await;
+    metrics().set_scheduler_alive("schedule_webhooks", false);
+    result
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::{clock, edit_guild, settle, start, Server, SUBMISSION};
+use serde_json::{json, Value};
+use serenity::model::prelude::UserId;
+
+fn badges(user: Value) -> Vec<String> {
+    user["badges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|earned| earned["badge"].as_str().unwrap().to_string())
+        .collect()
+}
+
+fn edit_user(server: &mut Server, user_id: UserId, edit: impl FnOnce(&mut Value)) {
+    edit_guild(&mut server.state.database, server.guild_id, |guild| {
+        edit(&mut guild["users"][user_id.to_string()])
+    });
+}
+
+#[tokio::test]
+async fn submissions_earn_the_badges_whose_rules_they_meet() {
+    // The fixture's daily question is Hard and the 31st completes March
+    let _guard = start(Utc.with_ymd_and_hms(2026, 3, 31, 12, 0, 0).unwrap()).await;
+    let mut server = Server::new(&["Alice", "Bob", "Carol"]).await;
+    let [alice, bob, carol] = server.users[..] else {
+        unreachable!()
+    };
+    edit_user(&mut server, alice, |user| {
+        user["streak"] = json!(29);
+        user["monthly_record"] = json!(30);
+    });
+    edit_user(&mut server, bob, |user| user["days_missed"] = json!(8));
+    edit_user(&mut server, carol, |user| user["days_missed"] = json!(7));
+    let thread = server.thread();
+
+    server.send(alice, thread, SUBMISSION).await;
+    assert_eq!(
+        badges(server.user(alice)),
+        ["perfect_month", "first_hard", "streak"]
+    );
+    assert_eq!(server.user(alice)["badges"][0]["date"], "2026-03-31");
+    let congrats = server.contents(thread).pop().unwrap();
+    assert!(congrats.contains("🏅 You earned the **30 Day Streak** badge"));
+    assert!(congrats.contains("🏅 You earned the **Perfect Month** badge"));
+
+    // A comeback is only after missing more than 7 days
+    server.send(bob, thread, SUBMISSION).await;
+    assert_eq!(badges(server.user(bob)), ["comeback", "first_hard"]);
+    server.send(carol, thread, SUBMISSION).await;
+    assert_eq!(badges(server.user(carol)), ["first_hard"]);
+}
+
+#[tokio::test]
+async fn first_hard_depends_on_the_difficulty_and_is_earned_once() {
+    let _guard = start(Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap()).await;
+    let mut server = Server::new(&["Alice", "Bob"]).await;
+    let [alice, bob] = server.users[..] else {
+        unreachable!()
+    };
+    let thread = server.thread();
+    edit_guild(&mut server.state.database, server.guild_id, |guild| {
+        guild["difficulty"] = json!("Medium")
+    });
+    server.send(alice, thread, SUBMISSION).await;
+    assert!(badges(server.user(alice)).is_empty());
+    assert!(!server.contents(thread).pop().unwrap().contains("🏅"));
+
+    // Badges already earned are kept from the day they were earned and not announced again
+    edit_guild(&mut server.state.database, server.guild_id, |guild| {
+        guild["difficulty"] = json!("Hard")
+    });
+    edit_user(&mut server, bob, |user| {
+        user["badges"] = json!([{"badge": "first_hard", "date": "2026-02-01"}])
+    });
+    server.send(bob, thread, SUBMISSION).await;
+    assert_eq!(server.user(bob)["badges"].as_array().unwrap().len(), 1);
+    assert_eq!(server.user(bob)["badges"][0]["date"], "2026-02-01");
+    assert!(!server.contents(thread).pop().unwrap().contains("🏅"));
+}
+
+#[tokio::test]
+async fn finishing_every_contest_question_earns_a_badge() {
+    let _guard = start(Utc.with_ymd_and_hms(2026, 3, 15, 3, 0, 0).unwrap()).await;
+    let mut server = Server::new(&["Alice"]).await;
+    let alice = server.users[0];
+    let weekly = server.platform.add_text_channel();
+    edit_guild(&mut server.state.database, server.guild_id, |guild| {
+        guild["active_weekly"] = json!(true);
+        guild["weekly_id"] = json!(weekly);
+    });
+    for _ in 0..3 {
+        server.send(alice, weekly, SUBMISSION).await;
+    }
+    assert!(badges(server.user(alice)).is_empty());
+    server.send(alice, weekly, SUBMISSION).await;
+    assert_eq!(badges(server.user(alice)), ["contest_full_solve"]);
+    assert!(server
+        .contents(weekly)
+        .pop()
+        .unwrap()
+        .contains("🏅 You earned the **Contest Full Solve** badge"));
+}
+
+#[tokio::test]
+async fn everyone_tied_for_the_most_votes_earns_a_badge_at_rollover() {
+    let _guard = start(Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap()).await;
+    let shared = Server::new(&["Alice", "Bob", "Carol", "Dave"])
+        .await
+        .into_shared();
+    let [alice, bob, carol, dave] = shared.users[..] else {
+        unreachable!()
+    };
+    shared
+        .edit_guild(|guild| {
+            for (user_id, voted_for) in [(alice, bob), (bob, alice), (carol, alice), (dave, bob)] {
+                let user = &mut guild["users"][user_id.to_string()];
+                user["submitted"] = json!("https://example.com");
+                user["voted_for"] = json!(voted_for);
+            }
+        })
+        .await;
+    let task = shared.schedule_daily_question();
+    settle(1).await;
+
+    clock().set(Utc.with_ymd_and_hms(2026, 3, 10, 23, 1, 0).unwrap());
+    settle(1).await;
+    clock().set(Utc.with_ymd_and_hms(2026, 3, 11, 0, 1, 0).unwrap());
+    settle(1).await;
+    assert_eq!(shared.guild().await["last_rollover"], "2026-03-11");
+    for user_id in [alice, bob] {
+        let user = shared.user(user_id).await;
+        assert_eq!(badges(user.clone()), ["top_voted"]);
+        // Votes are for the day that just ended
+        assert_eq!(user["badges"][0]["date"], "2026-03-10");
+    }
+    assert!(badges(shared.user(carol).await).is_empty());
+    let summary = shared.contents(shared.thread().await)[0].clone();
+    assert!(summary.contains(&format!("🏅 <@{alice}> earned the **Top Voted** badge")));
+    task.abort();
+}
@@ -0,0 +1,256 @@
+use serde_json::{json, Value};
+use std::{
+    fs::{read_dir, read_to_string, write},
+    path::Path,
+    process::{Command, Output},
+};
+
+// Runs the binary in an empty directory so no config.toml is picked up, reading the saved responses
+fn leetcode_daily(args: &[&str]) -> Output {
+    let dir = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_leetcode_daily"))
+        .arg("--leetcode-fixtures")
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/fixtures"))
+        .args(args)
+        .current_dir(dir.path())
+        .env_clear()
+        .output()
+        .unwrap();
+    // Queries don't open the database or the log
+    assert_eq!(read_dir(dir.path()).unwrap().count(), 0);
+    output
+}
+
+fn stdout(args: &[&str]) -> String {
+    let output = leetcode_daily(args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn stderr(args: &[&str]) -> String {
+    let output = leetcode_daily(args);
+    assert!(!output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn daily_prints_the_question_of_the_day() {
+    assert_eq!(
+        stdout(&["daily"]),
+        "42. Trapping Rain Water\nhttps://leetcode.com/problems/trapping-rain-water/\nDifficulty: Hard\nAcceptance Rate: 63.90%\n"
+    );
+}
+
+#[test]
+fn daily_prints_json_from_leetcode_cn() {
+    // leetcode.cn titles are in Chinese
+    let daily =
+        serde_json::from_str::<Value>(&stdout(&["daily", "--site", "cn", "--format", "json"]))
+            .unwrap();
+    assert_eq!(
+        daily,
+        json!({
+            "title": "42. 接雨水",
+            "url": "https://leetcode.cn/problems/trapping-rain-water/",
+            "colour": "#992D22",
+            "fields": [
+                {"name": "Difficulty", "value": "Hard"},
+                {"name": "Acceptance Rate", "value": "63.90%"},
+            ],
+        })
+    );
+}
+
+#[test]
+fn random_only_picks_questions_matching_every_filter() {
+    // Add Two Numbers is the only free medium question in the fixtures
+    assert!(stdout(&["random", "--filter", "medium,!paid"]).starts_with("2. Add Two Numbers\n"));
+    assert!(stdout(&["random", "--filter", "paid"]).starts_with("156. Binary Tree Upside Down\n"));
+    let hard = stdout(&["random", "--filter", "!easy,!medium"]);
+    assert!(hard.contains("Difficulty: Hard\n"), "{hard}");
+}
+
+#[test]
+fn random_fails_when_nothing_matches() {
+    assert!(stderr(&["random", "--filter", "paid,easy"])
+        .contains("No question found that fits your requirement"));
+}
+
+#[test]
+fn problem_finds_questions_by_number_or_slug() {
+    let by_number = stdout(&["problem", "704"]);
+    assert!(by_number.starts_with("704. Binary Search\n"));
+    assert!(by_number.ends_with("Difficulty: Easy\nAcceptance Rate: 58.40%\n"));
+    assert_eq!(stdout(&["problem", "binary-search"]), by_number);
+    assert!(stdout(&["problem", "704", "--site", "cn"])
+        .contains("https://leetcode.cn/problems/binary-search/\n"));
+}
+
+#[test]
+fn problem_fails_for_unknown_questions() {
+    assert!(stderr(&["problem", "9999"]).contains("No question found with number or slug 9999"));
+}
+
+#[test]
+fn start_refuses_a_database_that_does_not_parse() {
+    let dir = tempfile::tempdir().unwrap();
+    let database = dir.path().join("database.json");
+    write(&database, "{\"1\": ").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_leetcode_daily"))
+        .args(["--token", "token", "--log-output", "log"])
+        .current_dir(dir.path())
+        .env_clear()
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("leetcode_daily db validate"), "{stderr}");
+    assert_eq!(read_to_string(&database).unwrap(), "{\"1\": ");
+}
+
+// Runs a db subcommand against the database.json in dir
+fn db(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_leetcode_daily"))
+        .arg("db")
+        .args(args)
+        .current_dir(dir)
+        .env_clear()
+        .output()
+        .unwrap()
+}
+
+fn guild(users: Value) -> Value {
+    json!({
+        "users": users,
+        "channel_id": null,
+        "thread_id": null,
+        "weekly_id": null,
+        "poll_id": null,
+        "active_weekly": false,
+        "active_daily": false,
+    })
+}
+
+fn status(score: usize, voted_for: Option<u64>, submitted: Option<&str>) -> Value {
+    json!({
+        "voted_for": voted_for.map(|id| id.to_string()),
+        "submitted": submitted,
+        "weekly_submissions": 0,
+        "monthly_record": 0,
+        "days_missed": 0,
+        "score": score,
+    })
+}
+
+fn read_json(path: &Path) -> Value {
+    serde_json::from_str(&read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn db_import_replaces_only_the_guilds_in_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let database = dir.path().join("database.json");
+    let original = json!({
+        "1": guild(json!({"10": status(5, None, None)})),
+        "2": guild(json!({"20": status(1, None, None)})),
+    })
+    .to_string();
+    write(&database, &original).unwrap();
+    let imported = dir.path().join("guilds.json");
+    write(
+        &imported,
+        json!({
+            "2": guild(json!({"21": status(3, None, None)})),
+            "3": guild(json!({"30": status(7, None, None)})),
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let output = db(dir.path(), &["import", "guilds.json"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("Imported 2 guilds"));
+    let merged = read_json(&database);
+    assert_eq!(merged.as_object().unwrap().len(), 3);
+    assert_eq!(merged["1"]["users"]["10"]["score"], 5);
+    assert!(merged["2"]["users"].get("20").is_none());
+    assert_eq!(merged["2"]["users"]["21"]["score"], 3);
+    assert_eq!(merged["3"]["users"]["30"]["score"], 7);
+    assert_eq!(
+        read_to_string(dir.path().join("database.json.bak")).unwrap(),
+        original
+    );
+}
+
+#[test]
+fn db_import_refuses_a_file_with_problems() {
+    let dir = tempfile::tempdir().unwrap();
+    let database = dir.path().join("database.json");
+    let original = json!({"1": guild(json!({"10": status(5, None, None)}))}).to_string();
+    write(&database, &original).unwrap();
+    // Voting for someone who hasn't submitted
+    write(
+        dir.path().join("guilds.json"),
+        json!({"2": guild(json!({
+            "20": status(0, Some(21), None),
+            "21": status(0, None, None),
+        }))})
+        .to_string(),
+    )
+    .unwrap();
+    let output = db(dir.path(), &["import", "guilds.json"]);
+    assert!(!output.status.success());
+    assert_eq!(read_to_string(&database).unwrap(), original);
+    assert!(!dir.path().join("database.json.bak").exists());
+}
+
+#[test]
+fn db_validate_rejects_a_malformed_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let database = dir.path().join("database.json");
+    write(&database, "{\"1\": ").unwrap();
+    let output = db(dir.path(), &["validate"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Invalid database"), "{stderr}");
+
+    let broken = json!({"1": guild(json!({
+        "10": status(0, Some(10), Some("https://example.com")),
+        "11": status(0, Some(12), None),
+    }))})
+    .to_string();
+    write(&database, &broken).unwrap();
+    let output = db(dir.path(), &["validate"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("User 10 in guild 1 voted for themselves"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("User 11 in guild 1 voted for 12 who is not in the guild"));
+    assert!(stderr.contains("Found 2 problems in the database"));
+    // Validating never writes to the database
+    assert_eq!(read_to_string(&database).unwrap(), broken);
+
+    write(
+        &database,
+        json!({"1": guild(json!({"10": status(0, None, None)}))}).to_string(),
+    )
+    .unwrap();
+    let output = db(dir.path(), &["validate"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "No problems found\n"
+    );
+}
@@ -0,0 +1,284 @@
+#![allow(dead_code)]
+
+use chrono::{DateTime, Utc};
+use leetcode_daily::{
+    add_guild, handle_message, schedule_daily_question, schedule_weekly_contest, set_clock,
+    set_config, set_score, show_database, ChatPlatform, Database, Incoming, MemoryPlatform,
+    SharedState, SimulatedClock, State,
+};
+use serde_json::Value;
+use serenity::{
+    model::prelude::{ChannelId, GuildId, UserId},
+    prelude::{RwLock, TypeMap},
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+use tokio::{
+    spawn,
+    sync::{Mutex, MutexGuard},
+    task::{yield_now, JoinHandle},
+    time::timeout,
+};
+use toml::{Table, Value as Toml};
+
+// The config and clock are set once per process, so tests sharing them take turns
+static CLOCK: OnceLock<Arc<SimulatedClock>> = OnceLock::new();
+static LOCK: Mutex<()> = Mutex::const_new(());
+
+const CONFIG: &str = r#"
+[leetcode]
+fixtures = "assets/fixtures"
+
+[leaderboard]
+image = false
+"#;
+
+pub const SUBMISSION: &str = "||```rust\nfn main() {}\n```||";
+
+pub async fn start(now: DateTime<Utc>) -> MutexGuard<'static, ()> {
+    let guard = LOCK.lock().await;
+    clock().set(now);
+    guard
+}
+
+pub fn clock() -> &'static SimulatedClock {
+    configure("")
+}
+
+// Changes settings of the test config, only before the first test of the file starts
+pub fn configure(config: &str) -> &'static SimulatedClock {
+    CLOCK.get_or_init(|| {
+        let mut table = toml::from_str::<Table>(CONFIG).unwrap();
+        for (section, settings) in toml::from_str::<Table>(config).unwrap() {
+            match (table.get_mut(&section), settings) {
+                (Some(Toml::Table(defaults)), Toml::Table(settings)) => defaults.extend(settings),
+                (_, settings) => {
+                    table.insert(section, settings);
+                }
+            }
+        }
+        set_config(table.try_into().unwrap()).unwrap();
+        let clock = Arc::new(SimulatedClock::new(Utc::now()));
+        set_clock(clock.clone()).unwrap();
+        clock
+    })
+}
+
+// Lets woken schedulers run until that many are asleep again, so moving time next wakes them
+// rather than moving their deadlines. Nothing they do waits on real IO so a few turns is enough
+pub async fn settle(sleepers: usize) {
+    timeout(Duration::from_secs(5), async {
+        for _ in 0..10 {
+            yield_now().await;
+        }
+        while clock().sleepers() < sleepers {
+            yield_now().await;
+        }
+    })
+    .await
+    .expect("Schedulers did not go to sleep");
+}
+
+// Goes through the JSON form of the guild since the fields of the database are private
+pub fn edit_guild(database: &mut Database, guild_id: GuildId, edit: impl FnOnce(&mut Value)) {
+    let mut json = serde_json::to_value(&*database).unwrap();
+    edit(&mut json[guild_id.to_string()]);
+    *database = serde_json::from_value(json).unwrap();
+}
+
+pub struct Server {
+    pub platform: MemoryPlatform,
+    pub state: SharedState,
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub users: Vec<UserId>,
+}
+
+impl Server {
+    // Adds the guild the way joining a server does, which posts the first daily question
+    pub async fn new(names: &[&str]) -> Self {
+        let platform = MemoryPlatform::new();
+        let guild_id = GuildId::new(500);
+        let channel_id = platform.add_text_channel();
+        let users = names
+            .iter()
+            .map(|name| platform.add_member(guild_id, name))
+            .collect();
+        let mut state = SharedState {
+            ready: true,
+            shutting_down: false,
+            guilds: HashMap::new(),
+            file: tempfile::tempfile().unwrap(),
+            database: HashMap::new(),
+        };
+        add_guild(&platform, &mut state, guild_id, Some(channel_id))
+            .await
+            .unwrap();
+        Self {
+            platform,
+            state,
+            guild_id,
+            channel_id,
+            users,
+        }
+    }
+
+    // Hands the platform and state over to schedulers, which share them the way the bot does
+    pub fn into_shared(self) -> Shared {
+        let mut data = TypeMap::new();
+        data.insert::<State>(self.state);
+        Shared {
+            platform: Arc::new(self.platform),
+            data: Arc::new(RwLock::new(data)),
+            guild_id: self.guild_id,
+            channel_id: self.channel_id,
+            users: self.users,
+        }
+    }
+
+    pub fn set_score(&mut self, user_id: UserId, score: usize) {
+        set_score(&mut self.state.database, self.guild_id, user_id, score).unwrap();
+    }
+
+    pub async fn send(&mut self, author: UserId, channel_id: ChannelId, content: &str) {
+        handle_message(
+            &self.platform,
+            &mut self.state,
+            Incoming {
+                guild_id: Some(self.guild_id),
+                channel_id,
+                author,
+                content: content.to_string(),
+                link: format!("https://example.com/{channel_id}/{author}"),
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    pub fn guild(&self) -> Value {
+        show_database(&self.state.database, Some(self.guild_id), None).unwrap()
+    }
+
+    pub fn user(&self, user_id: UserId) -> Value {
+        show_database(&self.state.database, Some(self.guild_id), Some(user_id)).unwrap()
+    }
+
+    pub fn thread(&self) -> ChannelId {
+        serde_json::from_value(self.guild()["thread_id"].clone()).unwrap()
+    }
+
+    pub fn thread_name(&self, thread: ChannelId) -> String {
+        self.platform
+            .threads()
+            .into_iter()
+            .find(|created| created.id == thread)
+            .unwrap()
+            .name
+    }
+
+    pub fn contents(&self, channel_id: ChannelId) -> Vec<String> {
+        self.platform
+            .messages(channel_id)
+            .into_iter()
+            .map(|sent| sent.content)
+            .collect()
+    }
+
+    pub fn current_user(&self) -> UserId {
+        self.platform.current_user()
+    }
+}
+
+pub struct Shared {
+    pub platform: Arc<MemoryPlatform>,
+    pub data: Arc<RwLock<TypeMap>>,
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub users: Vec<UserId>,
+}
+
+impl Shared {
+    pub fn schedule_daily_question(&self) -> JoinHandle<Result<(), String>> {
+        let platform = self.platform.clone();
+        let data = self.data.clone();
+        spawn(async move {
+            let result = schedule_daily_question(platform.as_ref(), &data).await;
+            result.map_err(|why| why.to_string())
+        })
+    }
+
+    pub fn schedule_weekly_contest(&self) -> JoinHandle<Result<(), String>> {
+        let platform = self.platform.clone();
+        let data = self.data.clone();
+        spawn(async move {
+            let result = schedule_weekly_contest(platform.as_ref(), &data).await;
+            result.map_err(|why| why.to_string())
+        })
+    }
+
+    pub async fn send(&self, author: UserId, channel_id: ChannelId, content: &str) {
+        let mut data = self.data.write().await;
+        handle_message(
+            self.platform.as_ref(),
+            data.get_mut::<State>().unwrap(),
+            Incoming {
+                guild_id: Some(self.guild_id),
+                channel_id,
+                author,
+                content: content.to_string(),
+                link: format!("https://example.com/{channel_id}/{author}"),
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    pub async fn thread(&self) -> ChannelId {
+        serde_json::from_value(self.guild().await["thread_id"].clone()).unwrap()
+    }
+
+    pub async fn guild(&self) -> Value {
+        let data = self.data.read().await;
+        show_database(
+            &data.get::<State>().unwrap().database,
+            Some(self.guild_id),
+            None,
+        )
+        .unwrap()
+    }
+
+    pub async fn user(&self, user_id: UserId) -> Value {
+        let data = self.data.read().await;
+        let database = &data.get::<State>().unwrap().database;
+        show_database(database, Some(self.guild_id), Some(user_id)).unwrap()
+    }
+
+    pub async fn edit_guild(&self, edit: impl FnOnce(&mut Value)) {
+        let mut data = self.data.write().await;
+        edit_guild(
+            &mut data.get_mut::<State>().unwrap().database,
+            self.guild_id,
+            edit,
+        );
+    }
+
+    pub fn thread_names(&self) -> Vec<String> {
+        self.platform
+            .threads()
+            .into_iter()
+            .map(|thread| thread.name)
+            .collect()
+    }
+
+    pub fn contents(&self, channel_id: ChannelId) -> Vec<String> {
+        self.platform
+            .messages(channel_id)
+            .into_iter()
+            .map(|sent| sent.content)
+            .collect()
+    }
+}
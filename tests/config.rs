@@ -0,0 +1,22 @@
+use leetcode_daily::Config;
+use std::io::Write;
+
+#[test]
+fn submission_tiers_apply_in_any_order() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(
+        br#"
+[scoring]
+submission = [
+    { hours_left = 0, points = 1 },
+    { hours_left = 16, points = 3 },
+    { hours_left = 8, points = 2 },
+]
+"#,
+    )
+    .unwrap();
+    let config = Config::from_file(file.path()).unwrap();
+    assert_eq!(config.scoring.submission_points(20), 3);
+    assert_eq!(config.scoring.submission_points(10), 2);
+    assert_eq!(config.scoring.submission_points(3), 1);
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::{clock, configure, start, Server, SUBMISSION};
+use leetcode_daily::rollover_guilds;
+
+#[tokio::test]
+async fn restarting_before_the_daily_time_does_not_roll_over() {
+    configure("[schedule]\ndaily = \"08:00:00\"");
+    let _guard = start(Utc.with_ymd_and_hms(2026, 3, 10, 9, 0, 0).unwrap()).await;
+    let mut server = Server::new(&["Alice", "Bob"]).await;
+    let [alice, bob] = server.users[..] else {
+        unreachable!()
+    };
+    let thread = server.thread();
+    server.send(alice, thread, SUBMISSION).await;
+    assert_eq!(server.user(alice)["score"], 5);
+
+    // Past midnight the question of the 10th is still the current one
+    clock().set(Utc.with_ymd_and_hms(2026, 3, 11, 3, 0, 0).unwrap());
+    rollover_guilds(&server.platform, &mut server.state)
+        .await
+        .unwrap();
+    assert_eq!(server.guild()["last_rollover"], "2026-03-10");
+    assert_eq!(server.thread(), thread);
+    assert!(!server.user(alice)["submitted"].is_null());
+    assert_eq!(server.user(bob)["days_missed"], 0);
+
+    clock().set(Utc.with_ymd_and_hms(2026, 3, 11, 8, 0, 0).unwrap());
+    rollover_guilds(&server.platform, &mut server.state)
+        .await
+        .unwrap();
+    assert_eq!(server.guild()["last_rollover"], "2026-03-11");
+    assert_ne!(server.thread(), thread);
+    assert!(server.user(alice)["submitted"].is_null());
+    assert_eq!(server.user(bob)["days_missed"], 1);
+}
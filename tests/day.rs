@@ -0,0 +1,218 @@
+mod common;
+
+use chrono::{TimeDelta, TimeZone, Utc};
+use common::{clock, edit_guild, settle, start, Server, SUBMISSION};
+use leetcode_daily::{add_guild, handle_vote, remind_guilds, rollover_guilds};
+use serde_json::Value;
+use serenity::model::prelude::GuildId;
+
+#[tokio::test]
+async fn full_day_of_submissions_votes_and_penalties() {
+    let _guard = start(Utc.with_ymd_and_hms(2026, 3, 10, 0, 30, 0).unwrap()).await;
+    let mut server = Server::new(&["Alice", "Bob", "Dan"]).await;
+    let [alice, bob, dan] = server.users[..] else {
+        unreachable!()
+    };
+    server.set_score(dan, 3);
+
+    // The daily question goes to the default channel with a thread for submissions
+    let daily = server.platform.messages(server.channel_id);
+    assert!(daily.iter().any(|sent| sent.embed.is_some()));
+    let thread = server.thread();
+    assert_eq!(server.thread_name(thread), "10/03/2026");
+    assert!(server.contents(thread)[0].contains("The current leaderboard:"));
+
+    // Submitting with 23 hours left is worth the most points
+    server.send(alice, thread, SUBMISSION).await;
+    assert!(server
+        .contents(thread)
+        .last()
+        .unwrap()
+        .contains("completing today's challenge"));
+    assert_eq!(server.user(alice)["score"], 5);
+    assert_eq!(server.user(alice)["monthly_record"], 1);
+    let poll = server
+        .platform
+        .messages(thread)
+        .into_iter()
+        .find(|sent| sent.poll)
+        .expect("The first submission starts the poll");
+
+    // Submissions outside the daily thread and second submissions don't count
+    server.send(bob, server.channel_id, SUBMISSION).await;
+    assert_eq!(server.user(bob)["score"], 0);
+    server.send(alice, thread, SUBMISSION).await;
+    assert_eq!(server.user(alice)["score"], 5);
+
+    clock().advance(TimeDelta::hours(11) + TimeDelta::minutes(30));
+    server.send(bob, thread, SUBMISSION).await;
+    assert_eq!(server.user(bob)["score"], 2);
+
+    let vote = |server: &mut Server, user_id, voted_for| {
+        handle_vote(
+            &mut server.state,
+            server.guild_id,
+            user_id,
+            poll.id,
+            voted_for,
+        )
+        .unwrap()
+        .unwrap()
+    };
+    assert_eq!(vote(&mut server, alice, alice), "Cannot vote for yourself");
+    assert_eq!(
+        vote(&mut server, alice, dan),
+        "Cannot vote for someone who hasn't completed the challenge"
+    );
+    assert_eq!(
+        vote(&mut server, alice, bob),
+        format!("Successfully voted for <@{bob}>")
+    );
+    assert_eq!(
+        vote(&mut server, bob, alice),
+        format!("Successfully voted for <@{alice}>")
+    );
+    assert_eq!(
+        vote(&mut server, dan, bob),
+        format!("Successfully voted for <@{bob}>")
+    );
+    // Only the latest vote counts
+    assert_eq!(
+        vote(&mut server, dan, alice),
+        format!("Successfully voted for <@{alice}>")
+    );
+
+    // Midnight: votes become points and whoever didn't submit loses one
+    clock().set(Utc.with_ymd_and_hms(2026, 3, 11, 0, 1, 0).unwrap());
+    rollover_guilds(&server.platform, &mut server.state)
+        .await
+        .unwrap();
+
+    assert_eq!(server.user(alice)["score"], 7);
+    assert_eq!(server.user(alice)["votes"], 2);
+    assert_eq!(server.user(alice)["streak"], 1);
+    assert_eq!(server.user(bob)["score"], 3);
+    assert_eq!(server.user(bob)["votes"], 1);
+    assert_eq!(server.user(dan)["score"], 2);
+    assert_eq!(server.user(dan)["days_missed"], 1);
+    assert_eq!(server.user(dan)["streak"], 0);
+    for user_id in [alice, bob, dan] {
+        assert!(server.user(user_id)["submitted"].is_null());
+        assert!(server.user(user_id)["voted_for"].is_null());
+    }
+    assert_eq!(server.guild()["last_rollover"], "2026-03-11");
+
+    let thread = server.thread();
+    assert_eq!(server.thread_name(thread), "11/03/2026");
+    let summary = server.contents(thread)[0].clone();
+    assert!(
+        summary.contains("Yesterday 1 person did not complete the challenge 😭 each lost 1 point")
+    );
+    assert!(summary.contains(&format!("1. <@{alice}>: **2**")));
+    assert!(summary.contains(&format!("2. <@{bob}>: **1**")));
+    assert!(summary.contains("The current leaderboard:"));
+
+    // A second rollover on the same day does nothing
+    rollover_guilds(&server.platform, &mut server.state)
+        .await
+        .unwrap();
+    assert_eq!(server.user(dan)["score"], 2);
+    assert_eq!(server.thread(), thread);
+}
+
+#[tokio::test]
+async fn missed_rollovers_are_caught_up() {
+    let _guard = start(Utc.with_ymd_and_hms(2026, 3, 10, 0, 30, 0).unwrap()).await;
+    let mut server = Server::new(&["Alice", "Bob"]).await;
+    let [alice, bob] = server.users[..] else {
+        unreachable!()
+    };
+    server.set_score(alice, 10);
+    server.set_score(bob, 10);
+    let thread = server.thread();
+    server.send(alice, thread, SUBMISSION).await;
+
+    // The bot was down for two midnights
+    clock().set(Utc.with_ymd_and_hms(2026, 3, 12, 9, 0, 0).unwrap());
+    rollover_guilds(&server.platform, &mut server.state)
+        .await
+        .unwrap();
+
+    // Only the day that had a question is penalised, there was no thread on the 11th
+    assert_eq!(server.user(alice)["score"], 15);
+    assert_eq!(server.user(alice)["days_missed"], 0);
+    assert_eq!(server.user(alice)["streak"], 1);
+    assert_eq!(server.user(bob)["score"], 9);
+    assert_eq!(server.user(bob)["days_missed"], 1);
+    let summary = server.contents(server.thread())[0].clone();
+    assert!(summary.contains("**11/03/2026**"));
+    assert!(summary.contains("**12/03/2026**"));
+    assert!(summary.contains("Yesterday 1 person did not complete the challenge"));
+    assert!(summary.contains("Yesterday there was no question while I was offline"));
+}
+
+#[tokio::test]
+async fn failed_rollovers_are_retried_the_same_day() {
+    let _guard = start(Utc.with_ymd_and_hms(2026, 3, 10, 0, 30, 0).unwrap()).await;
+    let shared = Server::new(&["Alice"]).await.into_shared();
+    let channel_id = shared.channel_id;
+    // Without a default channel the daily question can't be sent
+    shared
+        .edit_guild(|guild| guild["channel_id"] = Value::Null)
+        .await;
+
+    clock().set(Utc.with_ymd_and_hms(2026, 3, 11, 0, 0, 0).unwrap());
+    let task = shared.schedule_daily_question();
+    settle(1).await;
+    assert_eq!(shared.guild().await["last_rollover"], "2026-03-10");
+
+    shared
+        .edit_guild(|guild| guild["channel_id"] = serde_json::to_value(channel_id).unwrap())
+        .await;
+    clock().advance(TimeDelta::minutes(1));
+    settle(1).await;
+    assert_eq!(shared.guild().await["last_rollover"], "2026-03-11");
+    assert!(shared.thread_names().contains(&String::from("11/03/2026")));
+    task.abort();
+}
+
+#[tokio::test]
+async fn a_failed_reminder_does_not_stop_the_others() {
+    let _guard = start(Utc.with_ymd_and_hms(2026, 3, 10, 22, 0, 0).unwrap()).await;
+    let mut server = Server::new(&["Alice"]).await;
+    let broken = GuildId::new(501);
+    let channel_id = server.platform.add_text_channel();
+    server.platform.add_member(broken, "Bob");
+    add_guild(
+        &server.platform,
+        &mut server.state,
+        broken,
+        Some(channel_id),
+    )
+    .await
+    .unwrap();
+    edit_guild(&mut server.state.database, broken, |guild| {
+        guild["thread_id"] = Value::Null
+    });
+
+    remind_guilds(&server.platform, &mut server.state)
+        .await
+        .unwrap();
+
+    assert!(server
+        .contents(server.thread())
+        .iter()
+        .any(|content| content.starts_with("An hour left to make your submission")));
+    assert!(!server.guild()["poll_id"].is_null());
+}
+
+#[tokio::test]
+async fn help_links_to_the_guild_site() {
+    let _guard = start(Utc.with_ymd_and_hms(2026, 3, 10, 0, 30, 0).unwrap()).await;
+    let mut server = Server::new(&["Alice"]).await;
+    let alice = server.users[0];
+    server.send(alice, server.channel_id, "/site cn").await;
+    server.send(alice, server.channel_id, "/help").await;
+    let help = server.contents(server.channel_id).pop().unwrap();
+    assert!(help.contains("(https://leetcode.cn/problemset)"));
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::{start, Server};
+
+#[tokio::test]
+async fn csv_exports_do_not_run_names_as_formulas() {
+    let _guard = start(Utc.with_ymd_and_hms(2026, 3, 10, 0, 30, 0).unwrap()).await;
+    let names = [
+        "=HYPERLINK(\"https://example.com\")",
+        "+1",
+        "-1",
+        "@SUM(A1)",
+        "Alice",
+    ];
+    let mut server = Server::new(&names).await;
+    let alice = server.users[4];
+    server
+        .send(alice, server.channel_id, "/export scores csv")
+        .await;
+
+    let sent = server.platform.messages(server.channel_id).pop().unwrap();
+    let (file_name, csv) = sent.file.expect("The export is sent as a file");
+    assert!(file_name.ends_with(".csv"));
+    let csv = String::from_utf8(csv).unwrap();
+    let names = csv
+        .lines()
+        .skip(1)
+        .map(|row| row.split(',').nth(2).unwrap())
+        .collect::<Vec<_>>();
+    assert!(names.contains(&"'+1"));
+    assert!(names.contains(&"'-1"));
+    assert!(names.contains(&"'@SUM(A1)"));
+    assert!(names.contains(&"Alice"));
+    // Names with quotes are still quoted around the prefix
+    assert!(csv.contains(",\"'=HYPERLINK(\"\"https://example.com\"\")\","));
+    // Only text is prefixed, the ranks and scores stay numbers
+    assert!(csv
+        .lines()
+        .skip(1)
+        .all(|row| row.starts_with(char::is_numeric)));
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::{clock, configure, start, Server};
+use leetcode_daily::rollover_guilds;
+
+#[tokio::test]
+async fn daily_threads_start_with_a_leaderboard_image() {
+    configure("[leaderboard]\nimage = true");
+    let _guard = start(Utc.with_ymd_and_hms(2026, 3, 10, 0, 30, 0).unwrap()).await;
+    let mut server = Server::new(&["Alice", "Bob"]).await;
+    // Without points there is nothing to draw
+    let sent = server.platform.messages(server.thread()).remove(0);
+    assert!(sent.file.is_none());
+    assert!(sent.content.ends_with("No one has any points yet"));
+
+    server.set_score(server.users[0], 3);
+    clock().set(Utc.with_ymd_and_hms(2026, 3, 11, 0, 1, 0).unwrap());
+    rollover_guilds(&server.platform, &mut server.state)
+        .await
+        .unwrap();
+    let sent = server.platform.messages(server.thread()).remove(0);
+    assert!(sent.content.ends_with("The current leaderboard:"));
+    let (file_name, png) = sent.file.expect("The leaderboard is sent as an image");
+    assert_eq!(file_name, "leaderboard.png");
+    assert!(png.starts_with(b"\x89PNG"));
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::{clock, settle, start, Server, SUBMISSION};
+use serde_json::json;
+
+#[tokio::test]
+async fn february_has_29_days_in_a_leap_year() {
+    let _guard = start(Utc.with_ymd_and_hms(2028, 2, 28, 23, 30, 0).unwrap()).await;
+    let shared = Server::new(&["Alice", "Bob"]).await.into_shared();
+    let [alice, bob] = shared.users[..] else {
+        unreachable!()
+    };
+    // Alice hasn't missed a day this month, which only makes a perfect month if she does the 29th
+    shared
+        .edit_guild(|guild| {
+            guild["users"][alice.to_string()]["monthly_record"] = json!(28);
+            guild["users"][bob.to_string()]["monthly_record"] = json!(27);
+        })
+        .await;
+    let task = shared.schedule_daily_question();
+    settle(1).await;
+
+    clock().set(Utc.with_ymd_and_hms(2028, 2, 29, 0, 1, 0).unwrap());
+    settle(1).await;
+    assert_eq!(shared.guild().await["last_rollover"], "2028-02-29");
+    assert!(shared.thread_names().contains(&String::from("29/02/2028")));
+    let thread = shared.thread().await;
+    shared.send(alice, thread, SUBMISSION).await;
+    assert_eq!(shared.user(alice).await["monthly_record"], 29);
+
+    clock().set(Utc.with_ymd_and_hms(2028, 2, 29, 23, 1, 0).unwrap());
+    settle(1).await;
+    assert!(shared
+        .contents(thread)
+        .iter()
+        .any(|content| content.starts_with("An hour left")));
+
+    clock().set(Utc.with_ymd_and_hms(2028, 3, 1, 0, 1, 0).unwrap());
+    settle(1).await;
+    let guild = shared.guild().await;
+    assert_eq!(guild["last_rollover"], "2028-03-01");
+    assert_eq!(guild["history"][0]["month"], "2028-02");
+    assert_eq!(guild["history"][0]["badges"], json!([alice]));
+    let summary = shared.contents(shared.thread().await)[0].clone();
+    assert!(summary.contains("Welcome to a new month!"));
+    assert!(summary.contains("and another 10 points"));
+    // 5 for submitting with 23 hours left, 5 for the most questions and 10 for a perfect month
+    assert_eq!(shared.user(alice).await["score"], 20);
+    assert_eq!(shared.user(alice).await["monthly_record"], 0);
+    assert_eq!(shared.user(bob).await["days_missed"], 2);
+    task.abort();
+}
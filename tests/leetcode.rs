@@ -0,0 +1,73 @@
+use leetcode_daily::{
+    daily_question_embed, problem_embed, random_question_embed, set_config, Site,
+};
+use std::sync::Once;
+
+fn fixtures() {
+    static CONFIG: Once = Once::new();
+    CONFIG.call_once(|| {
+        set_config(toml::from_str("[leetcode]\nfixtures = \"assets/fixtures\"").unwrap()).unwrap();
+    });
+}
+
+#[tokio::test]
+async fn daily_question_links_the_site_it_came_from() {
+    fixtures();
+    let (content, embed) = daily_question_embed(Site::Com).await.unwrap();
+    assert_eq!(
+        content,
+        "Today's [LeetCode](https://leetcode.com/problemset) Daily question is out @everyone"
+    );
+    assert_eq!(
+        embed.url,
+        "https://leetcode.com/problems/trapping-rain-water/"
+    );
+    let (content, embed) = daily_question_embed(Site::Cn).await.unwrap();
+    assert!(content.contains("(https://leetcode.cn/problemset)"));
+    assert_eq!(embed.title, "42. 接雨水");
+}
+
+#[tokio::test]
+async fn random_questions_match_every_filter() {
+    fixtures();
+    for _ in 0..20 {
+        let (title, embed) = random_question_embed(Site::Com, &["hard", "free"])
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(["Median of Two Sorted Arrays", "Trapping Rain Water"].contains(&title.as_str()));
+        assert_eq!(
+            embed.fields[0],
+            (String::from("Difficulty"), String::from("Hard"))
+        );
+    }
+    assert!(random_question_embed(Site::Com, &["easy", "paid"])
+        .await
+        .unwrap()
+        .is_none());
+    // Unknown filters match nothing rather than being ignored
+    assert!(random_question_embed(Site::Com, &["tree"])
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn problems_are_found_by_number_or_slug() {
+    fixtures();
+    let by_number = problem_embed(Site::Com, "4").await.unwrap().unwrap();
+    assert_eq!(by_number.title, "4. Median of Two Sorted Arrays");
+    assert_eq!(
+        by_number.fields[1],
+        (String::from("Acceptance Rate"), String::from("41.50%"))
+    );
+    let by_slug = problem_embed(Site::Com, "median-of-two-sorted-arrays")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(by_slug.title, by_number.title);
+    // Acceptance rates from leetcode.cn are fractions
+    let cn = problem_embed(Site::Cn, "4").await.unwrap().unwrap();
+    assert_eq!(cn.fields[1], by_number.fields[1]);
+    assert!(problem_embed(Site::Com, "9999").await.unwrap().is_none());
+}
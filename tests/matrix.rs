@@ -0,0 +1,323 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post, put},
+    Json, Router,
+};
+use leetcode_daily::{run_matrix, set_config, Matrix, SharedState, State as Shared};
+use serde_json::{json, Value};
+use serenity::prelude::{RwLock, TypeMap};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{net::TcpListener, spawn, sync::Notify, time::timeout};
+
+const ROOM: &str = "!room:example.org";
+const POLL_OPTIONS: &str = "leetcode_daily.poll_options";
+
+struct Sent {
+    room: String,
+    event_id: String,
+    content: Value,
+}
+
+// Serves the client API for a bot in one room with two members, syncs return whatever the test
+// queued since the last one
+#[derive(Default)]
+struct Homeserver {
+    sent: Mutex<Vec<Sent>>,
+    pinned: Mutex<Vec<String>>,
+    syncs: Mutex<VecDeque<Value>>,
+    queued: Notify,
+}
+
+type Server = State<Arc<Homeserver>>;
+
+async fn whoami() -> Json<Value> {
+    Json(json!({"user_id": "@bot:example.org"}))
+}
+
+async fn sync(State(server): Server, Query(query): Query<HashMap<String, String>>) -> Json<Value> {
+    if !query.contains_key("since") {
+        return Json(json!({"next_batch": "0"}));
+    }
+    let _ = timeout(Duration::from_millis(100), server.queued.notified()).await;
+    let rooms = server.syncs.lock().unwrap().pop_front().unwrap_or_default();
+    Json(json!({"next_batch": "1", "rooms": rooms}))
+}
+
+async fn joined_rooms() -> Json<Value> {
+    Json(json!({"joined_rooms": [ROOM]}))
+}
+
+async fn joined_members() -> Json<Value> {
+    Json(json!({"joined": {
+        "@bot:example.org": {"display_name": "LeetCode Daily"},
+        "@alice:example.org": {"display_name": "Alice"},
+        "@bob:example.org": {},
+    }}))
+}
+
+async fn send(
+    State(server): Server,
+    Path((room, _, _)): Path<(String, String, String)>,
+    Json(content): Json<Value>,
+) -> Json<Value> {
+    let mut sent = server.sent.lock().unwrap();
+    let event_id = format!("$event{}", sent.len());
+    sent.push(Sent {
+        room,
+        event_id: event_id.clone(),
+        content,
+    });
+    Json(json!({ "event_id": event_id }))
+}
+
+// Bundles the latest edit the way newer servers do
+async fn event(
+    State(server): Server,
+    Path((_, event_id)): Path<(String, String)>,
+) -> (StatusCode, Json<Value>) {
+    let sent = server.sent.lock().unwrap();
+    let Some(original) = sent.iter().find(|sent| sent.event_id == event_id) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"errcode": "M_NOT_FOUND"})),
+        );
+    };
+    let mut event = json!({"event_id": event_id, "content": original.content});
+    if let Some(edit) = sent.iter().rev().find(|sent| {
+        sent.content["m.relates_to"]["rel_type"] == "m.replace"
+            && sent.content["m.relates_to"]["event_id"] == event_id
+    }) {
+        event["unsigned"] = json!({"m.relations": {"m.replace": {
+            "event_id": edit.event_id,
+            "content": edit.content,
+        }}});
+    }
+    (StatusCode::OK, Json(event))
+}
+
+async fn pinned_events(State(server): Server) -> Json<Value> {
+    Json(json!({"pinned": *server.pinned.lock().unwrap()}))
+}
+
+async fn pin(State(server): Server, Json(content): Json<Value>) -> Json<Value> {
+    *server.pinned.lock().unwrap() = serde_json::from_value(content["pinned"].clone()).unwrap();
+    Json(json!({"event_id": "$pinned"}))
+}
+
+async fn join(Path(room): Path<String>) -> Json<Value> {
+    Json(json!({ "room_id": room }))
+}
+
+async fn unrecognised() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({"errcode": "M_UNRECOGNIZED"})),
+    )
+}
+
+struct MockMatrix {
+    server: Arc<Homeserver>,
+    events: usize,
+}
+
+impl MockMatrix {
+    // Starts the homeserver and lets the bot sync with it until it has set up the room
+    async fn start() -> Self {
+        let server = Arc::new(Homeserver::default());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let homeserver = format!("http://{}", listener.local_addr().unwrap());
+        let router = Router::new()
+            .route("/_matrix/client/v3/account/whoami", get(whoami))
+            .route("/_matrix/client/v3/sync", get(sync))
+            .route("/_matrix/client/v3/joined_rooms", get(joined_rooms))
+            .route(
+                "/_matrix/client/v3/rooms/:room/joined_members",
+                get(joined_members),
+            )
+            .route("/_matrix/client/v3/rooms/:room/send/:kind/:txn", put(send))
+            .route("/_matrix/client/v3/rooms/:room/event/:event", get(event))
+            .route(
+                "/_matrix/client/v3/rooms/:room/state/m.room.pinned_events",
+                get(pinned_events).put(pin),
+            )
+            .route("/_matrix/client/v3/rooms/:room/join", post(join))
+            .fallback(unrecognised)
+            .with_state(server.clone());
+        spawn(async move { axum::serve(listener, router).await });
+        let ids = tempfile::tempdir()
+            .unwrap()
+            .into_path()
+            .join("matrix_ids.json");
+        set_config(
+            toml::from_str(&format!(
+                r#"
+                admins = ["@alice:example.org"]
+
+                [leetcode]
+                fixtures = "assets/fixtures"
+
+                [leaderboard]
+                image = false
+
+                [matrix]
+                ids = "{}"
+                "#,
+                ids.display()
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        let platform = Arc::new(Matrix::new(&homeserver, "syt_test").await.unwrap());
+        let mut data = TypeMap::new();
+        data.insert::<Shared>(SharedState {
+            ready: false,
+            shutting_down: false,
+            guilds: HashMap::new(),
+            file: tempfile::tempfile().unwrap(),
+            database: HashMap::new(),
+        });
+        let data = Arc::new(RwLock::new(data));
+        spawn(async move {
+            run_matrix(platform, data)
+                .await
+                .map_err(|why| why.to_string())
+        });
+        let matrix = Self { server, events: 0 };
+        // Joining a room ends with a random question
+        matrix.wait_for(ROOM, "random").await;
+        matrix
+    }
+
+    // The latest message sent to a room with its body containing the text
+    async fn wait_for(&self, room: &str, text: &str) -> (String, Value) {
+        timeout(Duration::from_secs(10), async {
+            loop {
+                if let Some(sent) = self.server.sent.lock().unwrap().iter().rev().find(|sent| {
+                    sent.room == room && sent.content["body"].as_str().unwrap().contains(text)
+                }) {
+                    return (sent.event_id.clone(), sent.content.clone());
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| {
+            let sent = self.server.sent.lock().unwrap();
+            let bodies = sent
+                .iter()
+                .map(|sent| &sent.content["body"])
+                .collect::<Vec<_>>();
+            panic!("Nothing containing {text:?} was sent to {room}: {bodies:#?}")
+        })
+    }
+
+    fn queue(&self, rooms: Value) {
+        self.server.syncs.lock().unwrap().push_back(rooms);
+        self.server.queued.notify_one();
+    }
+
+    fn deliver(&mut self, kind: &str, sender: &str, content: Value) -> String {
+        self.events += 1;
+        let event_id = format!("$incoming{}", self.events);
+        self.queue(json!({"join": {ROOM: {"timeline": {"events": [{
+            "type": kind,
+            "sender": sender,
+            "event_id": event_id,
+            "content": content,
+        }]}}}}));
+        event_id
+    }
+
+    fn say_in_thread(&mut self, sender: &str, thread: &str, body: &str) -> String {
+        let content = json!({
+            "msgtype": "m.text",
+            "body": body,
+            "m.relates_to": {"rel_type": "m.thread", "event_id": thread},
+        });
+        self.deliver("m.room.message", sender, content)
+    }
+}
+
+// The config is set once per process, so the whole conversation runs against one homeserver
+#[tokio::test(flavor = "multi_thread")]
+async fn matrix_bot_syncs_sends_and_counts_reactions() {
+    let mut matrix = MockMatrix::start().await;
+
+    // The first sync sets up the joined room with the help, the daily question and its
+    // leaderboard in a thread under the question
+    let (_, help) = matrix.wait_for(ROOM, "Hi I'm LeetCode Daily").await;
+    assert!(help.get("m.relates_to").is_none());
+    assert!(help["formatted_body"]
+        .as_str()
+        .unwrap()
+        .contains("<a href="));
+    let (question, _) = matrix.wait_for(ROOM, "Daily question is out").await;
+    let (_, leaderboard) = matrix.wait_for(ROOM, "The current leaderboard").await;
+    assert_eq!(leaderboard["m.relates_to"]["rel_type"], "m.thread");
+    assert_eq!(leaderboard["m.relates_to"]["event_id"], question);
+
+    // Commands sent in the thread, here with the ! clients leave alone, are answered there
+    matrix.say_in_thread("@bob:example.org", &question, "!active");
+    let (_, active) = matrix.wait_for(ROOM, "is active for both").await;
+    assert_eq!(active["m.relates_to"]["rel_type"], "m.thread");
+    assert_eq!(active["m.relates_to"]["event_id"], question);
+    assert_eq!(
+        active["m.mentions"]["user_ids"],
+        json!(["@bot:example.org"])
+    );
+
+    // Submissions start a poll numbering each of them, which is pinned
+    let submission = "```rust\nfn main() {}\n```";
+    matrix.say_in_thread("@alice:example.org", &question, submission);
+    matrix
+        .wait_for(ROOM, "Congrats to @alice:example.org")
+        .await;
+    matrix.say_in_thread("@bob:example.org", &question, submission);
+    let (poll, options) = timeout(Duration::from_secs(10), async {
+        loop {
+            if let Some(edit) = matrix.server.sent.lock().unwrap().iter().find(|sent| {
+                sent.content["m.new_content"][POLL_OPTIONS]
+                    .as_array()
+                    .is_some_and(|options| options.len() == 2)
+            }) {
+                let poll = edit.content["m.relates_to"]["event_id"].as_str().unwrap();
+                let body = edit.content["m.new_content"]["body"].as_str().unwrap();
+                return (poll.to_string(), body.to_string());
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("The second submission was not added to the poll");
+    assert_eq!(*matrix.server.pinned.lock().unwrap(), [poll.as_str()]);
+
+    // Reactions vote for the submission with that number in the latest edit of the poll
+    let vote = |user: &str| {
+        let key = options
+            .lines()
+            .find_map(|line| Some(line.split_once(&format!(" {user}"))?.0))
+            .unwrap();
+        json!({"m.relates_to": {"rel_type": "m.annotation", "event_id": poll, "key": key}})
+    };
+    matrix.deliver("m.reaction", "@bob:example.org", vote("@bob:example.org"));
+    let (_, denied) = matrix.wait_for(ROOM, "Cannot vote for yourself").await;
+    assert_eq!(denied["m.relates_to"]["event_id"], question);
+    let reaction = matrix.deliver("m.reaction", "@bob:example.org", vote("@alice:example.org"));
+    let (_, voted) = matrix
+        .wait_for(ROOM, "Successfully voted for @alice:example.org")
+        .await;
+    assert_eq!(voted["m.relates_to"]["rel_type"], "m.thread");
+    assert_eq!(voted["m.relates_to"]["event_id"], question);
+    assert_eq!(voted["m.relates_to"]["m.in_reply_to"]["event_id"], reaction);
+
+    // Invites are joined and set up like the first room
+    matrix.queue(json!({"invite": {"!other:example.org": {}}}));
+    matrix
+        .wait_for("!other:example.org", "Daily question is out")
+        .await;
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::{clock, settle, start, Server, SUBMISSION};
+use serde_json::json;
+
+#[tokio::test]
+async fn december_rolls_over_into_january() {
+    let _guard = start(Utc.with_ymd_and_hms(2026, 12, 31, 0, 30, 0).unwrap()).await;
+    let shared = Server::new(&["Alice", "Bob"]).await.into_shared();
+    let [alice, bob] = shared.users[..] else {
+        unreachable!()
+    };
+    shared
+        .edit_guild(|guild| {
+            guild["users"][alice.to_string()]["monthly_record"] = json!(20);
+            guild["users"][bob.to_string()]["monthly_record"] = json!(19);
+        })
+        .await;
+    let thread = shared.thread().await;
+    shared.send(alice, thread, SUBMISSION).await;
+    let task = shared.schedule_daily_question();
+    settle(1).await;
+
+    clock().set(Utc.with_ymd_and_hms(2026, 12, 31, 23, 1, 0).unwrap());
+    settle(1).await;
+    clock().set(Utc.with_ymd_and_hms(2027, 1, 1, 0, 1, 0).unwrap());
+    settle(1).await;
+    let guild = shared.guild().await;
+    assert_eq!(guild["last_rollover"], "2027-01-01");
+    assert_eq!(guild["history"][0]["month"], "2026-12");
+    assert_eq!(guild["history"][0]["standings"][0]["user_id"], json!(alice));
+    assert!(shared.thread_names().contains(&String::from("01/01/2027")));
+    let summary = shared.contents(shared.thread().await)[0].clone();
+    assert!(summary.contains(&format!(
+        "Welcome to a new month! Last month <@{alice}> completed **21** questions"
+    )));
+    for user_id in [alice, bob] {
+        assert_eq!(shared.user(user_id).await["monthly_record"], 0);
+    }
+
+    // The next day is an ordinary rollover in the new year
+    clock().set(Utc.with_ymd_and_hms(2027, 1, 1, 23, 1, 0).unwrap());
+    settle(1).await;
+    clock().set(Utc.with_ymd_and_hms(2027, 1, 2, 0, 1, 0).unwrap());
+    settle(1).await;
+    assert_eq!(shared.guild().await["last_rollover"], "2027-01-02");
+    assert_eq!(shared.guild().await["history"].as_array().unwrap().len(), 1);
+    task.abort();
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::{clock, configure, settle, start, Server};
+use serde_json::json;
+
+// Seasons are quarters, so the first rollover of April ends the one that started in January
+fn quarters() {
+    configure("[schedule]\nseason_months = 3");
+}
+
+#[tokio::test]
+async fn season_scores_are_archived_and_reset() {
+    quarters();
+    let _guard = start(Utc.with_ymd_and_hms(2027, 3, 31, 12, 0, 0).unwrap()).await;
+    let shared = Server::new(&["Alice", "Bob", "Carol"]).await.into_shared();
+    let [alice, bob, carol] = shared.users[..] else {
+        unreachable!()
+    };
+    // Everyone submitted so the rollover takes no points away before the season ends
+    shared
+        .edit_guild(|guild| {
+            for (user_id, score, archived_score) in [(alice, 10, 5), (bob, 10, 0), (carol, 2, 0)] {
+                let user = &mut guild["users"][user_id.to_string()];
+                user["score"] = json!(score);
+                user["archived_score"] = json!(archived_score);
+                user["submitted"] = json!("https://example.com");
+            }
+        })
+        .await;
+    let task = shared.schedule_daily_question();
+    settle(1).await;
+
+    clock().set(Utc.with_ymd_and_hms(2027, 3, 31, 23, 1, 0).unwrap());
+    settle(1).await;
+    clock().set(Utc.with_ymd_and_hms(2027, 4, 1, 0, 1, 0).unwrap());
+    settle(1).await;
+    let guild = shared.guild().await;
+    assert_eq!(guild["last_rollover"], "2027-04-01");
+    let season = &guild["seasons"][0];
+    assert_eq!(season["start"], "2027-01");
+    assert_eq!(season["end"], "2027-03");
+    assert_eq!(season["champions"], json!([alice, bob]));
+    assert_eq!(
+        season["standings"][2],
+        json!({"user_id": carol, "score": 2})
+    );
+    for (user_id, archived_score) in [(alice, 15), (bob, 10), (carol, 2)] {
+        let user = shared.user(user_id).await;
+        assert_eq!(user["score"], 0);
+        assert_eq!(user["archived_score"], archived_score);
+    }
+    let summary = shared.contents(shared.thread().await)[0].clone();
+    assert!(summary.contains(&format!(
+        "The season of January 2027 to March 2027 is over! Congratulations to <@{alice}>, <@{bob}> for winning it with **10** points"
+    )));
+
+    // The month after is not the start of a season
+    clock().set(Utc.with_ymd_and_hms(2027, 4, 30, 23, 1, 0).unwrap());
+    settle(1).await;
+    clock().set(Utc.with_ymd_and_hms(2027, 5, 1, 0, 1, 0).unwrap());
+    settle(1).await;
+    assert_eq!(shared.guild().await["last_rollover"], "2027-05-01");
+    assert_eq!(shared.guild().await["seasons"].as_array().unwrap().len(), 1);
+    task.abort();
+}
+
+#[tokio::test]
+async fn seasons_without_points_have_no_champion() {
+    quarters();
+    let _guard = start(Utc.with_ymd_and_hms(2027, 6, 30, 12, 0, 0).unwrap()).await;
+    let shared = Server::new(&["Alice"]).await.into_shared();
+    let task = shared.schedule_daily_question();
+    settle(1).await;
+
+    clock().set(Utc.with_ymd_and_hms(2027, 6, 30, 23, 1, 0).unwrap());
+    settle(1).await;
+    clock().set(Utc.with_ymd_and_hms(2027, 7, 1, 0, 1, 0).unwrap());
+    settle(1).await;
+    let season = shared.guild().await["seasons"][0].clone();
+    assert_eq!(season["start"], "2027-04");
+    assert_eq!(season["champions"], json!([]));
+    let summary = shared.contents(shared.thread().await)[0].clone();
+    assert!(summary.contains("No one scored any points this season"));
+    task.abort();
+}
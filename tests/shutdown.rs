@@ -0,0 +1,96 @@
+use leetcode_daily::{get_shared_state, set_score, shutdown, Database, SharedState, State};
+use serenity::{
+    model::prelude::{GuildId, UserId},
+    prelude::{RwLock, TypeMap},
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::read_to_string,
+    io::{Seek, SeekFrom, Write},
+    path::Path,
+    sync::Arc,
+};
+use tokio::{spawn, task::yield_now};
+
+async fn shared_state(data: &RwLock<TypeMap>) -> Result<(), Box<dyn Error>> {
+    let mut data = data.write().await;
+    let _state = get_shared_state!(data);
+    Ok(())
+}
+
+fn shared_data(path: &Path) -> Arc<RwLock<TypeMap>> {
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .unwrap();
+    // Stale contents that the flush has to replace entirely
+    file.write_all(br#"{"1": "stale", "padding": "to be truncated"}"#)
+        .unwrap();
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let database: Database = serde_json::from_str(
+        r#"{"500": {"users": {"7": {"voted_for": null, "submitted": null, "weekly_submissions": 0, "monthly_record": 3, "days_missed": 0, "score": 12}}, "channel_id": null, "thread_id": null, "weekly_id": null, "poll_id": null, "active_weekly": true, "active_daily": true}}"#,
+    )
+    .unwrap();
+    let mut data = TypeMap::new();
+    data.insert::<State>(SharedState {
+        ready: true,
+        shutting_down: false,
+        guilds: HashMap::new(),
+        file,
+        database,
+    });
+    Arc::new(RwLock::new(data))
+}
+
+#[tokio::test]
+async fn shutdown_flushes_the_database_and_stops_handlers() {
+    let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+    let data = shared_data(&path);
+    assert!(shared_state(&data).await.is_ok());
+
+    shutdown(data.clone(), None).await.unwrap();
+
+    let flushed: serde_json::Value = serde_json::from_str(&read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(flushed["500"]["users"]["7"]["score"], 12);
+    assert_eq!(flushed["500"]["users"]["7"]["monthly_record"], 3);
+    assert!(flushed.get("1").is_none());
+    assert_eq!(
+        shared_state(&data).await.unwrap_err().to_string(),
+        "Shutting down"
+    );
+}
+
+#[tokio::test]
+async fn shutdown_waits_for_running_handlers() {
+    let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+    let data = shared_data(&path);
+    // A handler in the middle of updating a score holds the lock when the signal arrives
+    let mut handler = data.write().await;
+    let shutting_down = spawn({
+        let data = data.clone();
+        async move { shutdown(data, None).await.map_err(|why| why.to_string()) }
+    });
+    for _ in 0..10 {
+        yield_now().await;
+    }
+    assert!(!shutting_down.is_finished());
+    assert!(read_to_string(&path).unwrap().contains("stale"));
+
+    let state = handler.get_mut::<State>().unwrap();
+    set_score(&mut state.database, GuildId::new(500), UserId::new(7), 20).unwrap();
+    drop(handler);
+    shutting_down.await.unwrap().unwrap();
+
+    let flushed: serde_json::Value = serde_json::from_str(&read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(flushed["500"]["users"]["7"]["score"], 20);
+}
+
+#[tokio::test]
+async fn shutdown_without_a_database_succeeds() {
+    // Webhook mode never loads the database
+    let data = Arc::new(RwLock::new(TypeMap::new()));
+    shutdown(data.clone(), None).await.unwrap();
+    assert!(data.read().await.get::<State>().is_none());
+}
@@ -0,0 +1,240 @@
+use axum::{
+    extract::{Path, State},
+    routing::post,
+    Form, Json, Router,
+};
+use futures_util::{SinkExt, StreamExt};
+use leetcode_daily::{run_slack, set_config, SharedState, Slack, State as Shared};
+use serde_json::{json, Value};
+use serenity::prelude::{RwLock, TypeMap};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{net::TcpListener, spawn, time::timeout};
+use tokio_tungstenite::{accept_async, tungstenite::Message as Frame, WebSocketStream};
+
+type Calls = Arc<Mutex<Vec<(String, HashMap<String, String>)>>>;
+
+// Answers the Web API the way Slack does for a workspace with one channel and two members
+async fn api(
+    State((calls, socket)): State<(Calls, String)>,
+    Path(method): Path<String>,
+    Form(params): Form<HashMap<String, String>>,
+) -> Json<Value> {
+    let mut response = {
+        let mut calls = calls.lock().unwrap();
+        calls.push((method.clone(), params.clone()));
+        match method.as_str() {
+            "auth.test" => json!({"user_id": "UBOT", "team_id": "T1"}),
+            "apps.connections.open" => json!({"url": socket}),
+            "conversations.list" => {
+                json!({"channels": [{"id": "C1", "is_channel": true, "is_member": true}]})
+            }
+            "conversations.info" => json!({"channel": {"id": "C1", "is_channel": true}}),
+            "users.list" => json!({"members": [
+                {"id": "UBOT", "name": "bot", "is_bot": true},
+                {"id": "UALICE", "name": "alice"},
+                {"id": "UBOB", "name": "bob"},
+            ]}),
+            "chat.postMessage" => {
+                json!({"channel": params["channel"], "ts": format!("1700000000.{:06}", calls.len())})
+            }
+            "chat.getPermalink" => json!({"permalink": "https://example.slack.com/archives/C1"}),
+            "conversations.replies" => json!({"messages": []}),
+            _ => json!({}),
+        }
+    };
+    response["ok"] = json!(true);
+    Json(response)
+}
+
+struct MockSlack {
+    calls: Calls,
+    socket: WebSocketStream<tokio::net::TcpStream>,
+}
+
+impl MockSlack {
+    // Starts the Web API and the Socket Mode endpoint, then connects the bot to both
+    async fn start() -> Self {
+        let calls = Calls::default();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let sockets = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let socket_url = format!("ws://{}", sockets.local_addr().unwrap());
+        let api_url = format!("http://{}/api", listener.local_addr().unwrap());
+        let router = Router::new()
+            .route("/api/:method", post(api))
+            .with_state((calls.clone(), socket_url));
+        spawn(async move { axum::serve(listener, router).await });
+        let ids = tempfile::tempdir()
+            .unwrap()
+            .into_path()
+            .join("slack_ids.json");
+        set_config(
+            toml::from_str(&format!(
+                r#"
+                admins = ["UALICE"]
+
+                [leetcode]
+                fixtures = "assets/fixtures"
+
+                [leaderboard]
+                image = false
+
+                [slack]
+                api_url = "{api_url}"
+                ids = "{}"
+                "#,
+                ids.display()
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        let platform = Arc::new(Slack::new("xapp-test", "xoxb-test").await.unwrap());
+        let mut data = TypeMap::new();
+        data.insert::<Shared>(SharedState {
+            ready: false,
+            shutting_down: false,
+            guilds: HashMap::new(),
+            file: tempfile::tempfile().unwrap(),
+            database: HashMap::new(),
+        });
+        let data = Arc::new(RwLock::new(data));
+        spawn(async move {
+            run_slack(platform, data)
+                .await
+                .map_err(|why| why.to_string())
+        });
+        let (stream, _) = sockets.accept().await.unwrap();
+        let mut socket = accept_async(stream).await.unwrap();
+        socket
+            .send(Frame::Text(json!({"type": "hello"}).to_string()))
+            .await
+            .unwrap();
+        let mut slack = Self { calls, socket };
+        // Joining the workspace ends with a random question
+        slack.wait_for_post("random").await;
+        slack
+    }
+
+    fn posts(&self) -> Vec<HashMap<String, String>> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(method, _)| method == "chat.postMessage")
+            .map(|(_, params)| params.clone())
+            .collect()
+    }
+
+    async fn wait_for_post(&mut self, text: &str) -> HashMap<String, String> {
+        timeout(Duration::from_secs(10), async {
+            loop {
+                if let Some(post) = self
+                    .posts()
+                    .into_iter()
+                    .rev()
+                    .find(|post| post["text"].contains(text))
+                {
+                    return post;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| {
+            panic!(
+                "Nothing containing {text:?} was posted: {:#?}",
+                self.posts()
+            )
+        })
+    }
+
+    // Delivers an envelope and checks the bot acknowledges it
+    async fn deliver(&mut self, envelope_id: &str, kind: &str, payload: Value) {
+        self.socket
+            .send(Frame::Text(
+                json!({"type": kind, "envelope_id": envelope_id, "payload": payload}).to_string(),
+            ))
+            .await
+            .unwrap();
+        let ack = timeout(Duration::from_secs(10), self.socket.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let ack = serde_json::from_str::<Value>(ack.to_text().unwrap()).unwrap();
+        assert_eq!(ack, json!({"envelope_id": envelope_id}));
+    }
+
+    async fn slash_command(&mut self, envelope_id: &str, user: &str, command: &str, text: &str) {
+        let payload = json!({
+            "team_id": "T1",
+            "channel_id": "C1",
+            "user_id": user,
+            "command": command,
+            "text": text,
+        });
+        self.deliver(envelope_id, "slash_commands", payload).await;
+    }
+}
+
+// The config is set once per process, so the whole conversation runs against one mock
+#[tokio::test(flavor = "multi_thread")]
+async fn slack_bot_talks_to_the_web_api() {
+    let mut slack = MockSlack::start().await;
+    let calls = slack.calls.lock().unwrap().clone();
+    assert_eq!(calls[0].0, "auth.test");
+    assert!(calls.iter().any(|(method, _)| method == "users.list"));
+
+    // Joining posts the help, the daily question in the first channel and its leaderboard in a
+    // thread under the question
+    let posts = slack.posts();
+    assert!(posts.iter().all(|post| post["channel"] == "C1"));
+    let help = slack.wait_for_post("Hi I'm LeetCode Daily").await;
+    assert!(!help.contains_key("thread_ts"));
+    let question = slack.wait_for_post("Daily question is out").await;
+    assert!(question.contains_key("attachments"));
+    let question_ts = format!(
+        "1700000000.{:06}",
+        slack
+            .calls
+            .lock()
+            .unwrap()
+            .iter()
+            .position(|(_, params)| *params == question)
+            .unwrap()
+            + 1
+    );
+    let leaderboard = slack.wait_for_post("The current leaderboard").await;
+    assert_eq!(leaderboard["thread_ts"], question_ts);
+
+    // Messages in the thread are answered in the thread
+    let message = json!({
+        "team_id": "T1",
+        "event": {
+            "type": "message",
+            "channel": "C1",
+            "user": "UBOB",
+            "text": "/active",
+            "ts": "1700000001.000001",
+            "thread_ts": question_ts,
+        },
+    });
+    slack.deliver("message", "events_api", message).await;
+    let reply = slack.wait_for_post("is active for both").await;
+    assert_eq!(reply["thread_ts"], question_ts);
+    assert_eq!(reply["text"], "<@UBOT> is active for both weekly and daily");
+
+    // Slash commands are answered in the channel they were run in, admins are Slack user IDs
+    slack
+        .slash_command("toggle", "UALICE", "/active", "weekly toggle")
+        .await;
+    let toggled = slack.wait_for_post("is now paused for weekly").await;
+    assert!(!toggled.contains_key("thread_ts"));
+    slack.slash_command("denied", "UBOB", "/daily", "").await;
+    let denied = slack.wait_for_post("Only admins").await;
+    assert_eq!(denied["channel"], "C1");
+    assert!(!denied.contains_key("thread_ts"));
+}
@@ -0,0 +1,340 @@
+use axum::{
+    extract::{Path, State},
+    routing::post,
+    Json, Router,
+};
+use leetcode_daily::{run_telegram, set_config, SharedState, State as Shared, Telegram};
+use serde_json::{json, Value};
+use serenity::prelude::{RwLock, TypeMap};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{net::TcpListener, spawn, sync::Notify, time::timeout};
+
+const CHAT: i64 = -1001234;
+const BOT: i64 = 1;
+const ALICE: i64 = 10;
+const BOB: i64 = 11;
+const CAROL: i64 = 12;
+
+struct Call {
+    method: String,
+    params: Value,
+    message_id: i64,
+}
+
+// Serves the Bot API for one supergroup where only Alice is an admin, updates return whatever
+// the test queued since the last poll
+#[derive(Default)]
+struct BotApi {
+    calls: Mutex<Vec<Call>>,
+    updates: Mutex<VecDeque<Value>>,
+    queued: Notify,
+}
+
+type Api = State<Arc<BotApi>>;
+
+fn account(id: i64) -> Value {
+    match id {
+        BOT => {
+            json!({"id": BOT, "is_bot": true, "first_name": "LeetCode Daily", "username": "leetcode_daily_bot"})
+        }
+        ALICE => json!({"id": ALICE, "first_name": "Alice"}),
+        BOB => json!({"id": BOB, "first_name": "Bob"}),
+        _ => json!({"id": id, "first_name": "Carol"}),
+    }
+}
+
+fn chat() -> Value {
+    json!({"id": CHAT, "type": "supergroup"})
+}
+
+async fn call(
+    State(api): Api,
+    Path((_, method)): Path<(String, String)>,
+    Json(params): Json<Value>,
+) -> Json<Value> {
+    let result = match method.as_str() {
+        "getMe" => account(BOT),
+        "getUpdates" => {
+            if !params["offset"].is_null() {
+                let _ = timeout(Duration::from_millis(100), api.queued.notified()).await;
+            }
+            return Json(
+                json!({"ok": true, "result": api.updates.lock().unwrap().drain(..).collect::<Vec<_>>()}),
+            );
+        }
+        "getChatAdministrators" => json!([
+            {"status": "creator", "user": account(ALICE)},
+            {"status": "administrator", "user": account(BOT)},
+        ]),
+        "getChat" => chat(),
+        "sendMessage" => json!({"chat": chat()}),
+        _ => json!(true),
+    };
+    let mut calls = api.calls.lock().unwrap();
+    let message_id = 1000 + calls.len() as i64;
+    calls.push(Call {
+        method,
+        params,
+        message_id,
+    });
+    let mut result = result;
+    if result.get("chat").is_some() {
+        result["message_id"] = json!(message_id);
+    }
+    Json(json!({"ok": true, "result": result}))
+}
+
+struct MockTelegram {
+    api: Arc<BotApi>,
+    updates: i64,
+}
+
+impl MockTelegram {
+    // Starts the Bot API with the bot just added to the group and lets it set up the chat
+    async fn start() -> Self {
+        let api = Arc::new(BotApi::default());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_url = format!("http://{}", listener.local_addr().unwrap());
+        let router = Router::new()
+            .route("/:bot/:method", post(call))
+            .with_state(api.clone());
+        spawn(async move { axum::serve(listener, router).await });
+        let ids = tempfile::tempdir()
+            .unwrap()
+            .into_path()
+            .join("telegram_ids.json");
+        set_config(
+            toml::from_str(&format!(
+                r#"
+                [leetcode]
+                fixtures = "assets/fixtures"
+
+                [leaderboard]
+                image = false
+
+                [telegram]
+                api_url = "{api_url}"
+                ids = "{}"
+                "#,
+                ids.display()
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        let mut telegram = Self { api, updates: 0 };
+        telegram.queue(json!({"my_chat_member": {
+            "chat": chat(),
+            "old_chat_member": {"status": "left", "user": account(BOT)},
+            "new_chat_member": {"status": "member", "user": account(BOT)},
+        }}));
+        let platform = Arc::new(Telegram::new("test").await.unwrap());
+        let mut data = TypeMap::new();
+        data.insert::<Shared>(SharedState {
+            ready: false,
+            shutting_down: false,
+            guilds: HashMap::new(),
+            file: tempfile::tempfile().unwrap(),
+            database: HashMap::new(),
+        });
+        let data = Arc::new(RwLock::new(data));
+        spawn(async move {
+            run_telegram(platform, data)
+                .await
+                .map_err(|why| why.to_string())
+        });
+        // Joining a chat ends with a random question
+        telegram.wait_for("sendMessage", "random").await;
+        telegram
+    }
+
+    // The latest call of the method with its text containing the text
+    async fn wait_for(&self, method: &str, text: &str) -> (i64, Value) {
+        timeout(Duration::from_secs(10), async {
+            loop {
+                if let Some(call) = self.api.calls.lock().unwrap().iter().rev().find(|call| {
+                    call.method == method
+                        && call.params["text"]
+                            .as_str()
+                            .is_some_and(|body| body.contains(text))
+                }) {
+                    return (call.message_id, call.params.clone());
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| {
+            let calls = self.api.calls.lock().unwrap();
+            let texts = calls
+                .iter()
+                .map(|call| (&call.method, &call.params["text"]))
+                .collect::<Vec<_>>();
+            panic!("No {method} containing {text:?}: {texts:#?}")
+        })
+    }
+
+    fn queue(&mut self, mut update: Value) {
+        self.updates += 1;
+        update["update_id"] = json!(self.updates);
+        self.api.updates.lock().unwrap().push_back(update);
+        self.api.queued.notify_one();
+    }
+
+    // Sends a message replying to another, the Bot API only includes the message replied to and
+    // not what that one replied to
+    fn reply(&mut self, from: i64, to: (i64, i64), text: &str, entities: Value) -> i64 {
+        let message_id = 2000 + self.updates;
+        self.queue(json!({"message": {
+            "message_id": message_id,
+            "chat": chat(),
+            "from": account(from),
+            "text": text,
+            "entities": entities,
+            "reply_to_message": {"message_id": to.1, "chat": chat(), "from": account(to.0)},
+        }}));
+        message_id
+    }
+
+    fn submit(&mut self, from: i64, to: (i64, i64)) -> i64 {
+        let entities = json!([
+            {"type": "spoiler", "offset": 0, "length": 12},
+            {"type": "pre", "offset": 0, "length": 12, "language": "rust"},
+        ]);
+        self.reply(from, to, "fn main() {}", entities)
+    }
+
+    fn vote(&mut self, from: i64, poll: i64, data: &Value) {
+        self.queue(json!({"callback_query": {
+            "id": format!("query{}", self.updates),
+            "from": account(from),
+            "message": {"message_id": poll, "chat": chat()},
+            "data": data,
+        }}));
+    }
+
+    async fn answer(&self, text: &str) {
+        timeout(Duration::from_secs(10), async {
+            while !self
+                .api
+                .calls
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|call| call.method == "answerCallbackQuery" && call.params["text"] == text)
+            {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("The vote was not answered with {text:?}"));
+    }
+}
+
+// The config is set once per process, so the whole conversation runs against one Bot API
+#[tokio::test(flavor = "multi_thread")]
+async fn telegram_bot_replies_in_threads_and_counts_votes() {
+    let mut telegram = MockTelegram::start().await;
+
+    // Joining sends the help and the daily question, with the leaderboard replying to it
+    let (_, help) = telegram
+        .wait_for("sendMessage", "Hi I'm LeetCode Daily")
+        .await;
+    assert!(help.get("reply_parameters").is_none());
+    assert_eq!(help["parse_mode"], "HTML");
+    let (question, _) = telegram
+        .wait_for("sendMessage", "Daily question is out")
+        .await;
+    let (_, leaderboard) = telegram
+        .wait_for("sendMessage", "The current leaderboard")
+        .await;
+    assert_eq!(leaderboard["reply_parameters"]["message_id"], question);
+
+    // A spoilered code block replying to the question is a submission and starts the poll
+    let alice = telegram.submit(ALICE, (BOT, question));
+    let (_, congrats) = telegram
+        .wait_for("sendMessage", "Alice</a> for completing")
+        .await;
+    assert_eq!(congrats["reply_parameters"]["message_id"], question);
+    let (poll, _) = timeout(Duration::from_secs(10), async {
+        loop {
+            if let Some(call) = telegram.api.calls.lock().unwrap().iter().find(|call| {
+                call.method == "sendMessage" && call.params.get("reply_markup").is_some()
+            }) {
+                return (call.message_id, call.params.clone());
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("The first submission did not start the poll");
+
+    // Replies to the poll or to other submissions are still in the question's thread
+    telegram.submit(BOB, (BOT, poll));
+    let (_, congrats) = telegram
+        .wait_for("sendMessage", "Bob</a> for completing")
+        .await;
+    assert_eq!(congrats["reply_parameters"]["message_id"], question);
+    telegram.submit(CAROL, (ALICE, alice));
+    telegram
+        .wait_for("sendMessage", "Carol</a> for completing")
+        .await;
+
+    // Every submission gets a button on the poll
+    let buttons = timeout(Duration::from_secs(10), async {
+        loop {
+            if let Some(call) = telegram
+                .api
+                .calls
+                .lock()
+                .unwrap()
+                .iter()
+                .rev()
+                .find(|call| {
+                    call.method == "editMessageText"
+                        && call.params["message_id"] == poll
+                        && call.params["reply_markup"]["inline_keyboard"]
+                            .as_array()
+                            .is_some_and(|buttons| buttons.len() == 3)
+                })
+            {
+                return call.params["reply_markup"]["inline_keyboard"].clone();
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("The poll was not updated with every submission");
+    let button = |name: &str| {
+        buttons
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|row| row[0]["text"] == name)
+            .map(|row| row[0]["callback_data"].clone())
+            .unwrap()
+    };
+
+    // Votes are answered only to the voter
+    telegram.vote(BOB, poll, &button("Bob"));
+    telegram.answer("Cannot vote for yourself").await;
+    telegram.vote(BOB, poll, &button("Alice"));
+    telegram.answer("Successfully voted for Alice").await;
+
+    // The poll is still known, so /poll points at it without touching the message
+    telegram.reply(ALICE, (BOT, question), "/poll", json!([]));
+    let (_, pointer) = telegram
+        .wait_for("sendMessage", "You can vote via this poll")
+        .await;
+    assert_eq!(pointer["reply_parameters"]["message_id"], poll);
+    assert!(!telegram
+        .api
+        .calls
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|call| call.method == "setMessageReaction"));
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use chrono::{TimeDelta, TimeZone, Utc};
+use common::{clock, settle, start, Server, SUBMISSION};
+use serenity::model::prelude::ChannelId;
+
+#[tokio::test]
+async fn contest_thread_is_open_for_the_contest_window() {
+    // The 15th of March 2026 is a Sunday, the contest runs from 02:30 for 90 minutes
+    let _guard = start(Utc.with_ymd_and_hms(2026, 3, 14, 12, 0, 0).unwrap()).await;
+    let shared = Server::new(&["Alice", "Bob"]).await.into_shared();
+    let [alice, bob] = shared.users[..] else {
+        unreachable!()
+    };
+    let task = shared.schedule_weekly_contest();
+    settle(1).await;
+
+    clock().set(Utc.with_ymd_and_hms(2026, 3, 15, 2, 29, 0).unwrap());
+    settle(1).await;
+    assert!(shared.guild().await["weekly_id"].is_null());
+
+    clock().advance(TimeDelta::minutes(1));
+    settle(1).await;
+    let weekly: ChannelId =
+        serde_json::from_value(shared.guild().await["weekly_id"].clone()).unwrap();
+    assert!(shared
+        .contents(shared.channel_id)
+        .last()
+        .unwrap()
+        .contains("Weekly Contest"));
+    // 15 March 2026 is in ISO week 11, threads are numbered from week 0
+    assert!(shared.thread_names().contains(&String::from("Week 10")));
+    for _ in 0..4 {
+        shared.send(alice, weekly, SUBMISSION).await;
+    }
+    shared.send(bob, weekly, SUBMISSION).await;
+    assert_eq!(shared.user(alice).await["weekly_submissions"], 4);
+    // 1 for each of the first three questions and 4 for finishing first
+    assert_eq!(shared.user(alice).await["score"], 7);
+
+    clock().advance(TimeDelta::minutes(89));
+    settle(1).await;
+    assert!(!shared.guild().await["weekly_id"].is_null());
+
+    clock().advance(TimeDelta::minutes(1));
+    settle(1).await;
+    assert!(shared.guild().await["weekly_id"].is_null());
+    let results = shared.contents(weekly).pop().unwrap();
+    assert!(results.contains("Weekly contest just ended"));
+    assert!(results.contains(&format!("1. <@{alice}> completed **4** questions")));
+    assert!(results.contains(&format!("2. <@{bob}> completed **1** question")));
+    assert_eq!(shared.user(alice).await["weekly_submissions"], 0);
+
+    // Nothing happens until the following Sunday
+    clock().set(Utc.with_ymd_and_hms(2026, 3, 22, 2, 29, 0).unwrap());
+    settle(1).await;
+    assert!(shared.guild().await["weekly_id"].is_null());
+    task.abort();
+}